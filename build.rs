@@ -0,0 +1,18 @@
+//! rustyroad 0.1 asks to link `libpq` by its Windows file name, which no
+//! other platform has. The Postgres client comes from sqlx, so an empty
+//! archive of that name is enough to satisfy the linker elsewhere
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
+        return;
+    }
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("cargo sets OUT_DIR"));
+    fs::write(out_dir.join("liblibpq.a"), b"!<arch>\n").expect("failed to write liblibpq.a");
+    println!("cargo:rustc-link-search=native={}", out_dir.display());
+}
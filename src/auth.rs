@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// How users of a generated project log in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthScheme {
+    /// A login page and a dashboard behind a session, the historical default
+    #[default]
+    Session,
+    /// Token issuing JSON handlers signing JSON Web Tokens, for APIs
+    Jwt,
+    /// No users, login page or dashboard
+    None,
+}
+
+impl AuthScheme {
+    /// Whether the user models are generated
+    /// JWT handlers check credentials against the users table too
+    pub fn has_user_models(self) -> bool {
+        matches!(self, AuthScheme::Session | AuthScheme::Jwt)
+    }
+
+    /// Whether the login page and the dashboard are generated
+    pub fn has_login_pages(self) -> bool {
+        self == AuthScheme::Session
+    }
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// The CI service a generated project gets a workflow for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CiProvider {
+    /// GitHub Actions, `.github/workflows/ci.yml`
+    Github,
+}
@@ -0,0 +1,101 @@
+//! The CockroachDB dialect of the initial SQL
+//! CockroachDB speaks the Postgres protocol, so its projects connect through
+//! the Postgres driver and are `DatabaseType::Postgres` everywhere else, but
+//! it rejects part of the SQL the upstream loader generates for Postgres
+
+use tracing::{debug, warn};
+
+use crate::migrate::created_table;
+use crate::timestamps::with_on_update;
+
+/// The database type stored in rustyroad.toml for CockroachDB projects
+pub const DATABASE_TYPE: &str = "cockroachdb";
+
+/// Rewrites the Postgres statements of a new project for CockroachDB
+/// UUID defaults use the built-in `gen_random_uuid()`, extensions are
+/// dropped since CockroachDB ships the functions they provide, and the
+/// PL/pgSQL triggers keeping `updated_at` current are replaced by
+/// `ON UPDATE now()` on the column
+pub fn to_cockroachdb(statements: Vec<String>) -> Vec<String> {
+    statements
+        .into_iter()
+        .filter(|statement| {
+            let upper = statement.trim_start().to_uppercase();
+            if upper.starts_with("CREATE EXTENSION") {
+                debug!("Skipping for CockroachDB: {statement}");
+                return false;
+            }
+            // The timestamp triggers, replaced by `ON UPDATE now()` below
+            if upper.starts_with("DROP TRIGGER") || upper.contains("SET_UPDATED_AT") {
+                debug!("Skipping for CockroachDB: {statement}");
+                return false;
+            }
+            if upper.starts_with("CREATE TRIGGER") || upper.contains("LANGUAGE PLPGSQL") {
+                warn!("CockroachDB doesn't support PL/pgSQL triggers, skipping: {statement}");
+                return false;
+            }
+            true
+        })
+        .map(|statement| {
+            let statement = statement.replace("uuid_generate_v4()", "gen_random_uuid()");
+            if created_table(&statement).is_some() {
+                with_on_update(&statement, "now()")
+            } else {
+                statement
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rustyroad::database::DatabaseType;
+
+    use super::*;
+    use crate::timestamps::auto_update_timestamps;
+
+    fn users() -> Vec<String> {
+        auto_update_timestamps(
+            vec![
+                "CREATE EXTENSION IF NOT EXISTS \"uuid-ossp\"".to_string(),
+                "CREATE TABLE users (id UUID PRIMARY KEY DEFAULT uuid_generate_v4(), \
+                 updated_at TIMESTAMP NOT NULL DEFAULT now())"
+                    .to_string(),
+            ],
+            &DatabaseType::Postgres,
+        )
+    }
+
+    #[test]
+    fn drops_every_trigger_statement() {
+        let statements = to_cockroachdb(users());
+        for statement in &statements {
+            let upper = statement.to_uppercase();
+            assert!(!upper.contains("TRIGGER"), "{statement}");
+            assert!(!upper.contains("PLPGSQL"), "{statement}");
+            assert!(!upper.contains("EXTENSION"), "{statement}");
+        }
+        assert_eq!(statements.len(), 1);
+    }
+
+    #[test]
+    fn keeps_updated_at_current_on_update() {
+        let statements = to_cockroachdb(users());
+        assert_eq!(
+            statements,
+            [
+                "CREATE TABLE users (id UUID PRIMARY KEY DEFAULT gen_random_uuid(), \
+              updated_at TIMESTAMP NOT NULL DEFAULT now() ON UPDATE now())"
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_other_statements() {
+        let statements = to_cockroachdb(vec![
+            "CREATE INDEX posts_title ON posts (title)".to_string(),
+            "INSERT INTO posts (title) VALUES ('hello')".to_string(),
+        ]);
+        assert_eq!(statements.len(), 2);
+    }
+}
@@ -0,0 +1,255 @@
+//! User defaults for `rustyroad new`
+//! Read from `rustyroad/config.toml` in the user config directory, then from
+//! `.rustyroad/config.toml` in the directory `new` runs in. The local file
+//! wins over the global one and command line options win over both
+//!
+//! ```toml
+//! [new]
+//! framework = "axum"
+//! css = "bootstrap"
+//! auth = "jwt"
+//! author = "Jane Doe <jane@example.com>"
+//!
+//! [database]
+//! type = "postgres"
+//! host = "db.internal"
+//! port = "5433"
+//! user = "app"
+//! ```
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AuthScheme;
+use crate::credentials::PartialDatabase;
+use crate::css::CssFramework;
+use crate::database::parse_port;
+use crate::framework::Framework;
+use crate::rustyroad_toml::parse_database_type;
+use crate::ConfigError;
+
+/// Name of the config file in the user config directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// The project-local config file, relative to the current directory
+pub const LOCAL_CONFIG: &str = ".rustyroad/config.toml";
+
+/// The keys `rustyroad config set` accepts
+pub const KEYS: [&str; 8] = [
+    "new.framework",
+    "new.css",
+    "new.auth",
+    "new.author",
+    "database.type",
+    "database.host",
+    "database.port",
+    "database.user",
+];
+
+/// The defaults `rustyroad new` starts from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub new: NewDefaults,
+    pub database: DatabaseDefaults,
+}
+
+/// The `[new]` section, the generator choices
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NewDefaults {
+    pub framework: Option<Framework>,
+    pub css: Option<CssFramework>,
+    pub auth: Option<AuthScheme>,
+    /// Written to the `authors` of the generated Cargo.toml
+    pub author: Option<String>,
+}
+
+/// The `[database]` section, the server new projects usually live on
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseDefaults {
+    /// Any type `--db-type` accepts
+    #[serde(rename = "type")]
+    pub database_type: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<String>,
+    pub user: Option<String>,
+}
+
+impl Config {
+    /// Reads the global config and the local one in `current_dir`, missing
+    /// files count as empty
+    pub fn load(current_dir: &Path) -> Result<Self, ConfigError> {
+        let global = match global_config_path() {
+            Some(path) => read(&path)?,
+            None => Config::default(),
+        };
+        let local = read(&local_config_path(current_dir))?;
+        Ok(local.or(global))
+    }
+
+    /// Fills every value missing in `self` from `other`
+    fn or(self, other: Config) -> Config {
+        Config {
+            new: NewDefaults {
+                framework: self.new.framework.or(other.new.framework),
+                css: self.new.css.or(other.new.css),
+                auth: self.new.auth.or(other.new.auth),
+                author: self.new.author.or(other.new.author),
+            },
+            database: DatabaseDefaults {
+                database_type: self.database.database_type.or(other.database.database_type),
+                host: self.database.host.or(other.database.host),
+                port: self.database.port.or(other.database.port),
+                user: self.database.user.or(other.database.user),
+            },
+        }
+    }
+
+    /// The database settings, to fill what the command line and the
+    /// environment leave open
+    pub fn database_defaults(&self) -> PartialDatabase {
+        PartialDatabase {
+            username: self.database.user.clone(),
+            host: self.database.host.clone(),
+            port: self.database.port.clone(),
+            database_type: self
+                .database
+                .database_type
+                .as_deref()
+                .and_then(parse_database_type),
+            ..PartialDatabase::default()
+        }
+    }
+
+    /// The value of `key` such as `new.framework`, `None` when it isn't set
+    pub fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        let table = toml::Value::try_from(self).map_err(|why| ConfigError::InvalidValue {
+            key: key.to_string(),
+            reason: why.to_string(),
+        })?;
+        let (section, name) = split_key(key)?;
+
+        Ok(table
+            .get(section)
+            .and_then(|section| section.get(name))
+            .map(|value| match value {
+                toml::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            }))
+    }
+}
+
+/// `rustyroad` in the user config directory, `$XDG_CONFIG_HOME` or
+/// `~/.config`
+pub fn global_config_dir() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_dir.join("rustyroad"))
+}
+
+/// The global config file, `None` when there is no home directory
+pub fn global_config_path() -> Option<PathBuf> {
+    global_config_dir().map(|dir| dir.join(CONFIG_FILE))
+}
+
+/// The local config file of `dir`
+pub fn local_config_path(dir: &Path) -> PathBuf {
+    dir.join(LOCAL_CONFIG)
+}
+
+/// Sets `key` to `value` in the config file at `path`, creating it if needed
+/// The value is checked before the file is written, so a typo in a
+/// framework name is reported instead of breaking the next `rustyroad new`
+pub fn set(path: &Path, key: &str, value: &str) -> Result<(), ConfigError> {
+    let (section, name) = split_key(key)?;
+    let invalid = |reason: String| ConfigError::InvalidValue {
+        key: key.to_string(),
+        reason,
+    };
+
+    match key {
+        "database.type" if parse_database_type(value).is_none() => {
+            return Err(invalid("unknown database type".to_string()))
+        }
+        "database.port" => {
+            parse_port(value).map_err(|why| invalid(why.to_string()))?;
+        }
+        _ => {}
+    }
+
+    let mut table = read_table(path)?;
+    let entry = table
+        .entry(section)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    let toml::Value::Table(entry) = entry else {
+        return Err(invalid(format!("`{section}` is not a table")));
+    };
+    entry.insert(name.to_string(), toml::Value::String(value.to_string()));
+
+    // Enum values such as the framework are checked by parsing the result
+    let contents = toml::to_string(&table).map_err(|why| invalid(why.to_string()))?;
+    toml::from_str::<Config>(&contents).map_err(|why| invalid(why.message().to_string()))?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|source| ConfigError::FileWrite {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+    }
+    fs::write(path, contents).map_err(|source| ConfigError::FileWrite {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Splits `key` into its section and name, rejecting unknown keys
+fn split_key(key: &str) -> Result<(&str, &str), ConfigError> {
+    match key.split_once('.') {
+        Some(parts) if KEYS.contains(&key) => Ok(parts),
+        _ => Err(ConfigError::UnknownKey {
+            key: key.to_string(),
+        }),
+    }
+}
+
+/// Parses the config file at `path`, a missing file is an empty config
+fn read(path: &Path) -> Result<Config, ConfigError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(source) => {
+            return Err(ConfigError::FileRead {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    toml::from_str(&contents).map_err(|source| ConfigError::Invalid {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The config file at `path` as a plain table, keeping what it contains
+fn read_table(path: &Path) -> Result<toml::Table, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => toml::from_str(&contents).map_err(|source| ConfigError::Invalid {
+            path: path.to_path_buf(),
+            source,
+        }),
+        Err(why) if why.kind() == io::ErrorKind::NotFound => Ok(toml::Table::new()),
+        Err(source) => Err(ConfigError::FileRead {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
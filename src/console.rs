@@ -0,0 +1,205 @@
+//! An interactive SQL prompt on the database of an existing project for
+//! `rustyroad console`
+//! Statements end with `;` and may span several lines, result sets are
+//! printed as a table. `\q` or the end of input leaves the prompt, so SQL
+//! can be piped in as well
+
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use futures_util::TryStreamExt;
+use sqlx::any::AnyRow;
+use sqlx::{Any, AnyConnection, Column, Connection, Either, Executor, Row};
+use tracing::{error, warn};
+
+use crate::migrate::{load_project, open};
+use crate::rustyroad_toml::Environment;
+use crate::ConsoleError;
+
+/// The prompt of a new statement
+const PROMPT: &str = "sql> ";
+
+/// The prompt of the following lines of a statement
+const CONTINUATION: &str = "  -> ";
+
+/// What leaves the prompt
+const QUIT_COMMANDS: [&str; 3] = ["\\q", "exit", "quit"];
+
+/// How NULL is shown in result sets
+const NULL: &str = "NULL";
+
+/// Opens the prompt on the database of `environment` of the project
+/// containing `current_dir`
+/// A failing statement is reported and the prompt continues
+pub async fn run(current_dir: &Path, environment: Environment) -> Result<(), ConsoleError> {
+    let (_, database_data, tls, postgres) = load_project(current_dir, environment)?;
+    let mut connection = open(&database_data, &tls, &postgres).await?;
+
+    let interactive = io::stdin().is_terminal();
+    if interactive {
+        println!(
+            "Connected to {}, end statements with `;` and leave with \\q",
+            database_data.name
+        );
+    }
+
+    let mut statement = String::new();
+    loop {
+        if interactive {
+            print!(
+                "{}",
+                if statement.is_empty() {
+                    PROMPT
+                } else {
+                    CONTINUATION
+                }
+            );
+            io::stdout()
+                .flush()
+                .map_err(|source| ConsoleError::Terminal { source })?;
+        }
+
+        let mut line = String::new();
+        let read = io::stdin()
+            .read_line(&mut line)
+            .map_err(|source| ConsoleError::Terminal { source })?;
+        if read == 0 {
+            break;
+        }
+
+        let trimmed = line.trim();
+        if statement.is_empty() {
+            if QUIT_COMMANDS.contains(&trimmed) {
+                break;
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+        }
+        statement.push_str(&line);
+
+        if trimmed.ends_with(';') {
+            run_statement(&mut connection, &statement).await;
+            statement.clear();
+        }
+    }
+
+    // Piped input may end without the last `;`
+    if !statement.trim().is_empty() {
+        run_statement(&mut connection, &statement).await;
+    }
+    if interactive {
+        println!();
+    }
+
+    if let Err(why) = connection.close().await {
+        warn!("Failed to close the connection: {why}");
+    }
+    Ok(())
+}
+
+/// Runs `sql` and prints its results, or the error it failed with
+async fn run_statement(connection: &mut AnyConnection, sql: &str) {
+    match execute(connection, sql).await {
+        Ok(output) => print!("{output}"),
+        Err(why) => error!("{why}"),
+    }
+}
+
+/// Runs the statements in `sql`, returning a table for each result set and
+/// the affected row count of every other statement
+async fn execute(connection: &mut AnyConnection, sql: &str) -> Result<String, sqlx::Error> {
+    let mut output = String::new();
+    let mut rows = Vec::new();
+
+    let mut results = connection.fetch_many(sql);
+    while let Some(result) = results.try_next().await? {
+        match result {
+            Either::Left(done) if rows.is_empty() => {
+                output.push_str(&format!("{} row(s) affected\n", done.rows_affected()));
+            }
+            // A statement returning rows is done
+            Either::Left(_) => output.push_str(&format_table(&std::mem::take(&mut rows))),
+            Either::Right(row) => rows.push(row),
+        }
+    }
+    if !rows.is_empty() {
+        output.push_str(&format_table(&rows));
+    }
+
+    Ok(output)
+}
+
+/// Lays out `rows` as a table with a header of their column names
+fn format_table(rows: &[AnyRow]) -> String {
+    let Some(first) = rows.first() else {
+        return String::new();
+    };
+
+    let header: Vec<String> = first
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect();
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| (0..header.len()).map(|index| cell(row, index)).collect())
+        .collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|name| name.chars().count()).collect();
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |values: &[String]| {
+        let padded: Vec<String> = values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!(" {value:<width$} "))
+            .collect();
+        padded.join("|").trim_end().to_string() + "\n"
+    };
+
+    let mut table = line(&header);
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(width + 2)).collect();
+    table.push_str(&separator.join("+"));
+    table.push('\n');
+    for row in &cells {
+        table.push_str(&line(row));
+    }
+    table.push_str(&format!("({} row(s))\n", rows.len()));
+    table
+}
+
+/// The value of column `index` as text, trying the types the Any driver
+/// decodes in turn
+fn cell(row: &AnyRow, index: usize) -> String {
+    decode::<String>(row, index)
+        .or_else(|| decode::<i64>(row, index))
+        .or_else(|| decode::<f64>(row, index))
+        .or_else(|| decode::<bool>(row, index))
+        .or_else(|| {
+            row.try_get::<Option<Vec<u8>>, _>(index)
+                .ok()
+                .map(|value| match value {
+                    Some(bytes) => format!("<{} bytes>", bytes.len()),
+                    None => NULL.to_string(),
+                })
+        })
+        .unwrap_or_else(|| "?".to_string())
+}
+
+/// The value of column `index` decoded as `T`, `None` if it isn't a `T`
+fn decode<'r, T>(row: &'r AnyRow, index: usize) -> Option<String>
+where
+    T: sqlx::Decode<'r, Any> + sqlx::Type<Any> + ToString,
+{
+    row.try_get::<Option<T>, _>(index)
+        .ok()
+        .map(|value| match value {
+            Some(value) => value.to_string(),
+            None => NULL.to_string(),
+        })
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// The CSS framework the templates of a generated project are styled with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CssFramework {
+    /// Tailwind built through PostCSS, the historical default
+    #[default]
+    Tailwind,
+    /// Bootstrap loaded from its CDN, no build step
+    Bootstrap,
+    /// A plain stylesheet, no framework and no build step
+    None,
+}
+
+impl CssFramework {
+    /// The classes of the heading in generated templates
+    pub fn heading_class(self) -> &'static str {
+        match self {
+            CssFramework::Tailwind => "text-3xl font-bold",
+            CssFramework::Bootstrap => "display-6",
+            CssFramework::None => "title",
+        }
+    }
+
+    /// The classes of a paragraph in generated templates
+    pub fn text_class(self) -> &'static str {
+        match self {
+            CssFramework::Tailwind => "mt-4 text-gray-600",
+            CssFramework::Bootstrap => "lead text-muted",
+            CssFramework::None => "muted",
+        }
+    }
+}
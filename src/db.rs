@@ -0,0 +1,271 @@
+//! Manages the database of an existing project for `rustyroad db`
+
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+
+use rustyroad::database::{Database, DatabaseType};
+use sqlx::{Connection, Executor};
+use tracing::{info, warn};
+
+use crate::database::{
+    connect, create_database, is_sqlite_memory, quote_identifier, uses_unix_socket, Charset,
+    ConnectionTarget, PostgresSettings, Tls, DEFAULT_CONNECT_TIMEOUT,
+};
+use crate::initial_sql::load_initial_sql;
+use crate::layout::project_at;
+use crate::migrate::{self, load_project, open, INITIAL_MIGRATION};
+use crate::prompt::confirm;
+use crate::rustyroad_toml::{self, Environment};
+use crate::{DbError, MigrateError};
+
+/// Host names that only ever point at the developer's own machine
+const LOCAL_HOSTS: [&str; 4] = ["localhost", "127.0.0.1", "::1", "host.docker.internal"];
+
+/// Whether `database_data` looks like a production database
+/// That is any server off the local machine, or a database or host whose
+/// name mentions `prod`. Unix sockets are always local
+pub fn looks_like_production(database_data: &Database) -> bool {
+    let mentions_production = |value: &str| value.to_lowercase().contains("prod");
+    if mentions_production(&database_data.name) || mentions_production(&database_data.host) {
+        return true;
+    }
+
+    match database_data.database_type {
+        DatabaseType::Sqlite => false,
+        _ if uses_unix_socket(database_data) => false,
+        _ => !LOCAL_HOSTS.contains(&database_data.host.as_str()),
+    }
+}
+
+/// Drops the database of `environment` of the project containing
+/// `current_dir` and creates it again with the initial schema and every
+/// migration applied
+/// The production environment and databases that look like production
+/// ones are only reset when `confirmed` or confirmed at the prompt
+/// Returns the applied migrations
+pub async fn reset(
+    current_dir: &Path,
+    environment: Environment,
+    confirmed: bool,
+) -> Result<Vec<String>, DbError> {
+    let (root, database_data, tls, postgres) = load_project(current_dir, environment)?;
+    let target = target_name(&database_data);
+    let production =
+        environment == Environment::Production || looks_like_production(&database_data);
+    // A closed stdin counts as no
+    if production
+        && !confirmed
+        && !confirm(&format!(
+            "{target} looks like a production database, reset it?"
+        ))
+        .unwrap_or(false)
+    {
+        return Err(DbError::LooksLikeProduction { target });
+    }
+
+    drop_database(&database_data, &tls).await?;
+    info!("Dropped {target}");
+
+    let charset = rustyroad_toml::read(&root)?.database.charset;
+    create_empty_database(&database_data, &tls, &charset).await?;
+
+    // Projects from before the initial migration existed only have the
+    // schema the generator creates
+    let migrations = migrate::list_migrations(&migrate::migrations_dir(&root)?)?;
+    let has_initial_migration = migrations
+        .iter()
+        .any(|migration| migration.version.ends_with(INITIAL_MIGRATION));
+    if !has_initial_migration {
+        run_initial_sql(&root, &database_data, &tls, &postgres).await?;
+    }
+
+    let applied = migrate::migrate_up(current_dir, environment).await?;
+    info!("Recreated {target}");
+    Ok(applied)
+}
+
+/// Creates the database of `environment` of the project containing
+/// `current_dir` unless it exists, without tables
+/// Run `rustyroad migrate` afterwards to create the schema
+/// Returns how the database is named in messages
+pub async fn create(current_dir: &Path, environment: Environment) -> Result<String, DbError> {
+    let (root, database_data, tls, _) = load_project(current_dir, environment)?;
+    let charset = rustyroad_toml::read(&root)?.database.charset;
+
+    create_empty_database(&database_data, &tls, &charset).await?;
+    Ok(target_name(&database_data))
+}
+
+/// Drops the database of `environment` of the project containing
+/// `current_dir`, which has to be `confirmed` with `--yes`, there is no
+/// prompt a stray Enter could answer
+/// The production environment and databases that look like production
+/// ones are always refused
+/// Returns how the database is named in messages
+pub async fn drop(
+    current_dir: &Path,
+    environment: Environment,
+    confirmed: bool,
+) -> Result<String, DbError> {
+    let (_, database_data, tls, _) = load_project(current_dir, environment)?;
+    let target = target_name(&database_data);
+    if environment == Environment::Production || looks_like_production(&database_data) {
+        return Err(DbError::DropProduction { target });
+    }
+    if !confirmed {
+        return Err(DbError::DropNotConfirmed { target });
+    }
+
+    drop_database(&database_data, &tls).await?;
+    Ok(target)
+}
+
+/// Creates the project database unless it exists, for SQLite an empty file
+/// along with its directory
+async fn create_empty_database(
+    database_data: &Database,
+    tls: &Tls,
+    charset: &Charset,
+) -> Result<(), DbError> {
+    match database_data.database_type {
+        DatabaseType::Sqlite => {
+            if is_sqlite_memory(database_data) {
+                return Ok(());
+            }
+            let path = Path::new(&database_data.name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|source| DbError::FileWrite {
+                    path: parent.to_path_buf(),
+                    source,
+                })?;
+            }
+            // An empty file is a valid SQLite database
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map(|_| ())
+                .map_err(|source| DbError::FileWrite {
+                    path: path.to_path_buf(),
+                    source,
+                })
+        }
+        _ => create_database(database_data, tls, charset, DEFAULT_CONNECT_TIMEOUT)
+            .await
+            .map_err(|source| DbError::Create {
+                name: database_data.name.clone(),
+                source,
+            }),
+    }
+}
+
+/// Drops the project database, for SQLite by deleting its file
+/// A database that doesn't exist is not an error
+async fn drop_database(database_data: &Database, tls: &Tls) -> Result<(), DbError> {
+    let statement = match database_data.database_type {
+        DatabaseType::Sqlite => {
+            if is_sqlite_memory(database_data) {
+                return Ok(());
+            }
+            return match fs::remove_file(&database_data.name) {
+                Ok(()) => Ok(()),
+                Err(why) if why.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(source) => Err(DbError::FileWrite {
+                    path: database_data.name.clone().into(),
+                    source,
+                }),
+            };
+        }
+        DatabaseType::Postgres | DatabaseType::Mysql => format!(
+            "DROP DATABASE IF EXISTS {}",
+            quote_identifier(&database_data.database_type, &database_data.name)
+        ),
+        DatabaseType::Mongo => return Err(MigrateError::UnsupportedDatabase.into()),
+    };
+
+    let mut connection = connect(database_data, ConnectionTarget::Admin, tls)
+        .await
+        .map_err(|source| DbError::DatabaseConnection { source })?;
+    connection
+        .execute(statement.as_str())
+        .await
+        .map_err(|source| DbError::Drop {
+            name: database_data.name.clone(),
+            source,
+        })?;
+    if let Err(why) = connection.close().await {
+        warn!("Failed to close the connection: {why}");
+    }
+    Ok(())
+}
+
+/// Runs the SQL a new project is set up with against the recreated database
+async fn run_initial_sql(
+    root: &Path,
+    database_data: &Database,
+    tls: &Tls,
+    postgres: &PostgresSettings,
+) -> Result<(), DbError> {
+    let project = project_at(root);
+    let tables = rustyroad_toml::read(root)?.database.tables;
+    let statements = load_initial_sql(&project, database_data, postgres, &tables.prefix)
+        .await
+        .map_err(|source| DbError::SqlGeneration { source })?;
+
+    let mut connection = open(database_data, tls, postgres).await?;
+    let mut transaction = connection
+        .begin()
+        .await
+        .map_err(|source| DbError::DatabaseConnection { source })?;
+    for statement in &statements {
+        (&mut *transaction)
+            .execute(statement.sql.as_str())
+            .await
+            .map_err(|source| DbError::SqlExecution {
+                label: statement.label.clone(),
+                source,
+            })?;
+    }
+    transaction
+        .commit()
+        .await
+        .map_err(|source| DbError::DatabaseConnection { source })
+}
+
+/// How a database is named in messages, its file or `host/name`
+fn target_name(database_data: &Database) -> String {
+    match database_data.database_type {
+        DatabaseType::Sqlite => database_data.name.clone(),
+        _ => format!("{}/{}", database_data.host, database_data.name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn drop_refuses_without_confirmation() {
+        let root = std::env::temp_dir().join(format!("rustyroad-db-drop-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("rustyroad.toml"),
+            r#"[database]
+database_name = "blog.db"
+database_user = ""
+database_password = ""
+database_host = ""
+database_port = ""
+database_type = "sqlite"
+"#,
+        )
+        .unwrap();
+        fs::write(root.join("blog.db"), "").unwrap();
+
+        let result = drop(&root, Environment::Development, false).await;
+
+        assert!(matches!(result, Err(DbError::DropNotConfirmed { .. })));
+        assert!(root.join("blog.db").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
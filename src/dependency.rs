@@ -0,0 +1,95 @@
+//! Extra Cargo dependencies of a generated project, given as
+//! `name@version` or `name@version:feature,feature` on the command line
+//! A dependency the generated Cargo.toml already declares, such as
+//! `actix-web` or `sqlx`, is pinned to the given version instead
+
+use std::fmt;
+
+use crate::CreateProjectError;
+
+/// A dependency added to the generated Cargo.toml
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    pub version: String,
+    /// Features enabled on top of the ones the generator enables
+    pub features: Vec<String>,
+}
+
+impl Dependency {
+    /// Parses `name@version` with an optional `:feature,feature` suffix
+    pub fn parse(spec: &str) -> Result<Self, CreateProjectError> {
+        let invalid = |reason| CreateProjectError::InvalidDependency {
+            spec: spec.to_string(),
+            reason,
+        };
+
+        let (requirement, features) = match spec.split_once(':') {
+            Some((requirement, features)) => (requirement, features.split(',').collect()),
+            None => (spec, Vec::new()),
+        };
+        let (name, version) = requirement
+            .split_once('@')
+            .ok_or_else(|| invalid("expected `name@version`"))?;
+
+        if name.is_empty() || !name.chars().all(is_name_char) {
+            return Err(invalid(
+                "the name may only contain letters, digits, `-` and `_`",
+            ));
+        }
+        if version.trim().is_empty() {
+            return Err(invalid("the version is empty"));
+        }
+        if features
+            .iter()
+            .any(|feature: &&str| feature.is_empty() || !feature.chars().all(is_feature_char))
+        {
+            return Err(invalid(
+                "features may only contain letters, digits, `-`, `_`, `/` and `+`",
+            ));
+        }
+
+        Ok(Dependency {
+            name: name.to_string(),
+            version: version.trim().to_string(),
+            features: features.into_iter().map(str::to_string).collect(),
+        })
+    }
+
+    /// The line declaring the dependency in `[dependencies]`
+    pub fn to_line(&self) -> String {
+        let version = toml::Value::String(self.version.clone());
+        if self.features.is_empty() {
+            return format!("{} = {version}", self.name);
+        }
+
+        let features = toml::Value::Array(
+            self.features
+                .iter()
+                .map(|feature| toml::Value::String(feature.clone()))
+                .collect(),
+        );
+        format!(
+            "{} = {{ version = {version}, features = {features} }}",
+            self.name
+        )
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)?;
+        if !self.features.is_empty() {
+            write!(f, ":{}", self.features.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+fn is_feature_char(c: char) -> bool {
+    is_name_char(c) || c == '/' || c == '+'
+}
@@ -0,0 +1,249 @@
+//! Removes what a generator made for `rustyroad destroy`
+//! The generators record the files they create and the lines they add to
+//! existing files such as routes/mod.rs in the manifest. Destroying the
+//! resource deletes those files and takes the lines out again
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use crate::filesystem::FileSystem;
+use crate::manifest::{Manifest, MANIFEST_PATH};
+use crate::rustyroad_toml::find_project_root_on;
+use crate::GenerateError;
+
+/// Removes the resource `name` that `generator` made in the project on `fs`
+/// containing `current_dir`
+/// Refuses to delete files edited since they were generated unless `force`
+/// Returns the paths of every file it deleted or changed
+pub fn destroy(
+    fs: &dyn FileSystem,
+    current_dir: &Path,
+    generator: &'static str,
+    name: &str,
+    force: bool,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    let root = find_project_root_on(fs, current_dir)?;
+    let manifest_path = root.join(MANIFEST_PATH);
+    let mut manifest = Manifest::load(fs, &root).map_err(|source| GenerateError::FileRead {
+        path: manifest_path.clone(),
+        source,
+    })?;
+
+    let key = Manifest::generated_key(generator, name);
+    let generated = manifest
+        .generated
+        .remove(&key)
+        .ok_or_else(|| GenerateError::NotGenerated {
+            generator,
+            name: name.to_string(),
+        })?;
+
+    // Every file is checked before the first one is deleted
+    if !force {
+        let mut edited = Vec::new();
+        for relative in &generated.files {
+            let path = root.join(relative);
+            match fs.read(&path) {
+                Ok(contents) if manifest.is_edited(relative, &contents) => edited.push(path),
+                Ok(_) => {}
+                Err(why) if why.kind() == io::ErrorKind::NotFound => {}
+                Err(source) => return Err(GenerateError::FileRead { path, source }),
+            }
+        }
+        if !edited.is_empty() {
+            return Err(GenerateError::EditedSinceGenerated { paths: edited });
+        }
+    }
+
+    let mut changed = Vec::new();
+    for relative in &generated.files {
+        let path = root.join(relative);
+        match fs.remove_file(&path) {
+            Ok(()) => {
+                remove_empty_parents(fs, &root, &path);
+                changed.push(path);
+            }
+            Err(why) if why.kind() == io::ErrorKind::NotFound => {}
+            Err(source) => return Err(GenerateError::FileWrite { path, source }),
+        }
+        manifest.files.remove(relative);
+    }
+    for (relative, lines) in &generated.lines {
+        let path = root.join(relative);
+        if remove_lines(fs, &path, lines)? {
+            changed.push(path);
+        }
+    }
+
+    manifest
+        .save(fs, &root)
+        .map_err(GenerateError::file_write(manifest_path))?;
+
+    info!("Destroyed {generator} {name}");
+    Ok(changed)
+}
+
+/// Removes the first occurrence of each of `lines` from the file at `path`
+/// Returns whether the file changed, a missing file is left alone
+fn remove_lines(fs: &dyn FileSystem, path: &Path, lines: &[String]) -> Result<bool, GenerateError> {
+    let contents = match fs.read_to_string(path) {
+        Ok(contents) => contents,
+        Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(source) => {
+            return Err(GenerateError::FileRead {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    let mut remaining: Vec<&str> = contents.lines().collect();
+    let before = remaining.len();
+    for line in lines {
+        if let Some(index) = remaining.iter().position(|existing| existing == line) {
+            remaining.remove(index);
+        }
+    }
+    if remaining.len() == before {
+        return Ok(false);
+    }
+
+    let mut updated = remaining.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    fs.write(path, updated.as_bytes())
+        .map_err(GenerateError::file_write(path))?;
+    Ok(true)
+}
+
+/// Removes the directories above `path` that the deletion left empty, such
+/// as the migration directory, stopping at `root`
+fn remove_empty_parents(fs: &dyn FileSystem, root: &Path, path: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        // Only succeeds for empty directories
+        if current == root || fs.remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+    use crate::manifest::Generated;
+
+    const CONTROLLER: &str = "pub async fn index() {}\n";
+    const ROUTES_MOD: &str = "pub mod index;\npub use index::*;\npub mod post;\npub use post::*;\n";
+
+    /// A project on a memory filesystem with a generated controller `Post`
+    fn project() -> (MemoryFileSystem, PathBuf) {
+        let root = PathBuf::from("/blog");
+        let fs = MemoryFileSystem::new();
+        fs.create_dir(&root.join("src/controllers")).unwrap();
+        fs.create_dir(&root.join("src/routes")).unwrap();
+        fs.write(&root.join("rustyroad.toml"), b"").unwrap();
+        fs.write(&root.join("src/controllers/post.rs"), CONTROLLER.as_bytes())
+            .unwrap();
+        fs.write(&root.join("src/routes/mod.rs"), ROUTES_MOD.as_bytes())
+            .unwrap();
+
+        let mut manifest = Manifest::default();
+        manifest.record("src/controllers/post.rs", CONTROLLER.as_bytes());
+        manifest.generated.insert(
+            Manifest::generated_key("controller", "Post"),
+            Generated {
+                files: vec!["src/controllers/post.rs".to_string()],
+                lines: [(
+                    "src/routes/mod.rs".to_string(),
+                    vec!["pub mod post;".to_string(), "pub use post::*;".to_string()],
+                )]
+                .into(),
+            },
+        );
+        manifest.save(&fs, &root).unwrap();
+        (fs, root)
+    }
+
+    #[test]
+    fn removes_the_files_and_registration_lines() {
+        let (fs, root) = project();
+
+        let changed = destroy(&fs, &root, "controller", "Post", false).unwrap();
+
+        assert_eq!(
+            changed,
+            [
+                root.join("src/controllers/post.rs"),
+                root.join("src/routes/mod.rs")
+            ]
+        );
+        assert!(!fs.exists(&root.join("src/controllers/post.rs")));
+        assert!(!fs.exists(&root.join("src/controllers")));
+        assert_eq!(
+            fs.contents(root.join("src/routes/mod.rs")).as_deref(),
+            Some("pub mod index;\npub use index::*;\n")
+        );
+        let manifest = Manifest::load(&fs, &root).unwrap();
+        assert!(manifest.generated.is_empty());
+        assert!(manifest.files.is_empty());
+    }
+
+    #[test]
+    fn refuses_to_delete_edited_files() {
+        let (fs, root) = project();
+        let controller = root.join("src/controllers/post.rs");
+        fs.write(&controller, b"pub async fn index() { todo!() }\n")
+            .unwrap();
+
+        let refused = destroy(&fs, &root, "controller", "Post", false);
+
+        match refused {
+            Err(GenerateError::EditedSinceGenerated { paths }) => {
+                assert_eq!(paths, vec![controller.clone()])
+            }
+            other => panic!("expected a refusal, got {other:?}"),
+        }
+        assert!(fs.exists(&controller));
+        assert_eq!(
+            fs.contents(root.join("src/routes/mod.rs")).as_deref(),
+            Some(ROUTES_MOD)
+        );
+        let manifest = Manifest::load(&fs, &root).unwrap();
+        assert!(manifest.generated.contains_key("controller Post"));
+    }
+
+    #[test]
+    fn deletes_edited_files_when_forced() {
+        let (fs, root) = project();
+        let controller = root.join("src/controllers/post.rs");
+        fs.write(&controller, b"pub async fn index() { todo!() }\n")
+            .unwrap();
+
+        destroy(&fs, &root, "controller", "Post", true).unwrap();
+
+        assert!(!fs.exists(&controller));
+        assert_eq!(
+            fs.contents(root.join("src/routes/mod.rs")).as_deref(),
+            Some("pub mod index;\npub use index::*;\n")
+        );
+    }
+
+    #[test]
+    fn refuses_resources_it_did_not_generate() {
+        let (fs, root) = project();
+
+        assert!(matches!(
+            destroy(&fs, &root, "model", "Post", false),
+            Err(GenerateError::NotGenerated {
+                generator: "model",
+                ..
+            })
+        ));
+    }
+}
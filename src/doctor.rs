@@ -0,0 +1,214 @@
+//! Checks the environment of an existing project for `rustyroad doctor`
+//! Every check is reported on its own so a single run shows everything that
+//! is wrong, instead of stopping at the first problem
+
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+use rustyroad::database::DatabaseType;
+use rustyroad::Project;
+
+use crate::css::CssFramework;
+use crate::database::is_sqlite_memory;
+use crate::layout::{project_at, project_file, relative_to_project};
+use crate::migrate::{self, load_project, open};
+use crate::rustyroad_toml::{self, find_project_root, Environment, GeneratorSettings};
+use crate::tools::{find_tool, install_hint};
+use crate::{GenerateError, MigrateError};
+
+/// The outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Something is off but the project still builds and runs
+    Warn,
+    /// The project can't build or run like this
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckStatus::Pass => "ok",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "FAIL",
+        })
+    }
+}
+
+/// A single line of the doctor report
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Check {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Runs every check against the project containing `current_dir`
+/// Only fails when `current_dir` isn't inside a project, every other problem
+/// is reported as a failed check
+pub async fn diagnose(current_dir: &Path) -> Result<Vec<Check>, GenerateError> {
+    let root = find_project_root(current_dir)?;
+    let project = project_at(&root);
+    let mut checks = Vec::new();
+
+    let settings = match rustyroad_toml::read(&root) {
+        Ok(config) => {
+            checks.push(Check::new("rustyroad.toml", CheckStatus::Pass, "valid"));
+            Some(config.generator)
+        }
+        Err(why) => {
+            checks.push(Check::new(
+                "rustyroad.toml",
+                CheckStatus::Fail,
+                why.to_string(),
+            ));
+            None
+        }
+    };
+
+    checks.extend(file_checks(&project, &settings.clone().unwrap_or_default()));
+    // Without a readable rustyroad.toml there is no database to connect to
+    if settings.is_some() {
+        checks.extend(database_checks(current_dir).await);
+    }
+    checks.extend(tool_checks(&project, &settings.unwrap_or_default()));
+
+    Ok(checks)
+}
+
+/// Checks that the files the project needs to build are in place
+fn file_checks(project: &Project, settings: &GeneratorSettings) -> Vec<Check> {
+    let mut expected = vec![
+        (&project.cargo_toml, CheckStatus::Fail),
+        (&project.main_rs, CheckStatus::Fail),
+        (&project.routes_module, CheckStatus::Fail),
+    ];
+    if settings.frontend {
+        expected.push((&project.templates, CheckStatus::Fail));
+        expected.push((&project.base_html, CheckStatus::Warn));
+        if settings.css == CssFramework::Tailwind {
+            expected.push((&project.tailwind_config, CheckStatus::Warn));
+            expected.push((&project.package_json, CheckStatus::Warn));
+        }
+    }
+
+    expected
+        .into_iter()
+        .map(|(path, missing)| {
+            let name = relative_to_project(project, path);
+            if Path::new(path).exists() {
+                Check::new(name, CheckStatus::Pass, "present")
+            } else {
+                Check::new(name, missing, "missing")
+            }
+        })
+        .collect()
+}
+
+/// Checks that the development database is reachable and its migrations
+/// are applied
+async fn database_checks(current_dir: &Path) -> Vec<Check> {
+    let (database_data, tls, postgres) = match load_project(current_dir, Environment::Development) {
+        Ok((_, database_data, tls, postgres)) => (database_data, tls, postgres),
+        Err(MigrateError::UnsupportedDatabase) => {
+            return vec![Check::new(
+                "database",
+                CheckStatus::Warn,
+                "MongoDB connections are not checked",
+            )];
+        }
+        Err(why) => return vec![Check::new("database", CheckStatus::Fail, why.to_string())],
+    };
+
+    // Connecting would create a missing SQLite file and hide the problem
+    if matches!(database_data.database_type, DatabaseType::Sqlite)
+        && !is_sqlite_memory(&database_data)
+        && !Path::new(&database_data.name).is_file()
+    {
+        return vec![Check::new(
+            "database",
+            CheckStatus::Fail,
+            format!("{} does not exist", database_data.name),
+        )];
+    }
+
+    let mut checks = Vec::new();
+    match open(&database_data, &tls, &postgres).await {
+        Ok(_) => checks.push(Check::new("database", CheckStatus::Pass, "connected")),
+        Err(why) => {
+            checks.push(Check::new("database", CheckStatus::Fail, why.to_string()));
+            return checks;
+        }
+    }
+
+    match migrate::migrate_status(current_dir, Environment::Development).await {
+        Ok(statuses) => {
+            let pending = statuses.iter().filter(|status| !status.applied).count();
+            if pending == 0 {
+                checks.push(Check::new(
+                    "migrations",
+                    CheckStatus::Pass,
+                    format!("{} applied", statuses.len()),
+                ));
+            } else {
+                checks.push(Check::new(
+                    "migrations",
+                    CheckStatus::Warn,
+                    format!("{pending} pending, run `rustyroad migrate`"),
+                ));
+            }
+        }
+        Err(why) => checks.push(Check::new("migrations", CheckStatus::Fail, why.to_string())),
+    }
+
+    checks
+}
+
+/// Checks that the programs needed to build and run the project are installed
+fn tool_checks(project: &Project, settings: &GeneratorSettings) -> Vec<Check> {
+    let mut tools = vec![("cargo", CheckStatus::Fail), ("git", CheckStatus::Warn)];
+    if settings.frontend && settings.css == CssFramework::Tailwind {
+        tools.push(("npx", CheckStatus::Warn));
+    }
+    if project_file(project, "Dockerfile").is_file() {
+        tools.push(("docker", CheckStatus::Warn));
+    }
+
+    tools
+        .into_iter()
+        .map(|(program, missing)| match tool_version(program) {
+            Some(version) => Check::new(program, CheckStatus::Pass, version),
+            None => Check::new(
+                program,
+                missing,
+                format!("not installed, {}", install_hint(program)),
+            ),
+        })
+        .collect()
+}
+
+/// The first line `program --version` prints, `None` if it can't be run
+fn tool_version(program: &str) -> Option<String> {
+    let output = Command::new(find_tool(program)?)
+        .arg("--version")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(stdout.lines().next().unwrap_or_default().trim().to_string())
+}
@@ -0,0 +1,538 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Errors that can occur while generating a new project
+/// Each variant carries enough context for the CLI to print a friendly
+/// message and exit cleanly instead of panicking
+#[derive(Debug, Error)]
+pub enum CreateProjectError {
+    /// The project name can't be used as a directory and Cargo package name
+    #[error("invalid project name `{name}`: {reason}, try `{suggestion}` instead")]
+    InvalidName {
+        name: String,
+        reason: String,
+        suggestion: String,
+    },
+
+    /// The database settings are invalid
+    #[error(transparent)]
+    DatabaseConfig(#[from] DatabaseConfigError),
+
+    /// An `--add-dep` value isn't a `name@version` dependency
+    #[error("invalid dependency `{spec}`: {reason}")]
+    InvalidDependency { spec: String, reason: &'static str },
+
+    /// The user declined to set up the database on the server
+    #[error(
+        "setting up database `{name}` on {host} was declined, pass --yes to skip the question"
+    )]
+    DatabaseSetupDeclined { name: String, host: String },
+
+    /// The directory the project should be created in can't hold it
+    #[error("can't create the project in `{}`: {reason}", path.display())]
+    InvalidParentDir { path: PathBuf, reason: &'static str },
+
+    /// The project directory already exists
+    #[error("directory `{}` already exists, please choose a different name", path.display())]
+    DirectoryExists { path: PathBuf },
+
+    /// A generated file or directory could not be written
+    #[error("failed to write `{}`: {source}", path.display())]
+    FileWrite {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The database server could not be reached or rejected the credentials
+    #[error("failed to connect to the database: {source}")]
+    DatabaseConnection {
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// The project database could not be created on the server
+    #[error("failed to create database `{name}`: {source}")]
+    DatabaseCreation {
+        name: String,
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// The initial SQL for the project could not be generated
+    #[error("failed to generate the initial SQL: {source}")]
+    SqlGeneration {
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// A SQL statement failed while initializing the database
+    /// `label` says what the statement did, e.g. `creating the users table`
+    #[error("failed while {label}: {source}")]
+    SqlExecution {
+        label: String,
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// The MongoDB server could not be reached or the database set up
+    #[cfg(feature = "mongo")]
+    #[error("MongoDB error: {source}")]
+    Mongo {
+        #[source]
+        source: mongodb::error::Error,
+    },
+
+    /// The project needs a cargo feature that this build was compiled without
+    #[error(
+        "this build of rustyroad does not support {what}, rebuild it with `--features {feature}`"
+    )]
+    FeatureDisabled {
+        feature: &'static str,
+        what: &'static str,
+    },
+
+    /// An external program could not be started
+    #[error("failed to run `{program}`: {source}")]
+    Process {
+        program: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// An external program the CLI needs is not on PATH
+    #[error("`{tool}` is not installed or not on PATH, {hint}")]
+    ToolNotFound {
+        tool: &'static str,
+        hint: &'static str,
+    },
+
+    /// The `--template` directory doesn't exist
+    #[error("template `{template}` not found")]
+    TemplateNotFound { template: String },
+
+    /// A `.tera` file of a custom template could not be rendered
+    #[error("failed to render template `{}`: {source}", path.display())]
+    TemplateRender {
+        path: PathBuf,
+        #[source]
+        source: tera::Error,
+    },
+
+    /// An external program ran but exited with an error
+    #[error("`{command}` failed: {stderr}")]
+    CommandFailed { command: String, stderr: String },
+
+    /// A session store was chosen for a project without the session login
+    #[error("--session-store needs the session login of an Actix project with a frontend")]
+    SessionStoreWithoutSessions,
+
+    /// The migrations directory isn't a path inside the project
+    #[error("invalid migrations directory `{value}`: {reason}")]
+    InvalidMigrationsDir { value: String, reason: &'static str },
+
+    /// The manifest of the enclosing Cargo workspace could not be read or
+    /// the project added to its members
+    #[error("failed to update the workspace at `{}`: {source}", path.display())]
+    Workspace {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The `--example` app can't be generated with the chosen options
+    #[error("the {example} example {reason}")]
+    InvalidExample {
+        example: &'static str,
+        reason: &'static str,
+    },
+
+    /// The `--example` app could not be added to the generated project
+    #[error("failed to add the {example} example: {source}")]
+    Example {
+        example: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// The database server is older than the initial SQL supports
+    #[error(
+        "{database} {version} is not supported, the initial SQL needs at least {minimum}, \
+         pass --ignore-sql-errors to try anyway"
+    )]
+    UnsupportedServerVersion {
+        database: &'static str,
+        version: String,
+        minimum: String,
+    },
+}
+
+impl CreateProjectError {
+    /// Returns a closure that wraps an io error for the file at `path`
+    /// Meant to be used with `map_err` on the writer functions
+    pub fn file_write(path: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.into();
+        move |source| CreateProjectError::FileWrite { path, source }
+    }
+}
+
+/// Errors that can occur while running a generator inside an existing project
+#[derive(Debug, Error)]
+pub enum GenerateError {
+    /// No rustyroad.toml was found in the current directory or any parent
+    #[error("`{}` is not inside a RustyRoad project (no rustyroad.toml found)", start.display())]
+    NotInProject { start: PathBuf },
+
+    /// A project file could not be read
+    #[error("failed to read `{}`: {source}", path.display())]
+    FileRead {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// rustyroad.toml could not be parsed
+    #[error("`{}` is not a valid rustyroad.toml: {source}", path.display())]
+    InvalidConfig {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    /// A `${VARIABLE}` placeholder in rustyroad.toml names an unset variable
+    #[error("`{setting}` in rustyroad.toml uses `${{{variable}}}`, which is not set")]
+    UnsetVariable {
+        setting: &'static str,
+        variable: String,
+    },
+
+    /// rustyroad.toml names a database type the CLI doesn't know about
+    #[error("unknown database type `{value}` in rustyroad.toml")]
+    UnknownDatabaseType { value: String },
+
+    /// The generator doesn't support the project's database type
+    #[error("`generate {generator}` is not supported for {database} projects")]
+    UnsupportedDatabase {
+        generator: &'static str,
+        database: &'static str,
+    },
+
+    /// The generator produces templates, which projects without a frontend lack
+    #[error("`generate {generator}` needs a project with a frontend")]
+    RequiresFrontend { generator: &'static str },
+
+    /// The resource name is not a valid identifier
+    #[error("invalid name `{name}`: {reason}")]
+    InvalidName { name: String, reason: String },
+
+    /// A `field:type` specification could not be parsed
+    #[error("invalid field `{spec}`: {reason}")]
+    InvalidField { spec: String, reason: String },
+
+    /// An index was asked for on a field the model doesn't have
+    #[error("can't index `{field}`, the model has no field of that name")]
+    UnknownIndexField { field: String },
+
+    /// Indexes were asked for on a SQL model, whose indexes belong in a migration
+    #[error(
+        "--index is only supported for MongoDB models, index {database} tables in a migration"
+    )]
+    IndexWithoutMongo { database: &'static str },
+
+    /// The generator would overwrite an existing file
+    #[error("`{}` already exists", path.display())]
+    AlreadyExists { path: PathBuf },
+
+    /// A generated file is missing the marker the generator inserts code at
+    #[error("`{}` has no `{marker}` line to register the new code at", path.display())]
+    MissingMarker { path: PathBuf, marker: &'static str },
+
+    /// A generated file or directory could not be written
+    #[error("failed to write `{}`: {source}", path.display())]
+    FileWrite {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A view override could not be rendered
+    #[error("failed to render `{}`: {source}", path.display())]
+    TemplateRender {
+        path: PathBuf,
+        #[source]
+        source: tera::Error,
+    },
+
+    /// The manifest has no record of the resource `destroy` should remove
+    #[error("the manifest has no record of {generator} `{name}`, was it generated?")]
+    NotGenerated {
+        generator: &'static str,
+        name: String,
+    },
+
+    /// Files `destroy` would delete were edited since they were generated
+    #[error(
+        "{} edited since generated, pass --force to delete anyway",
+        paths.iter().map(|path| format!("`{}`", path.display())).collect::<Vec<_>>().join(", ")
+    )]
+    EditedSinceGenerated { paths: Vec<PathBuf> },
+}
+
+impl GenerateError {
+    /// Returns a closure that wraps an io error for the file at `path`
+    /// Meant to be used with `map_err` on file writes
+    pub fn file_write(path: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.into();
+        move |source| GenerateError::FileWrite { path, source }
+    }
+}
+
+/// Errors that can occur while running migrations
+#[derive(Debug, Error)]
+pub enum MigrateError {
+    /// The project or its rustyroad.toml could not be loaded
+    #[error(transparent)]
+    Project(#[from] GenerateError),
+
+    /// Migrations only exist for the SQL databases
+    #[error("migrations are not supported for MongoDB projects")]
+    UnsupportedDatabase,
+
+    /// A migration file could not be read
+    #[error("failed to read `{}`: {source}", path.display())]
+    FileRead {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The database could not be reached or the tracking table queried
+    #[error("database error: {source}")]
+    DatabaseConnection {
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// A migration failed, the transaction was rolled back
+    #[error("migration `{version}` failed: {source}")]
+    Migration {
+        version: String,
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// A migration can't be reverted because it has no down.sql
+    #[error("migration `{version}` has no down.sql and can't be reverted")]
+    MissingDown { version: String },
+}
+
+/// Errors that can occur while seeding the database
+#[derive(Debug, Error)]
+pub enum SeedError {
+    /// The project could not be loaded or the reset migrations failed
+    #[error(transparent)]
+    Migrate(#[from] MigrateError),
+
+    /// The project has no seeds file
+    #[error("`{}` not found, create it to seed the database", path.display())]
+    MissingSeeds { path: PathBuf },
+
+    /// The seeds file could not be read
+    #[error("failed to read `{}`: {source}", path.display())]
+    FileRead {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The database could not be reached
+    #[error("database error: {source}")]
+    DatabaseConnection {
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// The seeds failed, the transaction was rolled back
+    #[error("seeding from `{}` failed: {source}", path.display())]
+    Seed {
+        path: PathBuf,
+        #[source]
+        source: sqlx::Error,
+    },
+}
+
+/// Errors that can occur while managing the database of an existing project
+#[derive(Debug, Error)]
+pub enum DbError {
+    /// The project could not be loaded or its migrations failed
+    #[error(transparent)]
+    Migrate(#[from] MigrateError),
+
+    /// The database looks like a production one and `--yes` wasn't given
+    #[error("`{target}` looks like a production database, pass --yes to reset it anyway")]
+    LooksLikeProduction { target: String },
+
+    /// `db drop` was run without `--yes`
+    #[error("dropping `{target}` deletes all of its data, pass --yes to drop it")]
+    DropNotConfirmed { target: String },
+
+    /// `db drop` was pointed at a database that looks like a production one
+    #[error("`{target}` looks like a production database, drop it with the database's own tools")]
+    DropProduction { target: String },
+
+    /// The database server could not be reached
+    #[error("database error: {source}")]
+    DatabaseConnection {
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// The database could not be dropped
+    #[error("failed to drop database `{name}`: {source}")]
+    Drop {
+        name: String,
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// The database could not be created
+    #[error("failed to create database `{name}`: {source}")]
+    Create {
+        name: String,
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// The SQLite database file could not be removed or its directory created
+    #[error("failed to write `{}`: {source}", path.display())]
+    FileWrite {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The initial SQL could not be generated
+    #[error("failed to generate the initial SQL: {source}")]
+    SqlGeneration {
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// A statement of the initial SQL failed
+    #[error("failed while {label}: {source}")]
+    SqlExecution {
+        label: String,
+        #[source]
+        source: sqlx::Error,
+    },
+}
+
+impl From<GenerateError> for DbError {
+    fn from(why: GenerateError) -> Self {
+        DbError::Migrate(MigrateError::Project(why))
+    }
+}
+
+/// Errors that can occur while assembling the database settings
+#[derive(Debug, Error)]
+pub enum DatabaseConfigError {
+    /// The connection URL could not be parsed
+    #[error("malformed database URL: {source}")]
+    MalformedUrl {
+        #[source]
+        source: url::ParseError,
+    },
+
+    /// The connection URL uses a scheme RustyRoad doesn't support
+    #[error(
+        "unsupported database URL scheme `{scheme}`, expected postgres, mysql, sqlite or mongodb"
+    )]
+    UnsupportedScheme { scheme: String },
+
+    /// The connection URL doesn't name a database
+    #[error("the database URL doesn't include a database name")]
+    MissingDatabaseName,
+
+    /// The port isn't a number between 1 and 65535
+    #[error("invalid database port `{value}`, expected a number between 1 and 65535")]
+    InvalidPort { value: String },
+
+    /// The database name can't be safely used in `CREATE DATABASE`
+    #[error("invalid database name `{name}`: {reason}")]
+    InvalidDatabaseName { name: String, reason: &'static str },
+
+    /// A SQLite file location was given for a database server
+    #[error("a SQLite path was given, but the database is {database}")]
+    SqlitePathWithoutSqlite { database: &'static str },
+
+    /// The charset or collation isn't a plain name such as `utf8mb4`
+    #[error("invalid charset or collation `{value}`, expected letters, digits and underscores")]
+    InvalidCharset { value: String },
+
+    /// The Postgres schema isn't a lowercase identifier such as `billing`
+    #[error("invalid schema `{value}`, expected lowercase letters, digits and underscores")]
+    InvalidSchema { value: String },
+
+    /// The table prefix can't start an unquoted identifier
+    #[error("invalid table prefix `{value}`: {reason}")]
+    InvalidTablePrefix { value: String, reason: &'static str },
+
+    /// The .env file could not be parsed
+    #[error("failed to read .env: {source}")]
+    EnvFile {
+        #[source]
+        source: dotenvy::Error,
+    },
+}
+
+/// Errors that can occur while reading or changing the user config files
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    /// A config file could not be read
+    #[error("failed to read `{}`: {source}", path.display())]
+    FileRead {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A config file or its directory could not be written
+    #[error("failed to write `{}`: {source}", path.display())]
+    FileWrite {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A config file could not be parsed
+    #[error("`{}` is not a valid config file: {source}", path.display())]
+    Invalid {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    /// The key isn't one `rustyroad config` knows about
+    #[error("unknown config key `{key}`, expected one of {}", crate::config::KEYS.join(", "))]
+    UnknownKey { key: String },
+
+    /// The value can't be used for the key
+    #[error("invalid value for `{key}`: {reason}")]
+    InvalidValue { key: String, reason: String },
+}
+
+/// Errors that can occur while running `rustyroad console`
+#[derive(Debug, Error)]
+pub enum ConsoleError {
+    /// The project could not be loaded or its database reached
+    #[error(transparent)]
+    Migrate(#[from] MigrateError),
+
+    /// The prompt could not be read or written
+    #[error("terminal error: {source}")]
+    Terminal {
+        #[source]
+        source: std::io::Error,
+    },
+}
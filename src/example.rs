@@ -0,0 +1,177 @@
+//! The demo apps `rustyroad new --example` generates
+//! An example is the normal project plus a scaffold for each of its
+//! resources, migrated and seeded, so `cargo run` shows pages with data
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::DatabaseType;
+use serde::{Deserialize, Serialize};
+
+use crate::database::qualified_table;
+use crate::filesystem::RealFileSystem;
+use crate::generators::{generate_scaffold, pluralize, to_snake_case};
+use crate::migrate::migrate_up;
+use crate::overwrite::OverwritePolicy;
+use crate::rustyroad_toml::{self, Environment};
+use crate::seed::{seed, SEEDS_FILE};
+use crate::CreateProjectError;
+
+/// A demo app generated on top of a new project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Example {
+    /// Posts with comments
+    Blog,
+    /// A todo list
+    Todo,
+}
+
+/// A scaffolded resource of an example and the rows it is seeded with
+struct Resource {
+    name: &'static str,
+    fields: &'static [&'static str],
+    /// SQL literals, one per field
+    rows: &'static [&'static [&'static str]],
+}
+
+impl Example {
+    /// The name of the example, as passed to `--example`
+    pub fn name(self) -> &'static str {
+        match self {
+            Example::Blog => "blog",
+            Example::Todo => "todo",
+        }
+    }
+
+    fn resources(self) -> &'static [Resource] {
+        match self {
+            Example::Blog => &[
+                Resource {
+                    name: "Post",
+                    fields: &["title:string", "body:text", "published:bool"],
+                    rows: &[
+                        &[
+                            "'Hello, RustyRoad'",
+                            "'This post was seeded by the blog example.'",
+                            "TRUE",
+                        ],
+                        &[
+                            "'Working with scaffolds'",
+                            "'Run rustyroad generate scaffold to add your own resources.'",
+                            "FALSE",
+                        ],
+                    ],
+                },
+                Resource {
+                    name: "Comment",
+                    fields: &["author:string", "body:text"],
+                    rows: &[&["'Ada'", "'Great first post!'"]],
+                },
+            ],
+            Example::Todo => &[Resource {
+                name: "Task",
+                fields: &["title:string", "done:bool"],
+                rows: &[
+                    &["'Generate a project'", "TRUE"],
+                    &["'Run cargo run'", "FALSE"],
+                    &["'Add a resource of your own'", "FALSE"],
+                ],
+            }],
+        }
+    }
+}
+
+/// Adds `example` to the freshly generated project at `root`: scaffolds its
+/// resources, applies their migrations and loads their rows, which are
+/// appended to seeds.sql
+/// Returns the files that were written
+pub async fn add_example(
+    root: &Path,
+    example: Example,
+) -> Result<Vec<PathBuf>, CreateProjectError> {
+    let failed = |source: Box<dyn std::error::Error + Send + Sync>| CreateProjectError::Example {
+        example: example.name(),
+        source,
+    };
+
+    let mut created = Vec::new();
+    for resource in example.resources() {
+        let fields: Vec<String> = resource
+            .fields
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        created.extend(
+            generate_scaffold(
+                &RealFileSystem,
+                root,
+                resource.name,
+                &fields,
+                OverwritePolicy::Abort,
+            )
+            .map_err(|why| failed(why.into()))?,
+        );
+    }
+
+    let config = rustyroad_toml::read(root).map_err(|why| failed(why.into()))?;
+    let database_type = config
+        .database
+        .to_database()
+        .map_err(|why| failed(why.into()))?
+        .database_type;
+    let mut seeds = format!("\n-- Data of the {} example\n", example.name());
+    for resource in example.resources() {
+        let table = qualified_table(
+            &database_type,
+            &config.database.postgres,
+            &config
+                .database
+                .tables
+                .table(&pluralize(&to_snake_case(resource.name))),
+        );
+        for row in resource.rows {
+            seeds.push_str(&insert_row(&database_type, &table, resource.fields, row));
+        }
+    }
+    let seeds_path = root.join(SEEDS_FILE);
+    OpenOptions::new()
+        .append(true)
+        .open(&seeds_path)
+        .and_then(|mut file| file.write_all(seeds.as_bytes()))
+        .map_err(CreateProjectError::file_write(&seeds_path))?;
+    created.push(seeds_path);
+
+    migrate_up(root, Environment::Development)
+        .await
+        .map_err(|why| failed(why.into()))?;
+    seed(root, Environment::Development, false)
+        .await
+        .map_err(|why| failed(why.into()))?;
+
+    Ok(created)
+}
+
+/// An insert of `row` into `table` that is skipped once a row with the same
+/// first field exists, so the seeds can run more than once
+/// Explicit ids would leave the Postgres sequence behind
+fn insert_row(database_type: &DatabaseType, table: &str, fields: &[&str], row: &[&str]) -> String {
+    let columns: Vec<&str> = fields
+        .iter()
+        .map(|field| field.split(':').next().unwrap_or(field))
+        .collect();
+    // MySQL only takes a WHERE clause on a SELECT with a FROM
+    let from_dual = match database_type {
+        DatabaseType::Mysql => " FROM DUAL",
+        _ => "",
+    };
+
+    format!(
+        "INSERT INTO {table} ({}) SELECT {}{from_dual} WHERE NOT EXISTS (SELECT 1 FROM {table} WHERE {} = {});\n",
+        columns.join(", "),
+        row.join(", "),
+        columns[0],
+        row[0]
+    )
+}
@@ -0,0 +1,291 @@
+//! The filesystem the writers produce files on
+//! Writers take a `&dyn FileSystem` instead of calling `std::fs`, so the
+//! files of a project can be generated in memory, e.g. to check what a
+//! writer produces without a temporary directory
+//! The generators of `rustyroad generate` and `rustyroad destroy` take one
+//! as well, so a resource can be added to a project held in memory
+//! The trait only covers the writers of this crate. The upstream writers of
+//! the `rustyroad` crate always write to disk, the files they produce that
+//! this crate edits afterwards, like rustyroad.toml, are imported onto the
+//! filesystem of the sink with `FileSink::import`. The rest, such as the
+//! project skeleton and the SQLite database, only exist on disk, so a new
+//! project can't be generated in memory as a whole
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// Where generated files are written and read back from for editing
+/// Shared by the concurrent write jobs, so it has to be `Sync`
+pub trait FileSystem: Debug + Send + Sync {
+    /// Writes `contents` to the file at `path`, replacing it if it exists
+    /// The parent directory has to exist
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Creates the directory `path` and any missing parents
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// The contents of the file at `path`
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Whether `path` is a file
+    fn is_file(&self, path: &Path) -> bool;
+
+    /// Whether `path` is a directory
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Removes the file at `path`
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Removes the directory at `path`, which has to be empty
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// The contents of the file at `path` as text
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))
+    }
+
+    /// Whether there is a file or directory at `path`
+    fn exists(&self, path: &Path) -> bool {
+        self.is_file(path) || self.is_dir(path)
+    }
+}
+
+/// The filesystem of the machine, through `std::fs`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(path)
+    }
+}
+
+/// An entry of a `MemoryFileSystem`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    File(Vec<u8>),
+    Dir,
+}
+
+/// A filesystem kept in memory, starting out empty
+/// Fails like the real one would, writing below a missing directory or
+/// reading a missing file, so writers behave the same on both
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    entries: Mutex<BTreeMap<PathBuf, Entry>>,
+}
+
+impl MemoryFileSystem {
+    /// An empty filesystem
+    pub fn new() -> Self {
+        MemoryFileSystem::default()
+    }
+
+    /// The paths of every file, sorted
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.entries()
+            .iter()
+            .filter(|(_, entry)| matches!(entry, Entry::File(_)))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// The text of the file at `path`, `None` if there is none
+    pub fn contents(&self, path: impl AsRef<Path>) -> Option<String> {
+        match self.entries().get(path.as_ref()) {
+            Some(Entry::File(contents)) => Some(String::from_utf8_lossy(contents).into_owned()),
+            _ => None,
+        }
+    }
+
+    /// The entries, which no operation panics while holding
+    fn entries(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Entry>> {
+        self.entries
+            .lock()
+            .expect("no filesystem operation panics while holding the entries")
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let mut entries = self.entries();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            if entries.get(parent) != Some(&Entry::Dir) {
+                return Err(not_found(parent));
+            }
+        }
+        if entries.get(path) == Some(&Entry::Dir) {
+            return Err(io::Error::other(format!(
+                "{} is a directory",
+                path.display()
+            )));
+        }
+
+        entries.insert(path.to_path_buf(), Entry::File(contents.to_vec()));
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries();
+        let missing: Vec<&Path> = path
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .collect();
+        if let Some(file) = missing
+            .iter()
+            .find(|ancestor| matches!(entries.get(**ancestor), Some(Entry::File(_))))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is a file", file.display()),
+            ));
+        }
+
+        for ancestor in missing {
+            entries.insert(ancestor.to_path_buf(), Entry::Dir);
+        }
+        Ok(())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.entries().get(path) {
+            Some(Entry::File(contents)) => Ok(contents.clone()),
+            Some(Entry::Dir) => Err(io::Error::other(format!(
+                "{} is a directory",
+                path.display()
+            ))),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        matches!(self.entries().get(path), Some(Entry::File(_)))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.entries().get(path) == Some(&Entry::Dir)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries();
+        match entries.get(path) {
+            Some(Entry::File(_)) => {
+                entries.remove(path);
+                Ok(())
+            }
+            Some(Entry::Dir) => Err(io::Error::other(format!(
+                "{} is a directory",
+                path.display()
+            ))),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries();
+        if entries.get(path) != Some(&Entry::Dir) {
+            return Err(not_found(path));
+        }
+        if entries.keys().any(|entry| entry.parent() == Some(path)) {
+            return Err(io::Error::other(format!("{} isn't empty", path.display())));
+        }
+
+        entries.remove(path);
+        Ok(())
+    }
+}
+
+/// The error of a missing file or directory at `path`
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} doesn't exist", path.display()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_back_what_was_written() {
+        let fs = MemoryFileSystem::new();
+        fs.create_dir(Path::new("blog/src")).unwrap();
+        fs.write(Path::new("blog/src/main.rs"), b"fn main() {}\n")
+            .unwrap();
+
+        assert_eq!(
+            fs.read_to_string(Path::new("blog/src/main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert_eq!(fs.files(), [PathBuf::from("blog/src/main.rs")]);
+        assert_eq!(
+            fs.contents("blog/src/main.rs").as_deref(),
+            Some("fn main() {}\n")
+        );
+    }
+
+    #[test]
+    fn creates_missing_parents() {
+        let fs = MemoryFileSystem::new();
+        fs.create_dir(Path::new("blog/src/views")).unwrap();
+
+        assert!(fs.is_dir(Path::new("blog")));
+        assert!(fs.is_dir(Path::new("blog/src")));
+        assert!(fs.exists(Path::new("blog/src/views")));
+        assert!(fs.files().is_empty());
+    }
+
+    #[test]
+    fn fails_like_the_real_filesystem() {
+        let fs = MemoryFileSystem::new();
+
+        let missing_parent = fs.write(Path::new("blog/main.rs"), b"").unwrap_err();
+        assert_eq!(missing_parent.kind(), io::ErrorKind::NotFound);
+        let missing_file = fs.read(Path::new("blog/main.rs")).unwrap_err();
+        assert_eq!(missing_file.kind(), io::ErrorKind::NotFound);
+
+        fs.create_dir(Path::new("blog")).unwrap();
+        fs.write(Path::new("blog/main.rs"), b"").unwrap();
+        assert!(fs.create_dir(Path::new("blog/main.rs/src")).is_err());
+        assert!(fs.write(Path::new("blog"), b"").is_err());
+        assert!(fs.remove_dir(Path::new("blog")).is_err());
+        assert!(fs.remove_file(Path::new("blog")).is_err());
+
+        fs.remove_file(Path::new("blog/main.rs")).unwrap();
+        fs.remove_dir(Path::new("blog")).unwrap();
+        assert!(!fs.exists(Path::new("blog")));
+    }
+}
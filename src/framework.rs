@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// The web framework a generated project is built on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Framework {
+    /// Actix Web, the historical default
+    #[default]
+    Actix,
+    /// Axum on top of Tokio and Tower
+    Axum,
+}
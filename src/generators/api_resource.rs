@@ -0,0 +1,322 @@
+//! `rustyroad generate api-resource`, the JSON counterpart of the scaffold
+//! The migration and the model are generated like for a scaffold, but the
+//! controller answers every CRUD action with JSON below `/api` and no
+//! templates are written, so projects without a frontend can use it too
+
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::DatabaseType;
+
+use crate::filesystem::FileSystem;
+use crate::generators::scaffold::{
+    bind_fields, form_struct, generate_resource, Queries, Resource, Responses,
+};
+use crate::overwrite::OverwritePolicy;
+use crate::GenerateError;
+
+/// The path the routes of API resources are mounted below
+pub const API_PREFIX: &str = "/api";
+
+/// Generates a JSON CRUD resource named `name` with the given `name:type`
+/// fields in the project on `fs` that contains `current_dir`
+/// Creates the migration, the model and a controller serving
+/// `/api/<table>` and `/api/<table>/<id>`, and registers the new modules,
+/// recording all of it in the manifest for `rustyroad destroy`
+/// Files that already exist are treated as `policy` says, and a failure
+/// undoes every change like for `generate_scaffold`
+/// Returns the paths of every file it created or modified
+pub fn generate_api_resource(
+    fs: &dyn FileSystem,
+    current_dir: &Path,
+    name: &str,
+    field_specs: &[String],
+    policy: OverwritePolicy,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    generate_resource(fs, current_dir, name, field_specs, policy, Responses::Json)
+}
+
+/// The body of the generated `insert`, which returns the new record
+/// MySQL has no `RETURNING`, the record is read back by its new id
+fn insert_body(resource: &Resource, database_type: &DatabaseType, queries: &Queries) -> String {
+    let binds = bind_fields(resource, "        ");
+    match database_type {
+        DatabaseType::Mysql => format!(
+            r#"    let done = sqlx::query("{insert}"){binds}
+        .execute(pool::shared())
+        .await?;
+    sqlx::query_as::<_, {name}>("{select_one}")
+        .bind(done.last_insert_id() as i32)
+        .fetch_one(pool::shared())
+        .await"#,
+            insert = queries.insert,
+            select_one = queries.select_one,
+            name = resource.name,
+        ),
+        _ => format!(
+            r#"    sqlx::query_as::<_, {name}>("{insert} RETURNING *"){binds}
+        .fetch_one(pool::shared())
+        .await"#,
+            insert = queries.insert,
+            name = resource.name,
+        ),
+    }
+}
+
+/// The helpers reading and inserting records, shared by both frameworks
+fn record_helpers(resource: &Resource, database_type: &DatabaseType, queries: &Queries) -> String {
+    let Resource { name, .. } = resource;
+    format!(
+        r#"/// The record `id`, `None` if there is none
+async fn find(id: i32) -> Result<Option<{name}>, sqlx::Error> {{
+    sqlx::query_as::<_, {name}>("{select_one}")
+        .bind(id)
+        .fetch_optional(pool::shared())
+        .await
+}}
+
+/// Inserts a record with the fields of `form` and returns it
+async fn insert(form: &{form}) -> Result<{name}, sqlx::Error> {{
+{insert}
+}}
+"#,
+        form = resource.form_name(),
+        select_one = queries.select_one,
+        insert = insert_body(resource, database_type, queries),
+    )
+}
+
+/// The Rust source of an Actix JSON controller backed by the resource's table
+pub(super) fn actix_api_source(resource: &Resource, database_type: &DatabaseType) -> String {
+    let Resource {
+        name,
+        module,
+        table,
+        ..
+    } = resource;
+    let form = resource.form_name();
+    let queries = Queries::new(resource, database_type);
+    let binds = bind_fields(resource, "        ");
+
+    format!(
+        r#"use actix_web::{{delete, get, post, put, web, HttpResponse}};
+use serde::{{Deserialize, Serialize}};
+
+use crate::models::{module}::{name};
+use crate::pool;
+
+{form_struct}
+/// The body of error responses
+#[derive(Serialize)]
+struct ApiError {{
+    error: String,
+}}
+
+/// Registers the routes of this controller below `{API_PREFIX}/{table}`
+pub fn configure(cfg: &mut web::ServiceConfig) {{
+    cfg.service(
+        web::scope("{API_PREFIX}/{table}")
+            .service(index)
+            .service(create)
+            .service(show)
+            .service(update)
+            .service(destroy),
+    );
+}}
+
+#[get("")]
+async fn index() -> HttpResponse {{
+    let records = sqlx::query_as::<_, {name}>("{select_all}")
+        .fetch_all(pool::shared())
+        .await;
+
+    match records {{
+        Ok(records) => HttpResponse::Ok().json(records),
+        Err(why) => server_error(why),
+    }}
+}}
+
+#[post("")]
+async fn create(form: web::Json<{form}>) -> HttpResponse {{
+    match insert(&form).await {{
+        Ok(record) => HttpResponse::Created().json(record),
+        Err(why) => server_error(why),
+    }}
+}}
+
+#[get("/{{id}}")]
+async fn show(path: web::Path<i32>) -> HttpResponse {{
+    match find(path.into_inner()).await {{
+        Ok(Some(record)) => HttpResponse::Ok().json(record),
+        Ok(None) => not_found(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+#[put("/{{id}}")]
+async fn update(path: web::Path<i32>, form: web::Json<{form}>) -> HttpResponse {{
+    let id = path.into_inner();
+    let result = sqlx::query("{update}"){binds}
+        .bind(id)
+        .execute(pool::shared())
+        .await;
+    if let Err(why) = result {{
+        return server_error(why);
+    }}
+
+    match find(id).await {{
+        Ok(Some(record)) => HttpResponse::Ok().json(record),
+        Ok(None) => not_found(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+#[delete("/{{id}}")]
+async fn destroy(path: web::Path<i32>) -> HttpResponse {{
+    let result = sqlx::query("{delete}")
+        .bind(path.into_inner())
+        .execute(pool::shared())
+        .await;
+
+    match result {{
+        Ok(done) if done.rows_affected() == 0 => not_found(),
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+{helpers}
+fn not_found() -> HttpResponse {{
+    HttpResponse::NotFound().json(ApiError {{
+        error: "Not found".to_string(),
+    }})
+}}
+
+fn server_error(why: sqlx::Error) -> HttpResponse {{
+    HttpResponse::InternalServerError().json(ApiError {{
+        error: why.to_string(),
+    }})
+}}
+"#,
+        form_struct = form_struct(resource),
+        select_all = queries.select_all,
+        update = queries.update,
+        delete = queries.delete,
+        helpers = record_helpers(resource, database_type, &queries),
+    )
+}
+
+/// The Rust source of an Axum JSON controller backed by the resource's table
+/// routes/mod.rs nests its router below `API_PREFIX`
+pub(super) fn axum_api_source(resource: &Resource, database_type: &DatabaseType) -> String {
+    let Resource {
+        name,
+        module,
+        table,
+        ..
+    } = resource;
+    let form = resource.form_name();
+    let queries = Queries::new(resource, database_type);
+    let binds = bind_fields(resource, "        ");
+
+    format!(
+        r#"use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::response::{{IntoResponse, Response}};
+use axum::routing::get;
+use axum::{{Json, Router}};
+use serde::{{Deserialize, Serialize}};
+
+use crate::models::{module}::{name};
+use crate::pool;
+
+{form_struct}
+/// The body of error responses
+#[derive(Serialize)]
+struct ApiError {{
+    error: String,
+}}
+
+/// The routes of this controller, nested below `{API_PREFIX}` in routes/mod.rs
+pub fn router() -> Router {{
+    Router::new()
+        .route("/{table}", get(index).post(create))
+        .route("/{table}/:id", get(show).put(update).delete(destroy))
+}}
+
+async fn index() -> Response {{
+    let records = sqlx::query_as::<_, {name}>("{select_all}")
+        .fetch_all(pool::shared())
+        .await;
+
+    match records {{
+        Ok(records) => Json(records).into_response(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+async fn create(Json(form): Json<{form}>) -> Response {{
+    match insert(&form).await {{
+        Ok(record) => (StatusCode::CREATED, Json(record)).into_response(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+async fn show(Path(id): Path<i32>) -> Response {{
+    match find(id).await {{
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => not_found(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+async fn update(Path(id): Path<i32>, Json(form): Json<{form}>) -> Response {{
+    let result = sqlx::query("{update}"){binds}
+        .bind(id)
+        .execute(pool::shared())
+        .await;
+    if let Err(why) = result {{
+        return server_error(why);
+    }}
+
+    match find(id).await {{
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => not_found(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+async fn destroy(Path(id): Path<i32>) -> Response {{
+    let result = sqlx::query("{delete}")
+        .bind(id)
+        .execute(pool::shared())
+        .await;
+
+    match result {{
+        Ok(done) if done.rows_affected() == 0 => not_found(),
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+{helpers}
+fn not_found() -> Response {{
+    let error = ApiError {{
+        error: "Not found".to_string(),
+    }};
+    (StatusCode::NOT_FOUND, Json(error)).into_response()
+}}
+
+fn server_error(why: sqlx::Error) -> Response {{
+    let error = ApiError {{
+        error: why.to_string(),
+    }};
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+}}
+"#,
+        form_struct = form_struct(resource),
+        select_all = queries.select_all,
+        update = queries.update,
+        delete = queries.delete,
+        helpers = record_helpers(resource, database_type, &queries),
+    )
+}
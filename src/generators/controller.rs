@@ -0,0 +1,352 @@
+use std::path::{Path, PathBuf};
+
+use rustyroad::Project;
+use tera::Context;
+use tracing::info;
+
+use crate::css::CssFramework;
+use crate::filesystem::FileSystem;
+use crate::framework::Framework;
+use crate::generators::{record_generated, to_snake_case, validate_resource_name, Edit};
+use crate::layout::{project_at, template_name};
+use crate::rustyroad_toml::{find_project_root_on, read_on};
+use crate::views::Views;
+use crate::writers::{actix, axum, write_new_file};
+use crate::GenerateError;
+
+/// The seven RESTful actions generated when no explicit actions are given
+pub const REST_ACTIONS: [&str; 7] = [
+    "index", "show", "new", "create", "edit", "update", "destroy",
+];
+
+/// Generates a controller named `name` in the project on `fs` that contains
+/// `current_dir`
+/// With no `actions` the seven REST actions are generated, otherwise only
+/// the given ones
+/// Creates a route module with one handler per action for the project's
+/// framework, a Tera template stub per action and registers the module in
+/// routes/mod.rs, recording all of it in the manifest for `rustyroad destroy`
+/// Projects generated without a frontend get JSON handlers and no templates
+/// A template is taken from `controller/<action>.html.tera` in the view
+/// overrides when there is one
+/// Returns the paths of every file it created or modified
+pub fn generate_controller(
+    fs: &dyn FileSystem,
+    current_dir: &Path,
+    name: &str,
+    actions: &[String],
+) -> Result<Vec<PathBuf>, GenerateError> {
+    validate_resource_name(name)?;
+
+    let actions: Vec<&str> = if actions.is_empty() {
+        REST_ACTIONS.to_vec()
+    } else {
+        actions.iter().map(String::as_str).collect()
+    };
+    for action in &actions {
+        validate_action(action)?;
+    }
+
+    let root = find_project_root_on(fs, current_dir)?;
+    let settings = read_on(fs, &root)?.generator;
+    let project = project_at(&root);
+    let module = to_snake_case(name);
+
+    let routes_mod = PathBuf::from(&project.routes_module);
+    let routes_dir = routes_mod.parent().unwrap_or(&root).to_path_buf();
+    let controller_path = routes_dir.join(format!("{module}.rs"));
+    let templates_dir = Path::new(&project.templates).join(&module);
+
+    if fs.exists(&controller_path) {
+        return Err(GenerateError::AlreadyExists {
+            path: controller_path,
+        });
+    }
+
+    let mut created = Vec::new();
+
+    let source = match settings.framework {
+        Framework::Actix => actix_controller_source(&module, &actions, settings.frontend),
+        Framework::Axum => axum_controller_source(&module, &actions, settings.frontend),
+    };
+    write_new_file(fs, &controller_path, &source)?;
+    created.push(controller_path);
+
+    if settings.frontend {
+        let views = Views::for_project(&root);
+        for action in &actions {
+            let template_path = templates_dir.join(format!("{action}.html.tera"));
+            let mut context = Context::new();
+            context.insert("name", name);
+            context.insert("module", &module);
+            context.insert("action", action);
+            context.insert("base", template_name(&project, &project.base_html));
+            let source = views.render_template(
+                &format!("controller/{action}.html.tera"),
+                &context,
+                || template_source(&project, name, action, settings.css),
+            )?;
+            write_new_file(fs, &template_path, &source)?;
+            created.push(template_path);
+        }
+    }
+
+    let routes_mod_edit = Edit::of(fs, &routes_mod);
+    match settings.framework {
+        Framework::Actix => actix::register_route_module(fs, &routes_mod, &module)?,
+        Framework::Axum => axum::register_route_module(fs, &routes_mod, &module)?,
+    }
+
+    record_generated(fs, &root, "controller", name, &created, &[routes_mod_edit])?;
+    created.push(routes_mod);
+
+    info!("Controller {name} created!");
+
+    Ok(created)
+}
+
+/// Checks that an action name can be used as a Rust function name
+fn validate_action(action: &str) -> Result<(), GenerateError> {
+    let valid = action.starts_with(|c: char| c.is_ascii_lowercase())
+        && action
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(GenerateError::InvalidName {
+            name: action.to_string(),
+            reason: "actions must be snake_case, e.g. `publish`".to_string(),
+        })
+    }
+}
+
+/// The HTTP method and path of an action, with `{id}` marking the id segment
+/// HTML forms can only send GET and POST, so update and destroy use POST
+fn route_for(module: &str, action: &str) -> (&'static str, String) {
+    match action {
+        "index" => ("get", format!("/{module}")),
+        "new" => ("get", format!("/{module}/new")),
+        "create" => ("post", format!("/{module}")),
+        "show" => ("get", format!("/{module}/{{id}}")),
+        "edit" => ("get", format!("/{module}/{{id}}/edit")),
+        "update" => ("post", format!("/{module}/{{id}}")),
+        "destroy" => ("post", format!("/{module}/{{id}}/delete")),
+        custom => ("get", format!("/{module}/{custom}")),
+    }
+}
+
+/// The actions ordered so literal routes such as `/posts/new` come before
+/// `/posts/{id}` and aren't swallowed by the path parameter
+fn routing_order<'a>(module: &str, actions: &[&'a str]) -> Vec<&'a str> {
+    let mut ordered = actions.to_vec();
+    ordered.sort_by_key(|action| route_for(module, action).1.contains("{id}"));
+    ordered
+}
+
+/// The Rust source of an Actix controller module
+/// With `templates` the handlers render the action's template, otherwise
+/// they answer with JSON
+pub fn actix_controller_source(module: &str, actions: &[&str], templates: bool) -> String {
+    let mut source = String::from("use actix_web::{get, post, web, HttpResponse, Responder};\n");
+    if templates {
+        source.push_str("use tera::{Context, Tera};\n\n");
+    } else {
+        source.push_str("use serde_json::json;\n\n");
+    }
+
+    source.push_str("/// Registers the routes of this controller\n");
+    source.push_str("pub fn configure(cfg: &mut web::ServiceConfig) {\n");
+    for action in routing_order(module, actions) {
+        source.push_str(&format!("    cfg.service({action});\n"));
+    }
+    source.push_str("}\n");
+
+    for action in actions {
+        let (method, path) = route_for(module, action);
+
+        source.push_str(&format!("\n#[{method}(\"{path}\")]\n"));
+        if !templates {
+            source.push_str(&actix_json_handler(module, action, path.contains("{id}")));
+            continue;
+        }
+        if path.contains("{id}") {
+            source.push_str(&format!(
+                "async fn {action}(tera: web::Data<Tera>, path: web::Path<i32>) -> impl Responder {{\n    \
+                 let mut context = Context::new();\n    \
+                 context.insert(\"id\", &path.into_inner());\n"
+            ));
+        } else {
+            source.push_str(&format!(
+                "async fn {action}(tera: web::Data<Tera>) -> impl Responder {{\n    \
+                 let context = Context::new();\n"
+            ));
+        }
+        source.push_str(&format!(
+            "    render(&tera, \"{module}/{action}.html.tera\", &context)\n}}\n"
+        ));
+    }
+
+    if !templates {
+        return source;
+    }
+
+    source.push_str(
+        "\nfn render(tera: &Tera, template: &str, context: &Context) -> HttpResponse {\n    \
+         match tera.render(template, context) {\n        \
+         Ok(body) => HttpResponse::Ok().content_type(\"text/html\").body(body),\n        \
+         Err(why) => HttpResponse::InternalServerError().body(why.to_string()),\n    \
+         }\n}\n",
+    );
+
+    source
+}
+
+/// An Actix handler answering with the controller, action and id as JSON
+fn actix_json_handler(module: &str, action: &str, takes_id: bool) -> String {
+    if takes_id {
+        format!(
+            "async fn {action}(path: web::Path<i32>) -> impl Responder {{\n    \
+             HttpResponse::Ok().json(json!({{ \"controller\": \"{module}\", \"action\": \"{action}\", \"id\": path.into_inner() }}))\n}}\n"
+        )
+    } else {
+        format!(
+            "async fn {action}() -> impl Responder {{\n    \
+             HttpResponse::Ok().json(json!({{ \"controller\": \"{module}\", \"action\": \"{action}\" }}))\n}}\n"
+        )
+    }
+}
+
+/// The Rust source of an Axum controller module
+/// Routes sharing a path are chained on one `route` call as Axum requires
+/// With `templates` the handlers render the action's template, otherwise
+/// they answer with JSON
+pub fn axum_controller_source(module: &str, actions: &[&str], templates: bool) -> String {
+    let mut routes: Vec<(String, Vec<(&str, &str)>)> = Vec::new();
+    for action in routing_order(module, actions) {
+        let (method, path) = route_for(module, action);
+        let path = path.replace("{id}", ":id");
+        match routes.iter_mut().find(|(existing, _)| *existing == path) {
+            Some((_, handlers)) => handlers.push((method, action)),
+            None => routes.push((path, vec![(method, action)])),
+        }
+    }
+
+    let mut methods: Vec<&str> = routes
+        .iter()
+        .flat_map(|(_, handlers)| handlers.iter().map(|(method, _)| *method))
+        .collect();
+    methods.sort_unstable();
+    methods.dedup();
+
+    let takes_id = actions
+        .iter()
+        .any(|action| route_for(module, action).1.contains("{id}"));
+    let routing = methods.join(", ");
+
+    let mut source = if templates {
+        let extractors = if takes_id {
+            "use axum::extract::{Extension, Path};"
+        } else {
+            "use axum::extract::Extension;"
+        };
+        format!(
+            "use std::sync::Arc;\n\n\
+             {extractors}\n\
+             use axum::response::Response;\n\
+             use axum::routing::{{{routing}}};\n\
+             use axum::Router;\n\
+             use tera::{{Context, Tera}};\n\n\
+             use super::render;\n\n"
+        )
+    } else {
+        let extractors = if takes_id {
+            "use axum::extract::Path;\n"
+        } else {
+            ""
+        };
+        format!(
+            "{extractors}use axum::routing::{{{routing}}};\n\
+             use axum::{{Json, Router}};\n\
+             use serde_json::{{json, Value}};\n\n"
+        )
+    };
+
+    source.push_str("/// The routes of this controller\n");
+    source.push_str("pub fn router() -> Router {\n    Router::new()\n");
+    for (path, handlers) in &routes {
+        let chain = handlers
+            .iter()
+            .enumerate()
+            .map(|(i, (method, action))| {
+                if i == 0 {
+                    format!("{method}({action})")
+                } else {
+                    format!(".{method}({action})")
+                }
+            })
+            .collect::<String>();
+        source.push_str(&format!("        .route(\"{path}\", {chain})\n"));
+    }
+    source.push_str("}\n");
+
+    for action in actions {
+        let (_, path) = route_for(module, action);
+
+        if !templates {
+            source.push_str(&axum_json_handler(module, action, path.contains("{id}")));
+            continue;
+        }
+        if path.contains("{id}") {
+            source.push_str(&format!(
+                "\nasync fn {action}(Extension(tera): Extension<Arc<Tera>>, Path(id): Path<i32>) -> Response {{\n    \
+                 let mut context = Context::new();\n    \
+                 context.insert(\"id\", &id);\n"
+            ));
+        } else {
+            source.push_str(&format!(
+                "\nasync fn {action}(Extension(tera): Extension<Arc<Tera>>) -> Response {{\n    \
+                 let context = Context::new();\n"
+            ));
+        }
+        source.push_str(&format!(
+            "    render(&tera, \"{module}/{action}.html.tera\", &context)\n}}\n"
+        ));
+    }
+
+    source
+}
+
+/// An Axum handler answering with the controller, action and id as JSON
+fn axum_json_handler(module: &str, action: &str, takes_id: bool) -> String {
+    if takes_id {
+        format!(
+            "\nasync fn {action}(Path(id): Path<i32>) -> Json<Value> {{\n    \
+             Json(json!({{ \"controller\": \"{module}\", \"action\": \"{action}\", \"id\": id }}))\n}}\n"
+        )
+    } else {
+        format!(
+            "\nasync fn {action}() -> Json<Value> {{\n    \
+             Json(json!({{ \"controller\": \"{module}\", \"action\": \"{action}\" }}))\n}}\n"
+        )
+    }
+}
+
+/// A Tera template stub for an action, extending the project's base layout
+/// and styled with the classes of the project's CSS framework
+pub fn template_source(project: &Project, name: &str, action: &str, css: CssFramework) -> String {
+    let base = template_name(project, &project.base_html);
+    let module = to_snake_case(name);
+    let heading_class = css.heading_class();
+    let text_class = css.text_class();
+
+    format!(
+        "{{% extends \"{base}\" %}}\n\n\
+         {{% block title %}}{name} {action}{{% endblock title %}}\n\n\
+         {{% block content %}}\n\
+         <h1 class=\"{heading_class}\">{name}#{action}</h1>\n\
+         <p class=\"{text_class}\">Find me in {module}/{action}.html.tera</p>\n\
+         {{% endblock content %}}\n"
+    )
+}
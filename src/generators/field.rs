@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+use rustyroad::database::DatabaseType;
+
+use crate::generators::is_rust_keyword;
+use crate::GenerateError;
+
+/// The column types that can be used in a `name:type` field specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    String,
+    Text,
+    Integer,
+    BigInteger,
+    Float,
+    Boolean,
+    Date,
+    DateTime,
+}
+
+/// A single field of a generated model, parsed from `name:type`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub field_type: FieldType,
+}
+
+impl FromStr for FieldType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "string" => Ok(FieldType::String),
+            "text" => Ok(FieldType::Text),
+            "int" | "integer" => Ok(FieldType::Integer),
+            "bigint" => Ok(FieldType::BigInteger),
+            "float" | "double" | "decimal" => Ok(FieldType::Float),
+            "bool" | "boolean" => Ok(FieldType::Boolean),
+            "date" => Ok(FieldType::Date),
+            "datetime" | "timestamp" => Ok(FieldType::DateTime),
+            other => Err(format!(
+                "unknown type `{other}`, expected one of string, text, int, bigint, float, bool, date, datetime"
+            )),
+        }
+    }
+}
+
+impl FieldType {
+    /// The SQL column type for the given database
+    pub fn sql_type(self, database_type: &DatabaseType) -> &'static str {
+        match (self, database_type) {
+            (FieldType::String, DatabaseType::Sqlite) => "TEXT",
+            (FieldType::String, _) => "VARCHAR(255)",
+            (FieldType::Text, _) => "TEXT",
+            (FieldType::Integer, DatabaseType::Mysql) => "INT",
+            (FieldType::Integer, _) => "INTEGER",
+            (FieldType::BigInteger, DatabaseType::Sqlite) => "INTEGER",
+            (FieldType::BigInteger, _) => "BIGINT",
+            (FieldType::Float, DatabaseType::Sqlite) => "REAL",
+            (FieldType::Float, DatabaseType::Postgres) => "DOUBLE PRECISION",
+            (FieldType::Float, _) => "DOUBLE",
+            (FieldType::Boolean, _) => "BOOLEAN",
+            (FieldType::Date, _) => "DATE",
+            (FieldType::DateTime, DatabaseType::Postgres) => "TIMESTAMP",
+            (FieldType::DateTime, _) => "DATETIME",
+        }
+    }
+
+    /// The Rust type used for the field in the generated model struct
+    pub fn rust_type(self) -> &'static str {
+        match self {
+            FieldType::String | FieldType::Text => "String",
+            FieldType::Integer => "i32",
+            FieldType::BigInteger => "i64",
+            FieldType::Float => "f64",
+            FieldType::Boolean => "bool",
+            FieldType::Date => "chrono::NaiveDate",
+            FieldType::DateTime => "chrono::NaiveDateTime",
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = GenerateError;
+
+    /// Parses a field specification such as `title:string` or `author_id:int`
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| GenerateError::InvalidField {
+            spec: spec.to_string(),
+            reason,
+        };
+
+        let (name, field_type) = spec
+            .split_once(':')
+            .ok_or_else(|| invalid("expected `name:type`".to_string()))?;
+
+        let starts_ok = name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_');
+        let chars_ok = name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !starts_ok || !chars_ok {
+            return Err(invalid(
+                "field names must be snake_case, e.g. `author_id`".to_string(),
+            ));
+        }
+        if name.chars().all(|c| c == '_') {
+            return Err(invalid(format!("`{name}` is not a field name")));
+        }
+        if is_rust_keyword(name) {
+            return Err(invalid(format!("`{name}` is a Rust keyword")));
+        }
+        if ["id", "created_at", "updated_at"].contains(&name) {
+            return Err(invalid(format!("`{name}` is generated automatically")));
+        }
+
+        Ok(Field {
+            name: name.to_string(),
+            field_type: field_type.parse().map_err(invalid)?,
+        })
+    }
+}
+
+/// Parses every `name:type` specification, rejecting duplicate field names
+pub fn parse_fields(specs: &[String]) -> Result<Vec<Field>, GenerateError> {
+    let mut fields: Vec<Field> = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let field: Field = spec.parse()?;
+        if fields.iter().any(|existing| existing.name == field.name) {
+            return Err(GenerateError::InvalidField {
+                spec: spec.clone(),
+                reason: "field is declared more than once".to_string(),
+            });
+        }
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(spec: &str) -> String {
+        match spec.parse::<Field>() {
+            Err(GenerateError::InvalidField { reason, .. }) => reason,
+            other => panic!("`{spec}` parsed as {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_name_and_type() {
+        let field: Field = "author_id:int".parse().unwrap();
+        assert_eq!(field.name, "author_id");
+        assert_eq!(field.field_type, FieldType::Integer);
+    }
+
+    #[test]
+    fn rejects_rust_keywords() {
+        for spec in [
+            "type:string",
+            "fn:string",
+            "match:int",
+            "self:bool",
+            "async:text",
+        ] {
+            assert!(reason(spec).contains("Rust keyword"), "{spec}");
+        }
+        assert!("types:string".parse::<Field>().is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        assert!(reason("_:string").contains("not a field name"));
+        assert!(reason("Title:string").contains("snake_case"));
+        assert!(reason("id:int").contains("generated automatically"));
+        assert!(reason("title:blob").contains("unknown type"));
+    }
+}
@@ -0,0 +1,68 @@
+//! Writes the frontend of an existing project again
+//! After changing the Tailwind config or upgrading the CLI the stylesheets,
+//! entry points, base template, layout partials and index page can be
+//! regenerated without touching the database or the rest of the project.
+//! Changed files are handled by the overwrite policy, by default files edited
+//! since they were generated are only replaced once the user agreed to the
+//! shown diff
+
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use crate::layout::project_at;
+use crate::manifest::MANIFEST_PATH;
+use crate::overwrite::OverwritePolicy;
+use crate::rustyroad_toml::{self, find_project_root};
+use crate::sink::{FileSink, SinkMode};
+use crate::template::template_context;
+use crate::views::Views;
+use crate::{write_frontend, write_layout, GenerateError, NewProjectOptions};
+
+/// Regenerates the frontend of the project containing `current_dir` with
+/// the CSS framework and auth scheme it was created with, treating changed
+/// files as `policy` says
+/// The header and navbar are only written again when the project has them
+/// Returns the paths of every file written
+pub fn generate_frontend(
+    current_dir: &Path,
+    policy: OverwritePolicy,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    let root = find_project_root(current_dir)?;
+    let config = rustyroad_toml::read(&root)?;
+    if !config.generator.frontend {
+        return Err(GenerateError::RequiresFrontend {
+            generator: "frontend",
+        });
+    }
+
+    let project = project_at(&root);
+    let database_data = config.database.to_database()?;
+    let options = NewProjectOptions {
+        framework: config.generator.framework,
+        css: config.generator.css,
+        auth: config.generator.auth,
+        ..NewProjectOptions::default()
+    };
+
+    let manifest_path = root.join(MANIFEST_PATH);
+    let sink = FileSink::new(&root, SinkMode::Force)
+        .map_err(GenerateError::file_write(&manifest_path))?
+        .with_policy(policy);
+    let views = Views::for_project(&root);
+    let view_context = template_context(&project, &database_data, &options);
+
+    write_frontend(&project, &options, &sink, &views, &view_context)
+        .map_err(GenerateError::file_write(&project.package_json))?;
+    let has_layout = Path::new(&project.header_section).exists()
+        || Path::new(&project.navbar_component).exists();
+    if has_layout {
+        write_layout(&project, &sink, &views, &view_context);
+    }
+
+    sink.save_manifest()
+        .map_err(GenerateError::file_write(&manifest_path))?;
+
+    info!("Frontend regenerated!");
+    Ok(sink.created_paths())
+}
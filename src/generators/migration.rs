@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::DatabaseType;
+use tracing::info;
+
+use crate::filesystem::FileSystem;
+use crate::generators::{database_type_name, migration_directory};
+use crate::rustyroad_toml::{find_project_root_on, read_on};
+use crate::writers::write_new_file;
+use crate::GenerateError;
+
+/// Generates an empty migration named `name` in the project on `fs` that
+/// contains `current_dir`, for changes written by hand
+/// Creates `<timestamp>_<name>` in the migrations directory of
+/// rustyroad.toml with up.sql and down.sql holding
+/// only a header comment
+/// Returns the paths of both files
+pub fn generate_migration(
+    fs: &dyn FileSystem,
+    current_dir: &Path,
+    name: &str,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    validate_migration_name(name)?;
+
+    let root = find_project_root_on(fs, current_dir)?;
+    let config = read_on(fs, &root)?;
+    let database_type = config.database.to_database()?.database_type;
+    if let DatabaseType::Mongo = database_type {
+        return Err(GenerateError::UnsupportedDatabase {
+            generator: "migration",
+            database: database_type_name(&database_type),
+        });
+    }
+
+    let migrations_dir = config.generator.migrations_path(&root);
+    let migration_dir = migration_directory(&migrations_dir, name);
+    let up_path = migration_dir.join("up.sql");
+    let down_path = migration_dir.join("down.sql");
+
+    write_new_file(
+        fs,
+        &up_path,
+        &migration_header(name, "Applies", "rustyroad migrate"),
+    )?;
+    write_new_file(
+        fs,
+        &down_path,
+        &migration_header(name, "Reverts", "rustyroad migrate down"),
+    )?;
+
+    info!("Migration {name} created!");
+
+    Ok(vec![up_path, down_path])
+}
+
+/// Checks that `name` is a snake_case name such as `add_index_to_posts`
+fn validate_migration_name(name: &str) -> Result<(), GenerateError> {
+    let invalid = |reason: &str| GenerateError::InvalidName {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    };
+
+    if name.is_empty() {
+        return Err(invalid("name is empty"));
+    }
+    if !name.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return Err(invalid(
+            "must start with a lowercase letter, e.g. `add_index_to_posts`",
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(invalid(
+            "may only contain lowercase letters, digits and `_`",
+        ));
+    }
+    Ok(())
+}
+
+/// The comment an empty migration file starts with
+fn migration_header(name: &str, verb: &str, command: &str) -> String {
+    format!("-- Migration: {name}\n-- {verb} the change, run by `{command}`\n\n")
+}
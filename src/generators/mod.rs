@@ -0,0 +1,281 @@
+//! Generators that add code to an existing RustyRoad project
+//! Every generator locates the project root through rustyroad.toml, so they
+//! can be run from any directory inside the project
+
+pub mod api_resource;
+pub mod controller;
+pub mod field;
+pub mod frontend;
+pub mod migration;
+pub mod model;
+pub mod openapi;
+pub mod route;
+pub mod scaffold;
+
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::DatabaseType;
+
+use crate::filesystem::FileSystem;
+use crate::manifest::{Generated, Manifest, MANIFEST_PATH};
+use crate::GenerateError;
+
+pub use api_resource::generate_api_resource;
+pub use controller::generate_controller;
+pub use field::{Field, FieldType};
+pub use frontend::generate_frontend;
+pub use migration::generate_migration;
+pub use model::generate_model;
+pub use openapi::generate_openapi;
+pub use route::generate_route;
+pub use scaffold::generate_scaffold;
+
+/// Checks that `name` is a CamelCase type name such as `Post` or `BlogPost`
+pub fn validate_resource_name(name: &str) -> Result<(), GenerateError> {
+    let invalid = |reason: &str| GenerateError::InvalidName {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let first = name
+        .chars()
+        .next()
+        .ok_or_else(|| invalid("name is empty"))?;
+    if !first.is_ascii_uppercase() {
+        return Err(invalid("must start with an uppercase letter, e.g. `Post`"));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(invalid("may only contain letters and digits"));
+    }
+    let snake = to_snake_case(name);
+    if is_rust_keyword(&snake) || name == "Self" {
+        return Err(invalid(&format!(
+            "`{snake}` is a Rust keyword and can't name the generated module"
+        )));
+    }
+    Ok(())
+}
+
+/// Strict and reserved keywords of Rust 2021, plus `gen` reserved in 2024
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Whether `word` can't be used as a plain identifier in generated code
+pub(crate) fn is_rust_keyword(word: &str) -> bool {
+    RUST_KEYWORDS.contains(&word)
+}
+
+/// Converts a CamelCase name into snake_case, `BlogPost` becomes `blog_post`
+/// A run of capitals is one word, `HTTPServer` becomes `http_server`
+pub fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if previous.is_ascii_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_ascii_uppercase() && next_is_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+/// Naive English pluralization used for table and route names
+/// `post` becomes `posts`, `category` becomes `categories`, `box` becomes `boxes`
+pub fn pluralize(word: &str) -> String {
+    if let Some(stem) = word.strip_suffix('y') {
+        if !stem.ends_with(['a', 'e', 'i', 'o', 'u']) {
+            return format!("{stem}ies");
+        }
+    }
+    if word.ends_with('s') || word.ends_with('x') || word.ends_with("ch") || word.ends_with("sh") {
+        return format!("{word}es");
+    }
+    format!("{word}s")
+}
+
+/// Human readable name of a database type for messages
+pub fn database_type_name(database_type: &DatabaseType) -> &'static str {
+    match database_type {
+        DatabaseType::Sqlite => "SQLite",
+        DatabaseType::Postgres => "PostgreSQL",
+        DatabaseType::Mysql => "MySQL",
+        DatabaseType::Mongo => "MongoDB",
+    }
+}
+
+/// Directory for a new migration, `<migrations_dir>/<timestamp>_<name>`
+/// The UTC timestamp is zero padded so directory names sort in creation order
+pub fn migration_directory(migrations_dir: &Path, name: &str) -> PathBuf {
+    let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
+    migrations_dir.join(format!("{timestamp}_{name}"))
+}
+
+/// A file the generator changed in place, with its content from before
+/// Empty when the generator created it
+pub(crate) struct Edit {
+    pub path: PathBuf,
+    pub before: String,
+}
+
+impl Edit {
+    /// Remembers the current content of `path` on `fs` before it is changed
+    pub fn of(fs: &dyn FileSystem, path: &Path) -> Self {
+        Edit {
+            path: path.to_path_buf(),
+            before: fs.read_to_string(path).unwrap_or_default(),
+        }
+    }
+}
+
+/// Records in the manifest of the project at `root` what `generator` made
+/// for `name`, so `rustyroad destroy` can remove it again
+/// Of the `edited` files only the lines the generator added are recorded
+pub(crate) fn record_generated(
+    fs: &dyn FileSystem,
+    root: &Path,
+    generator: &str,
+    name: &str,
+    created: &[PathBuf],
+    edited: &[Edit],
+) -> Result<(), GenerateError> {
+    let manifest_path = root.join(MANIFEST_PATH);
+    let mut manifest = Manifest::load(fs, root).map_err(|source| GenerateError::FileRead {
+        path: manifest_path.clone(),
+        source,
+    })?;
+    let read = |path: &Path| {
+        fs.read(path).map_err(|source| GenerateError::FileRead {
+            path: path.to_path_buf(),
+            source,
+        })
+    };
+
+    let mut generated = Generated::default();
+    for path in created {
+        let relative = relative_path(root, path);
+        manifest.record(&relative, &read(path)?);
+        generated.files.push(relative);
+    }
+    for edit in edited {
+        let after = String::from_utf8_lossy(&read(&edit.path)?).into_owned();
+        let added = added_lines(&edit.before, &after);
+        if !added.is_empty() {
+            generated
+                .lines
+                .insert(relative_path(root, &edit.path), added);
+        }
+    }
+
+    manifest
+        .generated
+        .insert(Manifest::generated_key(generator, name), generated);
+    manifest
+        .save(fs, root)
+        .map_err(GenerateError::file_write(manifest_path))
+}
+
+/// `path` relative to the project root, as the manifest stores it
+pub(crate) fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// The non-blank lines of `after` that `before` doesn't have
+/// A line that occurs more often in `after` counts as added once per extra
+/// occurrence
+fn added_lines(before: &str, after: &str) -> Vec<String> {
+    let mut existing: Vec<&str> = before.lines().collect();
+    let mut added = Vec::new();
+    for line in after.lines() {
+        match existing.iter().position(|old| *old == line) {
+            Some(index) => {
+                existing.remove(index);
+            }
+            None if !line.trim().is_empty() => added.push(line.to_string()),
+            None => {}
+        }
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::destroy::destroy;
+    use crate::filesystem::MemoryFileSystem;
+
+    const SQLITE_TOML: &str = r#"[database]
+database_name = "blog.db"
+database_type = "sqlite"
+"#;
+
+    #[test]
+    fn generates_and_destroys_in_memory() {
+        // Missing on disk, so a write that bypassed the memory filesystem
+        // would fail or leave the directory behind
+        let root = std::env::temp_dir().join(format!("rustyroad-memory-{}", std::process::id()));
+        let fs = MemoryFileSystem::new();
+        fs.create_dir(&root).unwrap();
+        fs.write(&root.join("rustyroad.toml"), SQLITE_TOML.as_bytes())
+            .unwrap();
+
+        let model = generate_model(&fs, &root, "Post", &["title:string".to_string()], &[]).unwrap();
+        let controller = generate_controller(&fs, &root, "Comment", &[]).unwrap();
+        for path in model.iter().chain(&controller) {
+            assert!(fs.is_file(path), "{} wasn't generated", path.display());
+        }
+        let routes_mod = fs.contents(root.join("src/routes/mod.rs")).unwrap();
+        assert_eq!(routes_mod, "pub mod comment;\npub use comment::*;\n");
+
+        destroy(&fs, &root, "model", "Post", false).unwrap();
+        assert!(!fs.exists(&root.join("src/models/post.rs")));
+        assert_eq!(
+            fs.contents(root.join("src/models/mod.rs")).as_deref(),
+            Some("")
+        );
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn snake_cases_camel_case_names() {
+        assert_eq!(to_snake_case("Post"), "post");
+        assert_eq!(to_snake_case("BlogPost"), "blog_post");
+        assert_eq!(to_snake_case("Post2Comment"), "post2_comment");
+    }
+
+    #[test]
+    fn keeps_acronym_runs_together() {
+        assert_eq!(to_snake_case("HTTPServer"), "http_server");
+        assert_eq!(to_snake_case("APIKey"), "api_key");
+        assert_eq!(to_snake_case("UserAPI"), "user_api");
+        assert_eq!(to_snake_case("URL"), "url");
+    }
+
+    #[test]
+    fn rejects_names_whose_module_is_a_keyword() {
+        assert!(validate_resource_name("BlogPost").is_ok());
+        for name in ["Type", "Match", "Self", "Struct"] {
+            assert!(
+                matches!(
+                    validate_resource_name(name),
+                    Err(GenerateError::InvalidName { .. })
+                ),
+                "{name} was accepted"
+            );
+        }
+    }
+}
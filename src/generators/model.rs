@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::DatabaseType;
+use tracing::info;
+
+use crate::database::qualified_table;
+use crate::filesystem::FileSystem;
+use crate::generators::field::parse_fields;
+use crate::generators::{
+    database_type_name, migration_directory, pluralize, record_generated, to_snake_case,
+    validate_resource_name, Edit, Field,
+};
+use crate::rustyroad_toml::{find_project_root_on, read_on};
+use crate::timestamps::{timestamp_columns, updated_at_statements};
+use crate::writers::models::{model_source, mongo_model_source, primary_key_sql};
+use crate::writers::{add_module, write_new_file};
+use crate::GenerateError;
+
+/// Generates a model named `name` with the given `name:type` fields in the
+/// project on `fs` that contains `current_dir`
+/// Creates a migration with paired up.sql/down.sql files, the model struct
+/// in src/models and registers the module in src/models/mod.rs, and
+/// records all of it in the manifest for `rustyroad destroy`
+/// MongoDB is schemaless, so its models get no migration but a document
+/// struct with collection functions, and `indexes` names the fields to
+/// create indexes on
+/// Returns the paths of every file it created or modified
+pub fn generate_model(
+    fs: &dyn FileSystem,
+    current_dir: &Path,
+    name: &str,
+    field_specs: &[String],
+    indexes: &[String],
+) -> Result<Vec<PathBuf>, GenerateError> {
+    validate_resource_name(name)?;
+    let fields = parse_fields(field_specs)?;
+    if let Some(field) = indexes
+        .iter()
+        .find(|index| !fields.iter().any(|field| field.name == **index))
+    {
+        return Err(GenerateError::UnknownIndexField {
+            field: field.clone(),
+        });
+    }
+
+    let root = find_project_root_on(fs, current_dir)?;
+    let config = read_on(fs, &root)?;
+    let section = config.database;
+    let database_type = section.to_database()?.database_type;
+
+    let module = to_snake_case(name);
+    let table = pluralize(&module);
+    if let DatabaseType::Mongo = database_type {
+        return generate_mongo_model(fs, &root, name, &module, &table, &fields, indexes);
+    }
+    if !indexes.is_empty() {
+        return Err(GenerateError::IndexWithoutMongo {
+            database: database_type_name(&database_type),
+        });
+    }
+
+    let sql_table = qualified_table(
+        &database_type,
+        &section.postgres,
+        &section.tables.table(&table),
+    );
+
+    let migration_dir = migration_directory(
+        &config.generator.migrations_path(&root),
+        &format!("create_{table}"),
+    );
+    let up_path = migration_dir.join("up.sql");
+    let down_path = migration_dir.join("down.sql");
+    let model_path = root.join("src").join("models").join(format!("{module}.rs"));
+    let models_mod = root.join("src").join("models").join("mod.rs");
+
+    // Check for collisions up front so we don't leave a migration behind
+    // for a model that was never written
+    if fs.exists(&model_path) {
+        return Err(GenerateError::AlreadyExists { path: model_path });
+    }
+
+    write_new_file(
+        fs,
+        &up_path,
+        &create_table_sql(&sql_table, &fields, &database_type),
+    )?;
+    write_new_file(
+        fs,
+        &down_path,
+        &format!("DROP TABLE IF EXISTS {sql_table};\n"),
+    )?;
+    write_new_file(
+        fs,
+        &model_path,
+        &model_source(name, &fields, &database_type),
+    )?;
+    let models_mod_edit = Edit::of(fs, &models_mod);
+    add_module(fs, &models_mod, &module)?;
+
+    record_generated(
+        fs,
+        &root,
+        "model",
+        name,
+        &[up_path.clone(), down_path.clone(), model_path.clone()],
+        &[models_mod_edit],
+    )?;
+    info!("Model {name} created!");
+
+    Ok(vec![up_path, down_path, model_path, models_mod])
+}
+
+/// Generates the MongoDB model `name`, a document of the `collection`
+/// collection, without a migration
+fn generate_mongo_model(
+    fs: &dyn FileSystem,
+    root: &Path,
+    name: &str,
+    module: &str,
+    collection: &str,
+    fields: &[Field],
+    indexes: &[String],
+) -> Result<Vec<PathBuf>, GenerateError> {
+    let model_path = root.join("src").join("models").join(format!("{module}.rs"));
+    let models_mod = root.join("src").join("models").join("mod.rs");
+
+    write_new_file(
+        fs,
+        &model_path,
+        &mongo_model_source(name, collection, fields, indexes),
+    )?;
+    let models_mod_edit = Edit::of(fs, &models_mod);
+    add_module(fs, &models_mod, module)?;
+
+    record_generated(
+        fs,
+        root,
+        "model",
+        name,
+        std::slice::from_ref(&model_path),
+        &[models_mod_edit],
+    )?;
+    info!("Model {name} created!");
+    if !indexes.is_empty() {
+        info!("Call {name}::create_indexes on startup to create its indexes");
+    }
+
+    Ok(vec![model_path, models_mod])
+}
+
+/// The `CREATE TABLE` statement for a generated model, with `created_at`
+/// and `updated_at` columns and what keeps `updated_at` current
+pub fn create_table_sql(table: &str, fields: &[Field], database_type: &DatabaseType) -> String {
+    let mut columns = vec![primary_key_sql(database_type).to_string()];
+    columns.extend(fields.iter().map(|field| {
+        format!(
+            "{} {} NOT NULL",
+            field.name,
+            field.field_type.sql_type(database_type)
+        )
+    }));
+    columns.extend(timestamp_columns(database_type));
+
+    let mut sql = format!(
+        "CREATE TABLE {table} (\n    {}\n);\n",
+        columns.join(",\n    ")
+    );
+    for statement in updated_at_statements(table, database_type) {
+        sql.push_str(&format!("\n{statement};\n"));
+    }
+    sql
+}
@@ -0,0 +1,466 @@
+//! Describes the routes of an existing project as an OpenAPI document
+//! Routes and models are read back from the generated sources, so the
+//! document follows the conventions the controller, model and scaffold
+//! generators write code in
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_yaml::{Mapping, Value};
+use tracing::info;
+
+use crate::framework::Framework;
+use crate::generators::{pluralize, to_snake_case};
+use crate::layout::{package_name, project_at, project_file, routes_dir};
+use crate::rustyroad_toml::{self, find_project_root};
+use crate::GenerateError;
+
+/// The file the document is written to, in the project root
+pub const OPENAPI_YAML: &str = "openapi.yaml";
+
+/// The HTTP methods a route can be declared with
+const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+/// Writes openapi.yaml describing every route of the project containing
+/// `current_dir` and the models its controllers read and write
+/// The file is regenerated from scratch each time, so it should not be edited
+/// Returns the path of the written file
+pub fn generate_openapi(current_dir: &Path) -> Result<Vec<PathBuf>, GenerateError> {
+    let root = find_project_root(current_dir)?;
+    let settings = rustyroad_toml::read(&root)?.generator;
+    let project = project_at(&root);
+
+    let models = read_models(&root.join("src").join("models"))?;
+    let mut routes = Vec::new();
+    for (module, source) in read_sources(&routes_dir(&project))? {
+        routes.extend(parse_routes(&module, &source, settings.framework));
+    }
+
+    let document = document(package_name(&project), &routes, &models);
+    let yaml = serde_yaml::to_string(&document)
+        .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why));
+
+    let path = project_file(&project, OPENAPI_YAML);
+    yaml.and_then(|yaml| fs::write(&path, yaml))
+        .map_err(GenerateError::file_write(&path))?;
+
+    info!(
+        "Described {} route(s) and {} model(s) in {OPENAPI_YAML}",
+        routes.len(),
+        models.len()
+    );
+
+    Ok(vec![path])
+}
+
+/// A model struct read from src/models
+struct Model {
+    name: String,
+    /// The fields with their Rust types, in declaration order
+    fields: Vec<(String, String)>,
+}
+
+impl Model {
+    /// The controller module the scaffold generates for this model
+    fn table(&self) -> String {
+        pluralize(&to_snake_case(&self.name))
+    }
+}
+
+/// A single route read from a controller
+struct Route {
+    /// The controller module, e.g. `posts`
+    module: String,
+    method: String,
+    /// The path in OpenAPI form, with `{id}` marking parameters
+    path: String,
+    handler: String,
+    /// Whether the handler renders a template instead of answering with JSON
+    html: bool,
+}
+
+/// Reads every `.rs` file in `dir` except mod.rs, keyed by module name
+/// A missing directory yields no sources
+fn read_sources(dir: &Path) -> Result<Vec<(String, String)>, GenerateError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(GenerateError::FileRead {
+                path: dir.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rs"))
+        .filter(|path| path.file_stem().is_some_and(|stem| stem != "mod"))
+        .collect();
+    // Keep the document stable between runs
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let source = fs::read_to_string(&path).map_err(|source| GenerateError::FileRead {
+                path: path.clone(),
+                source,
+            })?;
+            let module = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            Ok((module, source))
+        })
+        .collect()
+}
+
+/// Reads the `pub struct` declarations of every model module
+fn read_models(models_dir: &Path) -> Result<Vec<Model>, GenerateError> {
+    Ok(read_sources(models_dir)?
+        .iter()
+        .flat_map(|(_, source)| parse_models(source))
+        .collect())
+}
+
+/// Parses the `pub struct Name { pub field: Type, }` declarations in `source`
+fn parse_models(source: &str) -> Vec<Model> {
+    let mut models = Vec::new();
+    let mut current: Option<Model> = None;
+
+    for line in source.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("pub struct ") {
+            if let Some(name) = rest.strip_suffix('{') {
+                current = Some(Model {
+                    name: name.trim().to_string(),
+                    fields: Vec::new(),
+                });
+            }
+        } else if line == "}" {
+            models.extend(current.take());
+        } else if let Some(model) = current.as_mut() {
+            let field = line
+                .strip_prefix("pub ")
+                .and_then(|field| field.strip_suffix(','))
+                .and_then(|field| field.split_once(':'));
+            if let Some((name, rust_type)) = field {
+                model
+                    .fields
+                    .push((name.trim().to_string(), rust_type.trim().to_string()));
+            }
+        }
+    }
+
+    models
+}
+
+/// Parses the routes a controller declares
+/// Actix controllers declare them as `#[get("/path")]` attributes on the
+/// handlers, Axum controllers as `.route("/path", get(handler))` calls
+fn parse_routes(module: &str, source: &str, framework: Framework) -> Vec<Route> {
+    let html = source.contains("render(");
+    let route = |method: &str, path: &str, handler: &str| Route {
+        module: module.to_string(),
+        method: method.to_string(),
+        path: openapi_path(path),
+        handler: handler.to_string(),
+        html,
+    };
+
+    let mut routes = Vec::new();
+    match framework {
+        Framework::Actix => {
+            let mut pending: Option<(&str, &str)> = None;
+            for line in source.lines().map(str::trim) {
+                if let Some(attribute) = line.strip_prefix("#[") {
+                    pending = attribute.split_once("(\"").and_then(|(method, rest)| {
+                        let path = rest.split('"').next()?;
+                        METHODS.contains(&method).then_some((method, path))
+                    });
+                } else if let Some((method, path)) = pending {
+                    if let Some(signature) = line.split("fn ").nth(1) {
+                        let handler = signature.split('(').next().unwrap_or_default();
+                        routes.push(route(method, path, handler));
+                        pending = None;
+                    }
+                }
+            }
+        }
+        Framework::Axum => {
+            for line in source.lines().map(str::trim) {
+                let Some(rest) = line.strip_prefix(".route(\"") else {
+                    continue;
+                };
+                let Some((path, handlers)) = rest.split_once('"') else {
+                    continue;
+                };
+                for call in handlers.split('.') {
+                    let call = call.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+                    if let Some((method, arguments)) = call.split_once('(') {
+                        let handler = arguments.split(')').next().unwrap_or_default();
+                        if METHODS.contains(&method) {
+                            routes.push(route(method, path, handler));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    routes
+}
+
+/// Converts Axum's `:id` path parameters into OpenAPI's `{id}`
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(parameter) => format!("{{{parameter}}}"),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Builds a mapping from key and value pairs, keeping their order
+fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Mapping(
+        entries
+            .into_iter()
+            .map(|(key, value)| (Value::from(key), value))
+            .collect(),
+    )
+}
+
+/// A reference to a schema in `components`
+fn schema_ref(name: &str) -> Value {
+    map([("$ref", Value::from(format!("#/components/schemas/{name}")))])
+}
+
+/// The whole OpenAPI document
+fn document(title: &str, routes: &[Route], models: &[Model]) -> Value {
+    let mut paths = Mapping::new();
+    for route in routes {
+        let model = models.iter().find(|model| model.table() == route.module);
+        let entry = paths
+            .entry(Value::from(route.path.as_str()))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+        if let Value::Mapping(operations) = entry {
+            operations.insert(Value::from(route.method.as_str()), operation(route, model));
+        }
+    }
+
+    let mut schemas = Mapping::new();
+    for model in models {
+        schemas.insert(Value::from(model.name.as_str()), model_schema(model, true));
+        schemas.insert(
+            Value::from(format!("{}Input", model.name)),
+            model_schema(model, false),
+        );
+    }
+
+    map([
+        ("openapi", Value::from("3.0.3")),
+        (
+            "info",
+            map([
+                ("title", Value::from(title)),
+                ("version", Value::from("0.1.0")),
+            ]),
+        ),
+        ("paths", Value::Mapping(paths)),
+        ("components", map([("schemas", Value::Mapping(schemas))])),
+    ])
+}
+
+/// The operation of a single route
+/// The seven REST actions get request and response bodies built from the
+/// controller's model, every other handler is described by its route only
+fn operation(route: &Route, model: Option<&Model>) -> Value {
+    let mut operation = Mapping::new();
+    operation.insert(
+        "operationId".into(),
+        format!("{}_{}", route.module, route.handler).into(),
+    );
+    operation.insert(
+        "tags".into(),
+        Value::Sequence(vec![route.module.as_str().into()]),
+    );
+
+    if route.path.contains("{id}") {
+        operation.insert(
+            "parameters".into(),
+            Value::Sequence(vec![map([
+                ("name", Value::from("id")),
+                ("in", Value::from("path")),
+                ("required", Value::from(true)),
+                ("schema", map([("type", Value::from("integer"))])),
+            ])]),
+        );
+    }
+
+    let writes = matches!(route.handler.as_str(), "create" | "update");
+    if let (true, Some(model)) = (writes, model) {
+        // HTML forms post url encoded fields, JSON handlers take a JSON body
+        let content_type = if route.html {
+            "application/x-www-form-urlencoded"
+        } else {
+            "application/json"
+        };
+        operation.insert(
+            "requestBody".into(),
+            map([
+                ("required", Value::from(true)),
+                (
+                    "content",
+                    map([(
+                        content_type,
+                        map([("schema", schema_ref(&format!("{}Input", model.name)))]),
+                    )]),
+                ),
+            ]),
+        );
+    }
+
+    operation.insert("responses".into(), responses(route, model));
+    Value::Mapping(operation)
+}
+
+/// The responses of a route
+fn responses(route: &Route, model: Option<&Model>) -> Value {
+    let not_found = || {
+        (
+            "404",
+            map([("description", Value::from("No record with this id"))]),
+        )
+    };
+
+    if route.html {
+        let redirects = matches!(route.handler.as_str(), "create" | "update" | "destroy")
+            && route.method == "post";
+        if redirects {
+            return map([(
+                "303",
+                map([("description", Value::from("Redirects to the updated page"))]),
+            )]);
+        }
+
+        let page = (
+            "200",
+            map([
+                ("description", Value::from("The rendered page")),
+                (
+                    "content",
+                    map([(
+                        "text/html",
+                        map([("schema", map([("type", Value::from("string"))]))]),
+                    )]),
+                ),
+            ]),
+        );
+        return match route.handler.as_str() {
+            "show" | "edit" if model.is_some() => map([page, not_found()]),
+            _ => map([page]),
+        };
+    }
+
+    let json = |description: &str, schema: Value| {
+        map([
+            ("description", Value::from(description)),
+            (
+                "content",
+                map([("application/json", map([("schema", schema)]))]),
+            ),
+        ])
+    };
+    match (route.handler.as_str(), model) {
+        ("index", Some(model)) => map([(
+            "200",
+            json(
+                "Every record",
+                map([
+                    ("type", Value::from("array")),
+                    ("items", schema_ref(&model.name)),
+                ]),
+            ),
+        )]),
+        ("show" | "update", Some(model)) => map([
+            ("200", json("The record", schema_ref(&model.name))),
+            not_found(),
+        ]),
+        ("create", Some(model)) => {
+            map([("201", json("The created record", schema_ref(&model.name)))])
+        }
+        ("destroy", Some(_)) => map([
+            (
+                "204",
+                map([("description", Value::from("The record was deleted"))]),
+            ),
+            not_found(),
+        ]),
+        _ => map([(
+            "200",
+            json("Success", map([("type", Value::from("object"))])),
+        )]),
+    }
+}
+
+/// The schema of a model, without its `id` when describing request input
+fn model_schema(model: &Model, with_id: bool) -> Value {
+    let mut properties = Mapping::new();
+    let mut required = Vec::new();
+    for (name, rust_type) in &model.fields {
+        if name == "id" && !with_id {
+            continue;
+        }
+        let (schema, optional) = type_schema(rust_type);
+        if !optional {
+            required.push(Value::from(name.as_str()));
+        }
+        properties.insert(Value::from(name.as_str()), schema);
+    }
+
+    map([
+        ("type", Value::from("object")),
+        ("required", Value::Sequence(required)),
+        ("properties", Value::Mapping(properties)),
+    ])
+}
+
+/// The schema of a Rust field type and whether the field may be missing
+fn type_schema(rust_type: &str) -> (Value, bool) {
+    if let Some(inner) = rust_type
+        .strip_prefix("Option<")
+        .and_then(|inner| inner.strip_suffix('>'))
+    {
+        let (schema, _) = type_schema(inner);
+        if let Value::Mapping(mut schema) = schema {
+            schema.insert("nullable".into(), true.into());
+            return (Value::Mapping(schema), true);
+        }
+        return (schema, true);
+    }
+
+    let typed = |schema_type: &str, format: Option<&str>| match format {
+        Some(format) => map([
+            ("type", Value::from(schema_type)),
+            ("format", Value::from(format)),
+        ]),
+        None => map([("type", Value::from(schema_type))]),
+    };
+    let schema = match rust_type.rsplit("::").next().unwrap_or(rust_type) {
+        "String" | "str" => typed("string", None),
+        "i8" | "i16" | "i32" | "u8" | "u16" | "u32" => typed("integer", Some("int32")),
+        "i64" | "u64" | "isize" | "usize" => typed("integer", Some("int64")),
+        "f32" => typed("number", Some("float")),
+        "f64" => typed("number", Some("double")),
+        "bool" => typed("boolean", None),
+        "NaiveDate" => typed("string", Some("date")),
+        "NaiveDateTime" => typed("string", Some("date-time")),
+        // Types the generators don't produce are left unconstrained
+        _ => Value::Mapping(Mapping::new()),
+    };
+    (schema, false)
+}
@@ -0,0 +1,192 @@
+//! Generates a single page route, lighter than a controller
+//! `generate route /docs/getting-started` writes a handler rendering
+//! `docs/getting-started.html.tera` in the route module
+//! `docs_getting_started` and the template stub, extending the base layout
+
+use std::path::{Path, PathBuf};
+
+use tera::Context;
+use tracing::info;
+
+use crate::css::CssFramework;
+use crate::filesystem::FileSystem;
+use crate::framework::Framework;
+use crate::layout::{project_at, routes_dir, template_name};
+use crate::rustyroad_toml::{find_project_root_on, read_on};
+use crate::views::Views;
+use crate::writers::{actix, axum, write_new_file};
+use crate::GenerateError;
+
+/// Generates a route serving the page at `path` in the project on `fs`
+/// that contains `current_dir`
+/// Creates a route module with one handler rendering the page's template,
+/// the template stub and registers the module in routes/mod.rs
+/// A stub is taken from `route/page.html.tera` in the view overrides when
+/// there is one
+/// Returns the paths of every file it created or modified
+pub fn generate_route(
+    fs: &dyn FileSystem,
+    current_dir: &Path,
+    path: &str,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    let segments = parse_route_path(path)?;
+
+    let root = find_project_root_on(fs, current_dir)?;
+    let settings = read_on(fs, &root)?.generator;
+    if !settings.frontend {
+        return Err(GenerateError::RequiresFrontend { generator: "route" });
+    }
+    let project = project_at(&root);
+
+    let module = segments.join("_").replace('-', "_");
+    let template = format!("{}.html.tera", segments.join("/"));
+    let path = format!("/{}", segments.join("/"));
+
+    let routes_mod = PathBuf::from(&project.routes_module);
+    let route_path = routes_dir(&project).join(format!("{module}.rs"));
+    let template_path = Path::new(&project.templates).join(&template);
+    for existing in [&route_path, &template_path] {
+        if fs.exists(existing) {
+            return Err(GenerateError::AlreadyExists {
+                path: existing.clone(),
+            });
+        }
+    }
+
+    let source = match settings.framework {
+        Framework::Actix => actix_route_source(&path, &template),
+        Framework::Axum => axum_route_source(&path, &template),
+    };
+    write_new_file(fs, &route_path, &source)?;
+
+    let title = page_title(segments.last().expect("a route path has segments"));
+    let mut context = Context::new();
+    context.insert("path", &path);
+    context.insert("title", &title);
+    context.insert("template", &template);
+    context.insert("base", template_name(&project, &project.base_html));
+    let stub =
+        Views::for_project(&root).render_template("route/page.html.tera", &context, || {
+            page_template_source(
+                template_name(&project, &project.base_html),
+                &title,
+                &template,
+                settings.css,
+            )
+        })?;
+    // Nested paths get nested template directories
+    write_new_file(fs, &template_path, &stub)?;
+
+    match settings.framework {
+        Framework::Actix => actix::register_route_module(fs, &routes_mod, &module)?,
+        Framework::Axum => axum::register_route_module(fs, &routes_mod, &module)?,
+    }
+
+    info!("Route {path} created!");
+
+    Ok(vec![route_path, template_path, routes_mod])
+}
+
+/// Splits a route path such as `/docs/getting-started` into its segments
+/// Every segment has to start with a lowercase letter followed by lowercase
+/// letters, digits, `-` and `_`, so the path maps onto a module name
+fn parse_route_path(path: &str) -> Result<Vec<&str>, GenerateError> {
+    let invalid = |reason: &str| GenerateError::InvalidName {
+        name: path.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let segments: Vec<&str> = path
+        .strip_prefix('/')
+        .ok_or_else(|| invalid("route paths start with `/`, e.g. `/about`"))?
+        .trim_end_matches('/')
+        .split('/')
+        .collect();
+
+    for segment in &segments {
+        let valid = segment.starts_with(|c: char| c.is_ascii_lowercase())
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid {
+            return Err(invalid(
+                "segments must be lowercase words separated by `-` or `_`, e.g. `/docs/getting-started`",
+            ));
+        }
+    }
+    Ok(segments)
+}
+
+/// The page title for the last segment of a route, `getting-started`
+/// becomes `Getting started`
+fn page_title(segment: &str) -> String {
+    let words = segment.replace(['-', '_'], " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// The Rust source of an Actix route module rendering `template` at `path`
+pub fn actix_route_source(path: &str, template: &str) -> String {
+    format!(
+        r#"use actix_web::{{get, web, HttpResponse, Responder}};
+use tera::{{Context, Tera}};
+
+/// Registers the routes of this module
+pub fn configure(cfg: &mut web::ServiceConfig) {{
+    cfg.service(page);
+}}
+
+#[get("{path}")]
+async fn page(tera: web::Data<Tera>) -> impl Responder {{
+    match tera.render("{template}", &Context::new()) {{
+        Ok(body) => HttpResponse::Ok().content_type("text/html").body(body),
+        Err(why) => HttpResponse::InternalServerError().body(why.to_string()),
+    }}
+}}
+"#
+    )
+}
+
+/// The Rust source of an Axum route module rendering `template` at `path`
+pub fn axum_route_source(path: &str, template: &str) -> String {
+    format!(
+        r#"use std::sync::Arc;
+
+use axum::extract::Extension;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use tera::{{Context, Tera}};
+
+use super::render;
+
+/// The routes of this module
+pub fn router() -> Router {{
+    Router::new().route("{path}", get(page))
+}}
+
+async fn page(Extension(tera): Extension<Arc<Tera>>) -> Response {{
+    render(&tera, "{template}", &Context::new())
+}}
+"#
+    )
+}
+
+/// A Tera template stub for a page, extending the project's base layout
+/// and styled with the classes of the project's CSS framework
+pub fn page_template_source(base: &str, title: &str, template: &str, css: CssFramework) -> String {
+    let heading_class = css.heading_class();
+    let text_class = css.text_class();
+
+    format!(
+        "{{% extends \"{base}\" %}}\n\n\
+         {{% block title %}}{title}{{% endblock title %}}\n\n\
+         {{% block content %}}\n\
+         <h1 class=\"{heading_class}\">{title}</h1>\n\
+         <p class=\"{text_class}\">Find me in {template}</p>\n\
+         {{% endblock content %}}\n"
+    )
+}
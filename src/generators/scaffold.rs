@@ -0,0 +1,973 @@
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::DatabaseType;
+use rustyroad::Project;
+use serde::Serialize;
+use tera::Context;
+use tracing::{info, warn};
+
+use crate::css::CssFramework;
+use crate::database::{placeholder, qualified_table, PostgresSettings, TableSettings};
+use crate::filesystem::FileSystem;
+use crate::framework::Framework;
+use crate::generators::api_resource::{actix_api_source, axum_api_source, API_PREFIX};
+use crate::generators::field::parse_fields;
+use crate::generators::model::create_table_sql;
+use crate::generators::{
+    database_type_name, migration_directory, pluralize, record_generated, to_snake_case,
+    validate_resource_name, Edit, Field, FieldType,
+};
+use crate::layout::{pool_module, project_at, routes_dir, template_name};
+use crate::overwrite::OverwritePolicy;
+use crate::prompt::{can_answer, confirm};
+use crate::rustyroad_toml::{find_project_root_on, read_on, RustyRoadToml};
+use crate::views::Views;
+use crate::writers::models::model_source;
+use crate::writers::pool::pool_source;
+use crate::writers::settings::config_module_path;
+use crate::writers::{actix, add_module, axum, declare_module, write_new_file};
+use crate::GenerateError;
+
+/// Generates a complete CRUD resource named `name` with the given
+/// `name:type` fields in the project on `fs` that contains `current_dir`
+/// Creates the migration, the model, a controller whose seven REST actions
+/// read and write the table and the templates of its pages, and registers
+/// the new modules, recording all of it in the manifest for `rustyroad destroy`
+/// Files that already exist are treated as `policy` says
+/// Either everything is generated or, if any step fails, every file written
+/// so far is removed and every modified file restored
+/// Returns the paths of every file it created or modified
+pub fn generate_scaffold(
+    fs: &dyn FileSystem,
+    current_dir: &Path,
+    name: &str,
+    field_specs: &[String],
+    policy: OverwritePolicy,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    generate_resource(fs, current_dir, name, field_specs, policy, Responses::Html)
+}
+
+/// What the controller of a generated resource answers with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Responses {
+    /// Rendered pages and form posts, `generate scaffold`
+    Html,
+    /// JSON below `/api`, `generate api-resource`
+    Json,
+}
+
+impl Responses {
+    /// The generator the resource is recorded under in the manifest
+    fn generator(self) -> &'static str {
+        match self {
+            Responses::Html => "scaffold",
+            Responses::Json => "api-resource",
+        }
+    }
+}
+
+/// Generates the migration, the model and the controller of a resource,
+/// with templates for `Responses::Html`, see `generate_scaffold`
+pub(super) fn generate_resource(
+    fs: &dyn FileSystem,
+    current_dir: &Path,
+    name: &str,
+    field_specs: &[String],
+    policy: OverwritePolicy,
+    responses: Responses,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    let generator = responses.generator();
+    validate_resource_name(name)?;
+    let fields = parse_fields(field_specs)?;
+    if fields.is_empty() {
+        return Err(GenerateError::InvalidField {
+            spec: String::new(),
+            reason: format!("{generator} needs at least one field"),
+        });
+    }
+
+    let root = find_project_root_on(fs, current_dir)?;
+    let config = read_on(fs, &root)?;
+    let database_type = config.database.to_database()?.database_type;
+    if let DatabaseType::Mongo = database_type {
+        return Err(GenerateError::UnsupportedDatabase {
+            generator,
+            database: database_type_name(&database_type),
+        });
+    }
+    if responses == Responses::Html && !config.generator.frontend {
+        return Err(GenerateError::RequiresFrontend { generator });
+    }
+
+    let resource = Resource::new(
+        name,
+        fields,
+        &database_type,
+        &config.database.postgres,
+        &config.database.tables,
+    );
+    let project = project_at(&root);
+
+    // Check for collisions up front so nothing has to be rolled back for them
+    if policy == OverwritePolicy::Abort {
+        for path in [
+            resource.model_path(&root),
+            resource.controller_path(&project),
+        ] {
+            if fs.exists(&path) {
+                return Err(GenerateError::AlreadyExists { path });
+            }
+        }
+    }
+
+    let mut changes = Changes::new(fs, policy);
+    match write_resource(
+        &mut changes,
+        &root,
+        &project,
+        &config,
+        &database_type,
+        &resource,
+        responses,
+    ) {
+        Ok(()) => {
+            info!("Generated {generator} {name}");
+            Ok(changes.paths())
+        }
+        Err(why) => {
+            warn!("Removing the partially generated {generator} {name}");
+            changes.rollback();
+            Err(why)
+        }
+    }
+}
+
+/// The names a scaffold derives from the resource name
+pub(super) struct Resource {
+    /// The model struct, e.g. `BlogPost`
+    pub name: String,
+    /// The model module, e.g. `blog_post`
+    pub module: String,
+    /// The table, the controller module and the URL prefix, e.g. `blog_posts`
+    pub table: String,
+    /// The table as the SQL refers to it, with the table prefix of the
+    /// project and the Postgres schema outside `public`
+    pub sql_table: String,
+    pub fields: Vec<Field>,
+}
+
+impl Resource {
+    fn new(
+        name: &str,
+        fields: Vec<Field>,
+        database_type: &DatabaseType,
+        postgres: &PostgresSettings,
+        tables: &TableSettings,
+    ) -> Self {
+        let module = to_snake_case(name);
+        let table = pluralize(&module);
+        let sql_table = qualified_table(database_type, postgres, &tables.table(&table));
+        Resource {
+            name: name.to_string(),
+            module,
+            table,
+            sql_table,
+            fields,
+        }
+    }
+
+    fn model_path(&self, root: &Path) -> PathBuf {
+        root.join("src")
+            .join("models")
+            .join(format!("{}.rs", self.module))
+    }
+
+    fn controller_path(&self, project: &Project) -> PathBuf {
+        routes_dir(project).join(format!("{}.rs", self.table))
+    }
+
+    /// The struct the create and update forms are deserialized into
+    pub fn form_name(&self) -> String {
+        format!("{}Form", self.name)
+    }
+
+    fn column_list(&self) -> String {
+        self.fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Writes every piece of the resource onto the filesystem of `changes`,
+/// recording each change in it
+fn write_resource(
+    changes: &mut Changes,
+    root: &Path,
+    project: &Project,
+    config: &RustyRoadToml,
+    database_type: &DatabaseType,
+    resource: &Resource,
+    responses: Responses,
+) -> Result<(), GenerateError> {
+    let fs = changes.fs;
+    let settings = &config.generator;
+
+    // The migration
+    let migration_dir = migration_directory(
+        &settings.migrations_path(root),
+        &format!("create_{}", resource.table),
+    );
+    changes.create(
+        &migration_dir.join("up.sql"),
+        &create_table_sql(&resource.sql_table, &resource.fields, database_type),
+    )?;
+    changes.create(
+        &migration_dir.join("down.sql"),
+        &format!("DROP TABLE IF EXISTS {};\n", resource.sql_table),
+    )?;
+
+    // The model
+    changes.create(
+        &resource.model_path(root),
+        &model_source(&resource.name, &resource.fields, database_type),
+    )?;
+    let models_mod = root.join("src").join("models").join("mod.rs");
+    changes.modify(&models_mod, || {
+        add_module(fs, &models_mod, &resource.module)
+    })?;
+
+    // The pool the handlers query through, older projects don't have one
+    let pool_path = pool_module(project);
+    if !fs.exists(&pool_path) {
+        let config_module = fs.exists(&config_module_path(project));
+        if let Some(source) = pool_source(database_type, &config.database.pool, config_module) {
+            changes.create(&pool_path, &source)?;
+        }
+    }
+    let main_rs = PathBuf::from(&project.main_rs);
+    for module in ["models", "pool"] {
+        changes.modify(&main_rs, || declare_module(fs, &main_rs, module))?;
+    }
+
+    // The controller
+    let controller = match (responses, settings.framework) {
+        (Responses::Html, Framework::Actix) => actix_scaffold_source(resource, database_type),
+        (Responses::Html, Framework::Axum) => axum_scaffold_source(resource, database_type),
+        (Responses::Json, Framework::Actix) => actix_api_source(resource, database_type),
+        (Responses::Json, Framework::Axum) => axum_api_source(resource, database_type),
+    };
+    changes.create(&resource.controller_path(project), &controller)?;
+
+    // Actix controllers of API resources scope their routes below `/api`
+    // themselves, Axum ones are nested there
+    let routes_mod = PathBuf::from(&project.routes_module);
+    changes.modify(&routes_mod, || match (responses, settings.framework) {
+        (_, Framework::Actix) => actix::register_route_module(fs, &routes_mod, &resource.table),
+        (Responses::Html, Framework::Axum) => {
+            axum::register_route_module(fs, &routes_mod, &resource.table)
+        }
+        (Responses::Json, Framework::Axum) => {
+            axum::nest_route_module(fs, &routes_mod, &resource.table, API_PREFIX)
+        }
+    })?;
+
+    // The templates, overridable with `scaffold/<page>.html.tera`
+    if responses == Responses::Html {
+        let views = Views::for_project(root);
+        let mut context = view_context(project, resource);
+        let templates_dir = Path::new(&project.templates).join(&resource.table);
+        for (page, builtin) in template_sources(project, resource, settings.css) {
+            context.insert("page", page);
+            let source =
+                views
+                    .render_template(&format!("scaffold/{page}.html.tera"), &context, || builtin)?;
+            changes.create(&templates_dir.join(format!("{page}.html.tera")), &source)?;
+        }
+    }
+
+    // The pool and the declarations in main.rs are shared with the other
+    // resources, `rustyroad destroy` leaves them alone
+    let created: Vec<PathBuf> = changes
+        .created
+        .iter()
+        .filter(|path| **path != pool_path)
+        .cloned()
+        .collect();
+    let edited: Vec<Edit> = changes
+        .modified
+        .iter()
+        .filter(|(path, _)| *path != main_rs)
+        .map(|(path, before)| Edit {
+            path: path.clone(),
+            before: String::from_utf8_lossy(before.as_deref().unwrap_or_default()).into_owned(),
+        })
+        .collect();
+    record_generated(
+        fs,
+        root,
+        responses.generator(),
+        &resource.name,
+        &created,
+        &edited,
+    )
+}
+
+/// What a generator changed on `fs`, so a failure can undo all of it
+struct Changes<'a> {
+    fs: &'a dyn FileSystem,
+    created: Vec<PathBuf>,
+    /// Files changed in place, with their content from before the change
+    /// `None` when the change created the file
+    modified: Vec<(PathBuf, Option<Vec<u8>>)>,
+    /// How files that already exist are treated
+    policy: OverwritePolicy,
+}
+
+impl<'a> Changes<'a> {
+    /// No changes yet, existing files are treated as `policy` says
+    fn new(fs: &'a dyn FileSystem, policy: OverwritePolicy) -> Self {
+        Changes {
+            fs,
+            created: Vec::new(),
+            modified: Vec::new(),
+            policy,
+        }
+    }
+
+    /// Writes a new file, an existing one is treated as the policy says
+    fn create(&mut self, path: &Path, contents: &str) -> Result<(), GenerateError> {
+        if !self.fs.exists(path) || self.policy == OverwritePolicy::Abort {
+            write_new_file(self.fs, path, contents)?;
+            self.created.push(path.to_path_buf());
+            return Ok(());
+        }
+
+        let overwrite = match self.policy {
+            OverwritePolicy::Abort | OverwritePolicy::Skip => false,
+            OverwritePolicy::Overwrite => true,
+            OverwritePolicy::Prompt if can_answer() => {
+                confirm(&format!("{} already exists, overwrite it?", path.display()))
+                    .map_err(GenerateError::file_write(path))?
+            }
+            OverwritePolicy::Prompt => {
+                warn!("{} already exists", path.display());
+                false
+            }
+        };
+        if !overwrite {
+            info!("Skipped {}", path.display());
+            return Ok(());
+        }
+        let fs = self.fs;
+        self.modify(path, || {
+            fs.write(path, contents.as_bytes())
+                .map_err(GenerateError::file_write(path))
+        })
+    }
+
+    /// Runs `modify`, an edit of `path`, remembering the content beforehand
+    fn modify(
+        &mut self,
+        path: &Path,
+        modify: impl FnOnce() -> Result<(), GenerateError>,
+    ) -> Result<(), GenerateError> {
+        if !self.modified.iter().any(|(modified, _)| modified == path) {
+            self.modified
+                .push((path.to_path_buf(), self.fs.read(path).ok()));
+        }
+        modify()
+    }
+
+    /// Every created or modified path
+    fn paths(&self) -> Vec<PathBuf> {
+        self.created
+            .iter()
+            .chain(self.modified.iter().map(|(path, _)| path))
+            .cloned()
+            .collect()
+    }
+
+    /// Removes the created files and restores the modified ones
+    /// Directories left empty by the removal are removed as well
+    fn rollback(self) {
+        for path in self.created.iter().rev() {
+            if let Err(why) = self.fs.remove_file(path) {
+                warn!("Failed to remove {}: {why}", path.display());
+            }
+            if let Some(parent) = path.parent() {
+                // Only succeeds for empty directories
+                let _ = self.fs.remove_dir(parent);
+            }
+        }
+
+        for (path, original) in self.modified.into_iter().rev() {
+            let restored = match original {
+                Some(contents) => self.fs.write(&path, &contents),
+                None => self.fs.remove_file(&path),
+            };
+            if let Err(why) = restored {
+                warn!("Failed to restore {}: {why}", path.display());
+            }
+        }
+    }
+}
+
+/// The queries of the seven REST actions in the project's SQL dialect
+pub(super) struct Queries {
+    pub select_all: String,
+    pub select_one: String,
+    pub insert: String,
+    pub update: String,
+    pub delete: String,
+}
+
+impl Queries {
+    pub fn new(resource: &Resource, database_type: &DatabaseType) -> Self {
+        let table = &resource.sql_table;
+        let count = resource.fields.len();
+        let values = (1..=count)
+            .map(|index| placeholder(database_type, index))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let assignments = resource
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                format!("{} = {}", field.name, placeholder(database_type, index + 1))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let by_id = placeholder(database_type, 1);
+
+        Queries {
+            select_all: format!("SELECT * FROM {table} ORDER BY id"),
+            select_one: format!("SELECT * FROM {table} WHERE id = {by_id}"),
+            insert: format!(
+                "INSERT INTO {table} ({}) VALUES ({values})",
+                resource.column_list()
+            ),
+            update: format!(
+                "UPDATE {table} SET {assignments} WHERE id = {}",
+                placeholder(database_type, count + 1)
+            ),
+            delete: format!("DELETE FROM {table} WHERE id = {by_id}"),
+        }
+    }
+}
+
+/// The form struct the create and update actions deserialize
+/// Unchecked checkboxes aren't submitted, so booleans default to false
+pub(super) fn form_struct(resource: &Resource) -> String {
+    let mut source = format!(
+        "/// The fields submitted by the {} form\n#[derive(Deserialize)]\npub struct {} {{\n",
+        resource.name,
+        resource.form_name()
+    );
+    for field in &resource.fields {
+        if field.field_type == FieldType::Boolean {
+            source.push_str("    #[serde(default)]\n");
+        }
+        source.push_str(&format!(
+            "    pub {}: {},\n",
+            field.name,
+            field.field_type.rust_type()
+        ));
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// The `.bind(...)` calls binding every field of `form`
+pub(super) fn bind_fields(resource: &Resource, indent: &str) -> String {
+    resource
+        .fields
+        .iter()
+        .map(|field| format!("\n{indent}.bind(&form.{})", field.name))
+        .collect()
+}
+
+/// The Rust source of an Actix controller backed by the resource's table
+fn actix_scaffold_source(resource: &Resource, database_type: &DatabaseType) -> String {
+    let Resource {
+        name,
+        module,
+        table,
+        ..
+    } = resource;
+    let form = resource.form_name();
+    let queries = Queries::new(resource, database_type);
+    let binds = bind_fields(resource, "        ");
+
+    format!(
+        r#"use actix_web::{{get, post, web, HttpResponse}};
+use serde::Deserialize;
+use tera::{{Context, Tera}};
+
+use crate::models::{module}::{name};
+use crate::pool;
+
+{form_struct}
+/// Registers the routes of this controller
+pub fn configure(cfg: &mut web::ServiceConfig) {{
+    cfg.service(index);
+    cfg.service(new);
+    cfg.service(create);
+    cfg.service(show);
+    cfg.service(edit);
+    cfg.service(update);
+    cfg.service(destroy);
+}}
+
+#[get("/{table}")]
+async fn index(tera: web::Data<Tera>) -> HttpResponse {{
+    let records = sqlx::query_as::<_, {name}>("{select_all}")
+        .fetch_all(pool::shared())
+        .await;
+
+    match records {{
+        Ok(records) => {{
+            let mut context = Context::new();
+            context.insert("records", &records);
+            render(&tera, "{table}/index.html.tera", &context)
+        }}
+        Err(why) => HttpResponse::InternalServerError().body(why.to_string()),
+    }}
+}}
+
+#[get("/{table}/new")]
+async fn new(tera: web::Data<Tera>) -> HttpResponse {{
+    render(&tera, "{table}/new.html.tera", &Context::new())
+}}
+
+#[post("/{table}")]
+async fn create(form: web::Form<{form}>) -> HttpResponse {{
+    let result = sqlx::query("{insert}"){binds}
+        .execute(pool::shared())
+        .await;
+
+    match result {{
+        Ok(_) => redirect("/{table}"),
+        Err(why) => HttpResponse::InternalServerError().body(why.to_string()),
+    }}
+}}
+
+#[get("/{table}/{{id}}")]
+async fn show(tera: web::Data<Tera>, path: web::Path<i32>) -> HttpResponse {{
+    render_record(&tera, "{table}/show.html.tera", path.into_inner()).await
+}}
+
+#[get("/{table}/{{id}}/edit")]
+async fn edit(tera: web::Data<Tera>, path: web::Path<i32>) -> HttpResponse {{
+    render_record(&tera, "{table}/edit.html.tera", path.into_inner()).await
+}}
+
+#[post("/{table}/{{id}}")]
+async fn update(path: web::Path<i32>, form: web::Form<{form}>) -> HttpResponse {{
+    let id = path.into_inner();
+    let result = sqlx::query("{update}"){binds}
+        .bind(id)
+        .execute(pool::shared())
+        .await;
+
+    match result {{
+        Ok(_) => redirect(&format!("/{table}/{{id}}")),
+        Err(why) => HttpResponse::InternalServerError().body(why.to_string()),
+    }}
+}}
+
+#[post("/{table}/{{id}}/delete")]
+async fn destroy(path: web::Path<i32>) -> HttpResponse {{
+    let result = sqlx::query("{delete}")
+        .bind(path.into_inner())
+        .execute(pool::shared())
+        .await;
+
+    match result {{
+        Ok(_) => redirect("/{table}"),
+        Err(why) => HttpResponse::InternalServerError().body(why.to_string()),
+    }}
+}}
+
+/// Renders `template` with the record `id`, or answers 404 if there is none
+async fn render_record(tera: &Tera, template: &str, id: i32) -> HttpResponse {{
+    let record = sqlx::query_as::<_, {name}>("{select_one}")
+        .bind(id)
+        .fetch_optional(pool::shared())
+        .await;
+
+    match record {{
+        Ok(Some(record)) => {{
+            let mut context = Context::new();
+            context.insert("record", &record);
+            render(tera, template, &context)
+        }}
+        Ok(None) => HttpResponse::NotFound().body("Not found"),
+        Err(why) => HttpResponse::InternalServerError().body(why.to_string()),
+    }}
+}}
+
+fn redirect(location: &str) -> HttpResponse {{
+    HttpResponse::SeeOther()
+        .insert_header(("Location", location))
+        .finish()
+}}
+
+fn render(tera: &Tera, template: &str, context: &Context) -> HttpResponse {{
+    match tera.render(template, context) {{
+        Ok(body) => HttpResponse::Ok().content_type("text/html").body(body),
+        Err(why) => HttpResponse::InternalServerError().body(why.to_string()),
+    }}
+}}
+"#,
+        form_struct = form_struct(resource),
+        select_all = queries.select_all,
+        select_one = queries.select_one,
+        insert = queries.insert,
+        update = queries.update,
+        delete = queries.delete,
+    )
+}
+
+/// The Rust source of an Axum controller backed by the resource's table
+fn axum_scaffold_source(resource: &Resource, database_type: &DatabaseType) -> String {
+    let Resource {
+        name,
+        module,
+        table,
+        ..
+    } = resource;
+    let form = resource.form_name();
+    let queries = Queries::new(resource, database_type);
+    let binds = bind_fields(resource, "        ");
+
+    format!(
+        r#"use std::sync::Arc;
+
+use axum::extract::{{Extension, Form, Path}};
+use axum::http::StatusCode;
+use axum::response::{{IntoResponse, Redirect, Response}};
+use axum::routing::{{get, post}};
+use axum::Router;
+use serde::Deserialize;
+use tera::{{Context, Tera}};
+
+use super::render;
+use crate::models::{module}::{name};
+use crate::pool;
+
+{form_struct}
+/// The routes of this controller
+pub fn router() -> Router {{
+    Router::new()
+        .route("/{table}", get(index).post(create))
+        .route("/{table}/new", get(new))
+        .route("/{table}/:id", get(show).post(update))
+        .route("/{table}/:id/edit", get(edit))
+        .route("/{table}/:id/delete", post(destroy))
+}}
+
+async fn index(Extension(tera): Extension<Arc<Tera>>) -> Response {{
+    let records = sqlx::query_as::<_, {name}>("{select_all}")
+        .fetch_all(pool::shared())
+        .await;
+
+    match records {{
+        Ok(records) => {{
+            let mut context = Context::new();
+            context.insert("records", &records);
+            render(&tera, "{table}/index.html.tera", &context)
+        }}
+        Err(why) => server_error(why),
+    }}
+}}
+
+async fn new(Extension(tera): Extension<Arc<Tera>>) -> Response {{
+    render(&tera, "{table}/new.html.tera", &Context::new())
+}}
+
+async fn create(Form(form): Form<{form}>) -> Response {{
+    let result = sqlx::query("{insert}"){binds}
+        .execute(pool::shared())
+        .await;
+
+    match result {{
+        Ok(_) => Redirect::to("/{table}").into_response(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+async fn show(Extension(tera): Extension<Arc<Tera>>, Path(id): Path<i32>) -> Response {{
+    render_record(&tera, "{table}/show.html.tera", id).await
+}}
+
+async fn edit(Extension(tera): Extension<Arc<Tera>>, Path(id): Path<i32>) -> Response {{
+    render_record(&tera, "{table}/edit.html.tera", id).await
+}}
+
+async fn update(Path(id): Path<i32>, Form(form): Form<{form}>) -> Response {{
+    let result = sqlx::query("{update}"){binds}
+        .bind(id)
+        .execute(pool::shared())
+        .await;
+
+    match result {{
+        Ok(_) => Redirect::to(&format!("/{table}/{{id}}")).into_response(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+async fn destroy(Path(id): Path<i32>) -> Response {{
+    let result = sqlx::query("{delete}")
+        .bind(id)
+        .execute(pool::shared())
+        .await;
+
+    match result {{
+        Ok(_) => Redirect::to("/{table}").into_response(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+/// Renders `template` with the record `id`, or answers 404 if there is none
+async fn render_record(tera: &Tera, template: &str, id: i32) -> Response {{
+    let record = sqlx::query_as::<_, {name}>("{select_one}")
+        .bind(id)
+        .fetch_optional(pool::shared())
+        .await;
+
+    match record {{
+        Ok(Some(record)) => {{
+            let mut context = Context::new();
+            context.insert("record", &record);
+            render(tera, template, &context)
+        }}
+        Ok(None) => (StatusCode::NOT_FOUND, "Not found").into_response(),
+        Err(why) => server_error(why),
+    }}
+}}
+
+fn server_error(why: sqlx::Error) -> Response {{
+    (StatusCode::INTERNAL_SERVER_ERROR, why.to_string()).into_response()
+}}
+"#,
+        form_struct = form_struct(resource),
+        select_all = queries.select_all,
+        select_one = queries.select_one,
+        insert = queries.insert,
+        update = queries.update,
+        delete = queries.delete,
+    )
+}
+
+/// The variables available to rendered view overrides of the scaffold
+fn view_context(project: &Project, resource: &Resource) -> Context {
+    #[derive(Serialize)]
+    struct FieldView<'a> {
+        name: &'a str,
+        label: String,
+        rust_type: &'static str,
+    }
+
+    let fields: Vec<FieldView> = resource
+        .fields
+        .iter()
+        .map(|field| FieldView {
+            name: &field.name,
+            label: label(&field.name),
+            rust_type: field.field_type.rust_type(),
+        })
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("name", &resource.name);
+    context.insert("module", &resource.module);
+    context.insert("table", &resource.table);
+    context.insert("base", template_name(project, &project.base_html));
+    context.insert("fields", &fields);
+    context
+}
+
+/// The Tera templates of the four pages, keyed by page name
+/// Values are escaped explicitly since Tera only autoescapes `.html` files
+fn template_sources(
+    project: &Project,
+    resource: &Resource,
+    css: CssFramework,
+) -> [(&'static str, String); 4] {
+    let base = template_name(project, &project.base_html);
+    let Resource { name, table, .. } = resource;
+    let heading_class = css.heading_class();
+    let page = |title: &str, content: String| {
+        format!(
+            "{{% extends \"{base}\" %}}\n\n\
+             {{% block title %}}{title}{{% endblock title %}}\n\n\
+             {{% block content %}}\n{content}{{% endblock content %}}\n"
+        )
+    };
+
+    let headers: String = resource
+        .fields
+        .iter()
+        .map(|field| {
+            format!(
+                "      <th class=\"px-4 py-2 text-left\">{}</th>\n",
+                label(&field.name)
+            )
+        })
+        .collect();
+    let cells: String = resource
+        .fields
+        .iter()
+        .map(|field| {
+            format!(
+                "      <td class=\"px-4 py-2\">{{{{ record.{} | escape }}}}</td>\n",
+                field.name
+            )
+        })
+        .collect();
+    let columns = resource.fields.len() + 1;
+    let index = page(
+        &label(table),
+        format!(
+            "<div class=\"flex items-center justify-between\">\n  \
+             <h1 class=\"{heading_class}\">{title}</h1>\n  \
+             <a href=\"/{table}/new\" class=\"rounded bg-blue-600 px-4 py-2 text-white\">New {name}</a>\n\
+             </div>\n\n\
+             <table class=\"mt-6 min-w-full divide-y divide-gray-200\">\n  \
+             <thead>\n    <tr>\n{headers}      <th></th>\n    </tr>\n  </thead>\n  \
+             <tbody class=\"divide-y divide-gray-100\">\n  \
+             {{% for record in records %}}\n    <tr>\n{cells}      \
+             <td class=\"px-4 py-2 text-right\">\n        \
+             <a href=\"/{table}/{{{{ record.id }}}}\" class=\"text-blue-600\">Show</a>\n        \
+             <a href=\"/{table}/{{{{ record.id }}}}/edit\" class=\"ml-2 text-blue-600\">Edit</a>\n        \
+             <form action=\"/{table}/{{{{ record.id }}}}/delete\" method=\"post\" class=\"inline\">\n          \
+             <button type=\"submit\" class=\"ml-2 text-red-600\">Delete</button>\n        \
+             </form>\n      </td>\n    </tr>\n  {{% endfor %}}\n  \
+             {{% if records | length == 0 %}}\n    \
+             <tr><td class=\"px-4 py-2 text-gray-500\" colspan=\"{columns}\">No {table} yet</td></tr>\n  \
+             {{% endif %}}\n  </tbody>\n</table>\n",
+            title = label(table),
+        ),
+    );
+
+    let details: String = resource
+        .fields
+        .iter()
+        .map(|field| {
+            format!(
+                "  <dt class=\"font-semibold\">{}</dt>\n  \
+                 <dd class=\"mb-4\">{{{{ record.{} | escape }}}}</dd>\n",
+                label(&field.name),
+                field.name
+            )
+        })
+        .collect();
+    let show = page(
+        name,
+        format!(
+            "<h1 class=\"{heading_class}\">{name} {{{{ record.id }}}}</h1>\n\n\
+             <dl class=\"mt-6\">\n{details}</dl>\n\n\
+             <a href=\"/{table}/{{{{ record.id }}}}/edit\" class=\"text-blue-600\">Edit</a>\n\
+             <a href=\"/{table}\" class=\"ml-2 text-blue-600\">Back</a>\n"
+        ),
+    );
+
+    let new = page(
+        &format!("New {name}"),
+        format!(
+            "<h1 class=\"{heading_class}\">New {name}</h1>\n\n{}\n\
+             <a href=\"/{table}\" class=\"text-blue-600\">Back</a>\n",
+            form_html(resource, &format!("/{table}"), false)
+        ),
+    );
+
+    let edit = page(
+        &format!("Edit {name}"),
+        format!(
+            "<h1 class=\"{heading_class}\">Edit {name}</h1>\n\n{}\n\
+             <a href=\"/{table}/{{{{ record.id }}}}\" class=\"text-blue-600\">Show</a>\n\
+             <a href=\"/{table}\" class=\"ml-2 text-blue-600\">Back</a>\n",
+            form_html(resource, &format!("/{table}/{{{{ record.id }}}}"), true)
+        ),
+    );
+
+    [
+        ("index", index),
+        ("show", show),
+        ("new", new),
+        ("edit", edit),
+    ]
+}
+
+/// The form of the new and edit pages, filled from `record` when editing
+fn form_html(resource: &Resource, action: &str, filled: bool) -> String {
+    let mut html = format!("<form action=\"{action}\" method=\"post\" class=\"mt-6 space-y-4\">\n");
+
+    for field in &resource.fields {
+        let name = &field.name;
+        let value = if filled {
+            format!(" value=\"{{{{ record.{name} | escape }}}}\"")
+        } else {
+            String::new()
+        };
+        let input_class = "mt-1 w-full rounded border px-3 py-2";
+
+        let input = match field.field_type {
+            FieldType::Text => {
+                let content = if filled {
+                    format!("{{{{ record.{name} | escape }}}}")
+                } else {
+                    String::new()
+                };
+                format!(
+                    "<textarea id=\"{name}\" name=\"{name}\" rows=\"5\" class=\"{input_class}\">{content}</textarea>"
+                )
+            }
+            FieldType::Boolean => {
+                let checked = if filled {
+                    format!("{{% if record.{name} %}} checked{{% endif %}}")
+                } else {
+                    String::new()
+                };
+                format!("<input id=\"{name}\" type=\"checkbox\" name=\"{name}\" value=\"true\"{checked}>")
+            }
+            other => {
+                let attributes = match other {
+                    FieldType::Integer | FieldType::BigInteger => "type=\"number\" step=\"1\"",
+                    FieldType::Float => "type=\"number\" step=\"any\"",
+                    FieldType::Date => "type=\"date\"",
+                    // Seconds are needed to parse the value back
+                    FieldType::DateTime => "type=\"datetime-local\" step=\"1\"",
+                    _ => "type=\"text\"",
+                };
+                format!(
+                    "<input id=\"{name}\" {attributes} name=\"{name}\"{value} class=\"{input_class}\">"
+                )
+            }
+        };
+
+        html.push_str(&format!(
+            "  <div>\n    <label for=\"{name}\" class=\"block font-semibold\">{}</label>\n    {input}\n  </div>\n",
+            label(name)
+        ));
+    }
+
+    html.push_str(
+        "  <button type=\"submit\" class=\"rounded bg-blue-600 px-4 py-2 text-white\">Save</button>\n</form>\n",
+    );
+    html
+}
+
+/// A human readable label for a snake_case name, `published_at` becomes
+/// `Published at`
+fn label(name: &str) -> String {
+    let spaced = name.replace('_', " ");
+    let mut chars = spaced.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => spaced,
+    }
+}
@@ -0,0 +1,76 @@
+//! Initializes a git repository in a freshly generated project and clones
+//! template repositories
+
+use std::path::Path;
+use std::process::Command;
+
+use tracing::{info, warn};
+
+use crate::tools::{find_tool, require_tool};
+use crate::CreateProjectError;
+
+/// The message of the commit made by `init_repository`
+pub const INITIAL_COMMIT_MESSAGE: &str = "Initial RustyRoad project";
+
+/// Runs `git init`, stages every file and makes the initial commit in `dir`
+/// Does nothing, apart from printing a warning, when git isn't installed or
+/// `dir` already is inside a git work tree
+pub fn init_repository(dir: &Path) -> Result<(), CreateProjectError> {
+    if find_tool("git").is_none() {
+        warn!("git is not installed, skipping git init");
+        return Ok(());
+    }
+
+    match git(dir, &["rev-parse", "--is-inside-work-tree"]) {
+        Ok(output) if output.trim() == "true" => {
+            warn!(
+                "{} is already inside a git repository, skipping git init",
+                dir.display()
+            );
+            return Ok(());
+        }
+        // Outside of a work tree rev-parse fails, which is what we want
+        Ok(_) | Err(CreateProjectError::CommandFailed { .. }) => {}
+        Err(why) => return Err(why),
+    }
+
+    git(dir, &["init"])?;
+    git(dir, &["add", "--all"])?;
+    git(dir, &["commit", "--message", INITIAL_COMMIT_MESSAGE])?;
+
+    info!("Initialized a git repository in {}", dir.display());
+
+    Ok(())
+}
+
+/// Clones the repository at `url` into `destination` without its history
+pub fn clone_repository(url: &str, destination: &Path) -> Result<(), CreateProjectError> {
+    let destination = destination.display().to_string();
+    git(
+        Path::new("."),
+        &["clone", "--depth", "1", url, &destination],
+    )?;
+    Ok(())
+}
+
+/// Runs git with `args` in `dir` and returns what it printed
+fn git(dir: &Path, args: &[&str]) -> Result<String, CreateProjectError> {
+    let program = require_tool("git")?;
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|source| CreateProjectError::Process {
+            program: "git".to_string(),
+            source,
+        })?;
+
+    if !output.status.success() {
+        return Err(CreateProjectError::CommandFailed {
+            command: format!("git {}", args.join(" ")),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
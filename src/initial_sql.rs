@@ -0,0 +1,151 @@
+//! The SQL a new project's database is set up with
+//! The upstream loader returns the statements as strings. They are adapted
+//! to the project here and labelled, so a failing statement is reported as
+//! e.g. `creating the users table` instead of its raw SQL
+
+use rustyroad::database::Database;
+use rustyroad::writers::initial_sql_loader;
+use rustyroad::Project;
+
+use crate::cockroach;
+use crate::database::PostgresSettings;
+use crate::migrate::{self, created_table};
+use crate::timestamps;
+use crate::user_indexes::unique_login_indexes;
+use crate::writers::password::hash_password_column;
+
+/// The in-memory SQLite database the upstream loader runs its SQL against
+const SQLITE_MEMORY: &str = "sqlite::memory:";
+
+/// A statement of the initial schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlStatement {
+    /// The SQL sent to the server
+    pub sql: String,
+    /// What the statement does, e.g. `creating the users table`
+    pub label: String,
+    /// Whether running the statement again leaves the database unchanged
+    pub idempotent: bool,
+}
+
+impl SqlStatement {
+    /// Labels `sql` by the object it creates or the table it changes
+    pub fn new(sql: impl Into<String>) -> Self {
+        let sql = sql.into();
+        let upper = sql.trim_start().to_ascii_uppercase();
+        let idempotent = upper.contains("IF NOT EXISTS")
+            || upper.contains("IF EXISTS")
+            || upper.starts_with("CREATE OR REPLACE ");
+
+        SqlStatement {
+            label: label(&sql),
+            sql,
+            idempotent,
+        }
+    }
+}
+
+/// Loads the initial schema of `project` in the dialect of `database_data`
+/// Timestamps are kept current, users get a column for password hashes
+/// and unique indexes on the columns they log in with,
+/// CockroachDB gets its variant of the SQL, tables are prefixed with
+/// `prefix` and every `CREATE` can run again against a partially set up
+/// database
+pub async fn load_initial_sql(
+    project: &Project,
+    database_data: &Database,
+    postgres: &PostgresSettings,
+    prefix: &str,
+) -> Result<Vec<SqlStatement>, sqlx::Error> {
+    // The upstream loader also runs the SQLite statements itself, unadapted,
+    // against the dev database. It gets a throwaway one instead, the adapted
+    // statements are run by the caller
+    let loader_project = Project {
+        config_dev_db: SQLITE_MEMORY.to_string(),
+        ..project.clone()
+    };
+    let statements =
+        initial_sql_loader::load_sql_for_new_project(&loader_project, database_data.clone())
+            .await?;
+    // CockroachDB drops the PL/pgSQL triggers again
+    let statements = timestamps::auto_update_timestamps(statements, &database_data.database_type);
+    let statements = hash_password_column(statements, &database_data.database_type);
+    let statements = if postgres.is_cockroachdb() {
+        cockroach::to_cockroachdb(statements)
+    } else {
+        statements
+    };
+    let statements = migrate::with_table_prefix(statements, prefix);
+    // Named after the prefixed table, so projects sharing a schema don't
+    // collide on the index name
+    let statements = unique_login_indexes(
+        statements,
+        &database_data.database_type,
+        &format!("{prefix}users"),
+    );
+    // A previous run may have been interrupted after creating some tables
+    let statements = migrate::if_not_exists(statements, &database_data.database_type);
+
+    Ok(statements.into_iter().map(SqlStatement::new).collect())
+}
+
+/// The SQL of `statements`, for the migration files written from them
+pub fn sql_of(statements: &[SqlStatement]) -> Vec<String> {
+    statements
+        .iter()
+        .map(|statement| statement.sql.clone())
+        .collect()
+}
+
+/// What `sql` does, `running a SQL statement` when it isn't recognized
+fn label(sql: &str) -> String {
+    if let Some(table) = created_table(sql) {
+        return format!("creating the {table} table");
+    }
+
+    let words: Vec<&str> = sql.split_whitespace().collect();
+    let keyword = |index: usize| words.get(index).map(|word| word.to_ascii_uppercase());
+    let name = |index: usize| {
+        words.get(index).map(|word| {
+            word.split('(')
+                .next()
+                .unwrap_or(word)
+                .trim_matches(|c| c == '"' || c == '`' || c == ';')
+                .to_string()
+        })
+    };
+    // The name after `CREATE [OR REPLACE] [UNIQUE] <kind> [IF NOT EXISTS]`
+    let created = |mut index: usize| {
+        if keyword(index).as_deref() == Some("IF") {
+            index += 3;
+        }
+        name(index)
+    };
+
+    let mut index = 1;
+    match keyword(0).as_deref() {
+        Some("CREATE") => {
+            if keyword(index).as_deref() == Some("OR") {
+                index += 2;
+            }
+            if keyword(index).as_deref() == Some("UNIQUE") {
+                index += 1;
+            }
+            match (keyword(index), created(index + 1)) {
+                (Some(kind), Some(name)) => {
+                    format!("creating the {name} {}", kind.to_ascii_lowercase())
+                }
+                _ => "running a SQL statement".to_string(),
+            }
+        }
+        Some("INSERT") if keyword(1).as_deref() == Some("INTO") => match name(2) {
+            Some(table) => format!("inserting into {table}"),
+            None => "running a SQL statement".to_string(),
+        },
+        Some("ALTER") if keyword(1).as_deref() == Some("TABLE") => match name(2) {
+            Some(table) => format!("altering the {table} table"),
+            None => "running a SQL statement".to_string(),
+        },
+        _ => "running a SQL statement".to_string(),
+    }
+}
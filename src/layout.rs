@@ -0,0 +1,124 @@
+//! Helpers for locating the files of a generated project
+//! The upstream `Project` builds every path as `<name>/<relative path>`, so
+//! opening a project by its root directory yields absolute paths
+
+use std::path::{Component, Path, PathBuf};
+
+use rustyroad::writers::new;
+use rustyroad::Project;
+
+use crate::CreateProjectError;
+
+/// The directory migrations are kept in unless rustyroad.toml names another
+pub const DEFAULT_MIGRATIONS_DIR: &str = "migrations";
+
+/// Opens the layout of the existing project rooted at `root`
+/// Every path field of the returned `Project` points inside `root`
+pub fn project_at(root: &Path) -> Project {
+    new(root.display().to_string())
+}
+
+/// A file at `relative` inside the project directory
+pub fn project_file(project: &Project, relative: &str) -> PathBuf {
+    Path::new(&project.name).join(relative)
+}
+
+/// The path of `path` relative to the project directory
+/// `blog/src/views/pages/index.html.tera` becomes `src/views/pages/index.html.tera`
+pub fn relative_to_project<'a>(project: &Project, path: &'a str) -> &'a str {
+    path.strip_prefix(&project.name)
+        .map(|rest| rest.trim_start_matches('/'))
+        .unwrap_or(path)
+}
+
+/// The name Tera knows a template by, its path relative to the templates directory
+/// `blog/src/views/pages/index.html.tera` becomes `pages/index.html.tera`
+pub fn template_name<'a>(project: &Project, path: &'a str) -> &'a str {
+    path.strip_prefix(&project.templates)
+        .map(|rest| rest.trim_start_matches('/'))
+        .unwrap_or(path)
+}
+
+/// The Cargo package name, the last component of the project directory
+/// It is also the name of the release binary
+pub fn package_name(project: &Project) -> &str {
+    Path::new(&project.name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(&project.name)
+}
+
+/// Checks that a migrations directory such as `db/migrate` is a relative
+/// path that stays inside the project
+pub fn parse_migrations_dir(value: &str) -> Result<String, CreateProjectError> {
+    let invalid = |reason| CreateProjectError::InvalidMigrationsDir {
+        value: value.to_string(),
+        reason,
+    };
+
+    let path = Path::new(value);
+    if value.is_empty() {
+        return Err(invalid("it is empty"));
+    }
+    if path.is_absolute() {
+        return Err(invalid("it must be relative to the project root"));
+    }
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(invalid("it must not leave the project directory"));
+    }
+    Ok(value.trim_end_matches('/').to_string())
+}
+
+/// The module of the generated application that sets up the database pool
+pub fn pool_module(project: &Project) -> PathBuf {
+    project_file(project, "src/pool.rs")
+}
+
+/// The directory holding the route modules
+pub fn routes_dir(project: &Project) -> PathBuf {
+    Path::new(&project.routes_module)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// The files that make up the frontend of a project, skipped by `--no-frontend`
+/// The templates and Tailwind, PostCSS and npm setup
+pub fn frontend_files(project: &Project) -> [&str; 11] {
+    [
+        &project.package_json,
+        &project.index_js,
+        &project.index_html,
+        &project.base_html,
+        &project.tailwind_css,
+        &project.tailwind_config,
+        &project.postcss_config,
+        &project.header_section,
+        &project.navbar_component,
+        &project.dashboard_page_html,
+        &project.login_page_html,
+    ]
+}
+
+/// The login page and the dashboard behind it, only kept for session auth
+pub fn login_files(project: &Project) -> [&str; 2] {
+    [&project.dashboard_page_html, &project.login_page_html]
+}
+
+/// The header and navbar partials, skipped with `no_layout`
+pub fn layout_files(project: &Project) -> [&str; 2] {
+    [&project.header_section, &project.navbar_component]
+}
+
+/// The Tailwind, PostCSS and npm files, skipped for the other CSS frameworks
+pub fn tailwind_files(project: &Project) -> [&str; 4] {
+    [
+        &project.package_json,
+        &project.tailwind_css,
+        &project.tailwind_config,
+        &project.postcss_config,
+    ]
+}
@@ -1,342 +1,1530 @@
-use std::env;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
 
 use rustyroad::database::*;
 use rustyroad::generators::create_directory;
 use rustyroad::writers::create_files;
-use rustyroad::writers::new;
 use rustyroad::Project;
+use sqlx::Connection;
+use tracing::{debug, error, info, warn};
+
+use crate::database::{
+    connect_with_retry, create_database, database_url_with_tls, is_sqlite_memory,
+    minimum_server_version, qualified_table, server_version, sqlite_location, use_schema,
+    with_retry, ConnectionTarget, ServerVersion,
+};
+use crate::progress::{planned_steps, Progress, Step};
+use crate::resume::ResumeState;
+use crate::rustyroad_toml::GeneratorSettings;
+use crate::sink::WriteJob;
+use crate::views::Views;
+use crate::workspace::Workspace;
+
+pub mod auth;
+pub mod builder;
+pub mod ci;
+pub mod cockroach;
+pub mod config;
+pub mod console;
+pub mod credentials;
+pub mod css;
+pub mod database;
+pub mod db;
+pub mod dependency;
+pub mod destroy;
+pub mod doctor;
+pub mod error;
+pub mod example;
+pub mod filesystem;
+pub mod framework;
+pub mod generators;
+pub mod git;
+pub mod initial_sql;
+pub mod layout;
+pub mod manifest;
+pub mod migrate;
+#[cfg(feature = "mongo")]
+pub mod mongo;
+pub mod options;
+pub mod overwrite;
+pub mod progress;
+pub mod project_name;
+pub mod prompt;
+pub mod resume;
+pub mod rustyroad_toml;
+pub mod seed;
+pub mod session;
+pub mod sink;
+pub mod tasks;
+pub mod template;
+pub mod timestamps;
+pub mod tools;
+pub mod user_indexes;
+pub mod verify;
+pub mod views;
+pub mod workspace;
+pub mod writers;
+
+pub use auth::AuthScheme;
+pub use builder::ProjectBuilder;
+pub use ci::CiProvider;
+pub use credentials::PartialDatabase;
+pub use css::CssFramework;
+pub use database::DatabaseBuilder;
+pub use dependency::Dependency;
+pub use error::{
+    ConfigError, ConsoleError, CreateProjectError, DatabaseConfigError, DbError, GenerateError,
+    MigrateError, SeedError,
+};
+pub use example::Example;
+pub use filesystem::{FileSystem, MemoryFileSystem, RealFileSystem};
+pub use framework::Framework;
+pub use options::NewProjectOptions;
+pub use overwrite::OverwritePolicy;
+pub use rustyroad_toml::load_from_rustyroad_toml;
+pub use session::SessionStore;
+pub use sink::{FileSink, SinkMode};
+pub use tasks::TaskRunner;
+pub use template::TemplateSource;
+
+/// A generated project together with what was written for it
+#[derive(Clone)]
+pub struct CreatedProject {
+    pub project: Project,
+    /// Every file and directory written, in the order they were written
+    /// A dry run lists the paths it would have written
+    pub created: Vec<PathBuf>,
+}
 
 /// Creates a new project
 /// Takes an optional name <String> and db_type <String>
 /// If no name is provided, it will default to "rustyroad"
 /// If a name is provided, it will create a new directory with that name
 /// and create a new project in that directory
-/// If a directory with the same name already exists, it will return an error
-/// and ask the user to choose a different name
+/// The directory is created in `options.parent_dir` when set, which must be
+/// an existing writable directory, and in the current directory otherwise
+/// The name must be a valid Cargo package name, otherwise
+/// `CreateProjectError::InvalidName` is returned before anything is written
+/// If a directory with the same name already exists, it will return
+/// `CreateProjectError::DirectoryExists` and ask the user to choose a different name
 /// If a db_type is provided, it will create a new database with that type
 /// If no db_type is provided, it will default to "sqlite"
 /// If a db_type is provided that is not supported, it will return an error
 /// and ask the user to choose a different db_type
-/// Allow unused variables because the db_type is not used yet
-#[allow(unused_variables)]
-pub async fn create_new_project(name: String, database_data: Database) -> Result<Project, Error> {
-    // If name is provided, create a new directory with that name
-    // If no name is provided, run the rest of the code in the function
-    // write the database data to the rustyroad.toml file
-
-    // Create new project with name
-    let mut project = new(name);
-
-    // Create the project directory
-    create_directory(&project).unwrap_or_else(|why| {
-        println!("Couldn't create directory: {:?}", why.kind());
-    });
+/// Postgres and MySQL servers are connected to before any file is written,
+/// so wrong credentials leave nothing behind
+/// Any failure is reported as a `CreateProjectError` instead of panicking
+/// If generation fails after the project directory was created, the directory
+/// is removed again so a retry starts from a clean slate, unless
+/// `options.keep_on_failure` is set or the directory existed beforehand
+/// With `options.dry_run` nothing is written and no database is touched,
+/// the paths that would be written are printed instead
+/// A thin wrapper over `ProjectBuilder` for the CLI
+pub async fn create_new_project(
+    name: String,
+    database_data: Database,
+    options: NewProjectOptions,
+) -> Result<CreatedProject, CreateProjectError> {
+    ProjectBuilder::new()
+        .name(name)
+        .database(database_data)
+        .options(options)
+        .build()
+        .await
+}
 
-    // Create the files
-    create_files(&project).unwrap_or_else(|why| {
-        panic!("Couldn't create files: {:?}", why.kind());
-    });
-
-    // Write to rustyroad.toml file
-    Project::write_to_rustyroad_toml(&project, &database_data)
-        .expect("Failed to write to rustyroad.toml");
+/// Generates the project files and sets up the database
+/// Split out of `ProjectBuilder::build` so that any error can be caught in one
+/// place and the partially generated directory rolled back
+pub(crate) async fn generate_project(
+    mut project: Project,
+    database_data: Database,
+    options: &NewProjectOptions,
+) -> Result<CreatedProject, CreateProjectError> {
+    // `--force` on an existing project regenerates its files in place
+    let mode = if options.dry_run {
+        SinkMode::DryRun
+    } else if options.overwrite != OverwritePolicy::Abort && Path::new(&project.name).is_dir() {
+        SinkMode::Force
+    } else {
+        SinkMode::Disk
+    };
+    // Only runs writing to disk record their steps, `--resume` picks up
+    // the ones a failed run recorded
+    let state_path = layout::project_file(&project, resume::STATE_FILE);
+    let resume = match mode {
+        SinkMode::Disk if options.resume => ResumeState::load(Path::new(&project.name))
+            .map_err(CreateProjectError::file_write(&state_path))?,
+        SinkMode::Disk => ResumeState::new(Path::new(&project.name)),
+        SinkMode::DryRun | SinkMode::Force => ResumeState::disabled(),
+    };
+    if resume.is_resuming() {
+        info!("Resuming the generation of {}", &project.name);
+    }
 
-    // Write to the cargo.toml file
-    rustyroad::writers::write_to_cargo_toml(&project, &database_data)
-        .expect("Failed to write to cargo.toml");
+    // Ask before anything is written, declining leaves no files behind
+    if options.confirm_database && mode == SinkMode::Disk && !resume.is_complete(Step::Database) {
+        confirm_database_setup(&database_data)?;
+    }
+    // Whether the project joins an enclosing Cargo workspace decides how
+    // Cargo.toml is written, so it is asked before anything is written too
+    let workspace = workspace::resolve_workspace(Path::new(&project.name), options.workspace)?;
+    // A wrong password or an unreachable server fails before anything is
+    // written instead of after the whole project tree
+    if mode == SinkMode::Disk && !resume.is_complete(Step::Database) {
+        check_database_connection(&database_data, options).await?;
+    }
 
-    // Write to main.rs file
-    rustyroad::writers::write_to_main_rs(&project).expect("Failed to write to main.rs");
+    let manifest_path = layout::project_file(&project, manifest::MANIFEST_PATH);
+    let sink = if resume.is_resuming() {
+        FileSink::resuming(&project.name)
+    } else {
+        FileSink::new(&project.name, mode).map(|sink| sink.with_policy(options.overwrite))
+    }
+    .map_err(CreateProjectError::file_write(&manifest_path))?;
+    // Regenerating prints diffs and asks before overwriting, which the
+    // counter would draw over
+    let mut steps = planned_steps(
+        &database_data,
+        options,
+        workspace.as_ref(),
+        sink.is_regenerating(),
+    );
+    steps.retain(|step| !resume.is_complete(*step));
+    let progress = Progress::new(steps, options.progress && mode == SinkMode::Disk);
 
-    // Write to package.json file
-    Project::write_to_package_json(&project).expect("Failed to write to package.json");
+    // Write the files of the project
+    let written = match &options.template {
+        TemplateSource::Builtin => write_builtin_template(
+            &project,
+            &database_data,
+            options,
+            workspace.as_ref(),
+            &sink,
+            &progress,
+            &resume,
+        ),
+        _ if resume.is_complete(Step::Template) => Ok(()),
+        source => {
+            progress.start(Step::Template);
+            template::scaffold(source, &project, &database_data, options, &sink)
+                .and_then(|()| complete_step(&resume, Step::Template))
+        }
+    };
+    // Files written before a failure stay recorded for `--resume`
+    if Path::new(&project.name).is_dir() {
+        sink.save_manifest()
+            .map_err(CreateProjectError::file_write(&manifest_path))?;
+    }
+    written?;
 
-    // Write to README.md file
-    Project::write_to_readme(&project).expect("Failed to write to README.md");
+    // A dry run never touches the database
+    if sink.is_dry_run() {
+        progress.finish();
+        info!(
+            "Would set up the {} database",
+            generators::database_type_name(&database_data.database_type)
+        );
+        if let Some(example) = options.example {
+            info!("Would add the {} example", example.name());
+        }
+        if let Some(workspace) = workspace.as_ref().filter(|workspace| workspace.join) {
+            info!(
+                "Would add the project to the workspace at {}",
+                workspace.manifest.display()
+            );
+        }
+        if options.git {
+            info!("Would initialize a git repository");
+        }
+        return Ok(CreatedProject {
+            project,
+            created: sink.created_paths(),
+        });
+    }
 
-    // Write to index.js file
-    Project::write_to_index_js(&project).unwrap_or_else(|why| {
-        println!("Failed to write to index.js: {:?}", why.kind());
-    });
-    // Write to index.html.tera file
-    rustyroad::writers::write_to_index_html(&project).unwrap_or_else(|why| {
-        println!("Failed to write to index.html: {:?}", why.kind());
-    });
-    // Write to base.html.tera file
-    rustyroad::writers::write_to_base_html(&project.base_html).unwrap_or_else(|why| {
-        println!("Failed to write to base.html: {:?}", why.kind());
-    });
+    // The database of a regenerated project is already set up
+    if sink.is_regenerating() {
+        progress.finish();
+        info!("Project {} regenerated!", &project.name);
+        return Ok(CreatedProject {
+            project,
+            created: sink.created_paths(),
+        });
+    }
 
-    // Write to tailwind.css file
-    Project::write_to_tailwind_css(&project).unwrap_or_else(|why| {
-        println!("Failed to write to tailwind.css: {:?}", why.kind());
-    });
-    // need to create the function
-    // Write to tailwind.config.js file
-    Project::write_to_tailwind_config(&project).unwrap_or_else(|why| {
-        println!("Failed to write to tailwind.config.js: {:?}", why.kind());
-    });
+    if !resume.is_complete(Step::Database) {
+        progress.start(Step::Database);
+        set_up_database(&mut project, &database_data, options, &sink).await?;
+        complete_step(&resume, Step::Database)?;
+    }
 
-    // Write to postcss.config.js file
-    Project::write_to_postcss_config(&project).unwrap_or_else(|why| {
-        println!("Failed to write to postcss.config.js: {:?}", why.kind());
-    });
+    // The example builds on the migrated database, a dry run only says so
+    if let Some(example) = options.example {
+        if !resume.is_complete(Step::Example) {
+            progress.start(Step::Example);
+            for path in example::add_example(Path::new(&project.name), example).await? {
+                sink.adopt(&path.display().to_string())
+                    .map_err(CreateProjectError::file_write(&path))?;
+            }
+            complete_step(&resume, Step::Example)?;
+        }
+    }
 
-    // Write to index.html route
-    rustyroad::writers::write_to_index_route(&project).unwrap_or_else(|why| {
-        println!("Failed to write to index.html: {:?}", why.kind());
-    });
+    // The database setup wrote the initial migration and may have changed
+    // rustyroad.toml, the example added its scaffolds
+    sink.save_manifest()
+        .map_err(CreateProjectError::file_write(&manifest_path))?;
 
-    // Write to gitignore file
-    Project::write_to_gitignore(&project).unwrap_or_else(|why| {
-        println!("Failed to write to .gitignore: {:?}", why.kind());
-    });
+    // Joined once everything else succeeded, a failed run never leaves the
+    // workspace with a member that doesn't build
+    if let Some(workspace) = workspace.as_ref().filter(|workspace| workspace.join) {
+        if !resume.is_complete(Step::Workspace) {
+            progress.start(Step::Workspace);
+            workspace::add_member(workspace).map_err(|source| CreateProjectError::Workspace {
+                path: workspace.manifest.clone(),
+                source,
+            })?;
+            info!(
+                "Added {} to the workspace at {}",
+                workspace.member,
+                workspace.manifest.display()
+            );
+            complete_step(&resume, Step::Workspace)?;
+        }
+    }
 
-    rustyroad::writers::write_to_routes_mod(&project.routes_module, "index".to_string()).unwrap_or_else(|why| {
-        println!("Failed to write to routes/mod: {:?}", why.kind());
-    });
-    // Write to Header
-    rustyroad::writers::write_to_header(&project.header_section).unwrap_or_else(|why| {
-        println!("Failed to write to header: {:?}", why.kind());
-    });
+    // The project is complete, a later `--resume` has nothing to continue
+    resume
+        .finish()
+        .map_err(CreateProjectError::file_write(&state_path))?;
 
-    // write to navbar
-    rustyroad::writers::write_to_navbar(&project).unwrap_or_else(|why| {
-        println!("Failed to write to navbar: {:?}", why.kind());
-    });
+    // Format before committing, a failing build only fails generation with `--check`
+    if options.verify || options.check {
+        progress.start(Step::Verify);
+        verify::verify_project(Path::new(&project.name), options.check)?;
+    }
 
-    // write to the dashboard page
-    rustyroad::writers::write_to_dashboard(project.clone()).unwrap_or_else(|why| {
-        println!("Failed to write to dashboard: {:?}", why.kind());
-    });
+    // Commit everything that was generated, a failure here doesn't undo the project
+    if options.git {
+        progress.start(Step::Git);
+        git::init_repository(Path::new(&project.name)).unwrap_or_else(|why| {
+            error!("Failed to initialize a git repository: {why}");
+        });
+    }
 
-    // write to the login page
-    rustyroad::writers::write_to_login_page(project.clone()).unwrap_or_else(|why| {
-        println!("Failed to write to login: {:?}", why.kind());
-    });
+    let elapsed = progress.finish();
+    info!(
+        "Project {} created in {:.1}s!",
+        &project.name,
+        elapsed.as_secs_f64()
+    );
 
-    // We need to tell Diesel where to find our database. We do this by setting the DATABASE_URL environment variable.
-    // We can do this by running the following command in the terminal:
-    let temp_database = &database_data.clone();
-    // Embed migrations from the "migrations" directory
-    // Use the embed_migrations macro to embed migrations into the binary
-    // Adjust the path to point to the location of your migration files
+    Ok(CreatedProject {
+        project,
+        created: sink.created_paths(),
+    })
+} // End of generate_project function
 
-    match temp_database.database_type {
+/// Creates the project database, runs the initial SQL against it and writes
+/// the user models matching its dialect
+/// `project.config_dev_db` is pointed at the database
+async fn set_up_database(
+    project: &mut Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+    sink: &FileSink,
+) -> Result<(), CreateProjectError> {
+    match database_data.database_type {
         DatabaseType::Sqlite => {
-            // Create the database URL
-            let database_url = project.config_dev_db.to_string();
-            println!("database_url: {database_url}");
+            // `--sqlite-path`, `:memory:` and absolute paths are honored,
+            // anything else uses the dev database file inside the project
+            let database_url =
+                sqlite_location(project, database_data, options.sqlite_path.as_deref());
+            project.config_dev_db = database_url.clone();
+            debug!("database_url: {database_url}");
 
             // In SQLite, creating a connection to a non-existent database
-            // automatically creates the database file, so we don't need to
-            // explicitly create the database.
-
-            // Generate the SQL content for the new project
-            let sql_content = rustyroad::writers::load_sql_for_new_project(&project, database_data.clone()).await?;
-
-            // Establish a connection to the new database
-            let connection_result = SqliteConnectOptions::new()
-                .filename(&database_url)
-                .connect()
-                .await;
-
-            // Check if the connection was successful
-            let mut connection = match connection_result {
-                Ok(conn) => conn,
-                Err(why) => {
-                    panic!("Failed to establish connection: {why}");
+            // automatically creates the database file, so we only have to make
+            // sure its directory exists. An in-memory database has no file and
+            // is gone once the connection closes
+            if !is_sqlite_memory(database_data) {
+                if let Some(parent) = Path::new(&database_url).parent() {
+                    fs::create_dir_all(parent).map_err(CreateProjectError::file_write(parent))?;
                 }
+            }
+
+            // Connect to the chosen location instead of the configured name
+            let sqlite_data = Database {
+                name: database_url,
+                ..database_data.clone()
             };
 
-            // Iterate through the vector of SQL commands and execute them one at a time
-            for sql_command in sql_content {
-                // Execute the SQL command
-                sqlx::query(&sql_command)
-                    .execute(&mut connection)
-                    .await
-                    .unwrap_or_else(|why| panic!("Failed to execute SQL command: {why}"));
+            load_and_run_sql(project, &sqlite_data, options, sink).await?;
+            if !is_sqlite_memory(&sqlite_data) {
+                sink.track(&sqlite_data.name);
             }
 
-            rustyroad::writers::write_to_sqlite_user_models(&project).unwrap_or_else(|why| {
-                println!("Failed to write to user models: {:?}", why.kind());
-            });
+            if options.auth.has_user_models() {
+                match writers::models::write_to_user_models(
+                    sink.fs(),
+                    project,
+                    &database_data.database_type,
+                    &options.postgres,
+                    &options.tables,
+                ) {
+                    Ok(()) => sink
+                        .adopt(&project.user_model)
+                        .map_err(CreateProjectError::file_write(&project.user_model))?,
+                    Err(why) => error!("Failed to write to user models: {:?}", why.kind()),
+                }
+            }
         }
 
-        DatabaseType::Postgres => {
-            // Replace this line with the correct URL for the default "postgres" database
-            let admin_database_url = format!(
-                "postgres://{}:{}@{}:{}/postgres",
-                database_data.username,
-                database_data.password,
-                database_data.host,
-                database_data.port,
-            );
+        DatabaseType::Postgres | DatabaseType::Mysql => {
+            // The server's maintenance database ("postgres" or "mysql") is used
+            // to create the project database. MySQL databases get an explicit
+            // charset, the server default is often latin1
+            with_retry(options.retry, "create the project database", || {
+                create_database(
+                    database_data,
+                    &options.tls,
+                    &options.charset,
+                    options.retry.connect_timeout,
+                )
+            })
+            .await
+            .map_err(|source| CreateProjectError::DatabaseCreation {
+                name: database_data.name.clone(),
+                source,
+            })?;
 
-            // Call the function with the admin_database_url
-            rustyroad::writers::create_database_if_not_exists(&admin_database_url, database_data.clone())
-                .await
-                .unwrap_or_else(|why| {
-                    panic!("Failed to create database: {why}");
-                });
+            // Create the database URL for the new database
+            let database_url =
+                database_url_with_tls(database_data, ConnectionTarget::Project, &options.tls);
 
-            // Create the database URL
-            let database_url = format!(
-                "postgres://{}:{}@{}:{}/{}",
-                database_data.username,
-                database_data.password,
-                database_data.host,
-                database_data.port,
-                database_data.name
-            );
+            project.config_dev_db = database_url.clone();
 
-            // Update the DATABASE_URL environment variable to point to the new 'test' database
-            env::set_var(
-                "DATABASE_URL",
-                database_url.replace(&database_data.name, "test"),
-            );
+            debug!("database_url: {database_url}");
 
-            project.config_dev_db = database_url.clone();
+            let version = load_and_run_sql(project, database_data, options, sink).await?;
+
+            // Keep the server version around for debugging
+            if let Some(version) = version {
+                rustyroad_toml::write_server_version(
+                    sink.fs(),
+                    Path::new(&project.rustyroad_toml),
+                    &version,
+                )
+                .and_then(|()| sink.record(&project.rustyroad_toml))
+                .map_err(CreateProjectError::file_write(&project.rustyroad_toml))?;
+            }
 
-            println!("database_url: {database_url}");
-
-            // Generate the SQL content for the new project
-            let sql_content =
-                rustyroad::writers::initial_sql_loader::load_sql_for_new_project(&project, database_data.clone())
-                    .await?;
-
-            // Establish a connection to the new database
-            let connection_result = PgConnectOptions::new()
-                .username(&database_data.username)
-                .password(&database_data.password)
-                .host(&database_data.host)
-                .port(database_data.port.parse::<u16>().unwrap())
-                .database(&database_data.name)
-                .connect()
-                .await;
-
-            // Check if the connection was successful
-            let mut connection = match connection_result {
-                Ok(conn) => conn,
-                Err(why) => {
-                    panic!("Failed to establish connection: {why}");
+            /* Write to user models file */
+            if options.auth.has_user_models() {
+                match writers::models::write_to_user_models(
+                    sink.fs(),
+                    project,
+                    &database_data.database_type,
+                    &options.postgres,
+                    &options.tables,
+                ) {
+                    Ok(()) => sink
+                        .adopt(&project.user_model)
+                        .map_err(CreateProjectError::file_write(&project.user_model))?,
+                    Err(why) => error!("Failed to write to user models: {:?}", why.kind()),
                 }
-            };
+            }
+        }
+
+        DatabaseType::Mongo => {
+            #[cfg(feature = "mongo")]
+            {
+                let database_url = database::database_url(database_data, ConnectionTarget::Project);
+                project.config_dev_db = database_url.clone();
+
+                debug!("database_url: {database_url}");
+
+                // Create the database and the users collection
+                mongo::setup_database(database_data, options.retry.connect_timeout).await?;
 
-            // Iterate through the vector of SQL commands and execute them one at a time
-            for sql_command in sql_content {
-                // Execute the SQL command
-                sqlx::query(&sql_command)
-                    .execute(&mut connection)
-                    .await
-                    .unwrap_or_else(|why| panic!("Failed to execute SQL command: {why}"));
+                if options.auth.has_user_models() {
+                    mongo::write_to_mongo_user_models(project)
+                        .map_err(CreateProjectError::file_write(&project.user_model))?;
+                    sink.adopt(&project.user_model)
+                        .map_err(CreateProjectError::file_write(&project.user_model))?;
+                }
             }
 
-            /* Write to user models file */
-            write_to_postgres_user_models(&project).unwrap_or_else(|why| {
-                println!("Failed to write to user models: {why}");
+            #[cfg(not(feature = "mongo"))]
+            return Err(CreateProjectError::FeatureDisabled {
+                feature: "mongo",
+                what: "MongoDB projects",
             });
         }
+    }
+
+    Ok(())
+}
+
+/// Connects to the server of a Postgres or MySQL database with the admin
+/// credentials the project database is created with
+/// SQLite creates its file on connect and MongoDB its database on first use,
+/// so they have nothing to check
+async fn check_database_connection(
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> Result<(), CreateProjectError> {
+    if !matches!(
+        database_data.database_type,
+        DatabaseType::Postgres | DatabaseType::Mysql
+    ) {
+        return Ok(());
+    }
+
+    let connection = connect_with_retry(
+        database_data,
+        ConnectionTarget::Admin,
+        &options.tls,
+        options.retry,
+    )
+    .await
+    .map_err(|source| CreateProjectError::DatabaseConnection { source })?;
+    if let Err(why) = connection.close().await {
+        warn!("Failed to close the connection: {why}");
+    }
+    debug!("Connected to the {} server", database_data.host);
+    Ok(())
+}
+
+/// Shows where the database of the project will be set up and asks the user
+/// to go ahead
+/// SQLite files are local to the project and MongoDB creates its database
+/// on first use, so only Postgres and MySQL servers are asked about
+fn confirm_database_setup(database_data: &Database) -> Result<(), CreateProjectError> {
+    if !matches!(
+        database_data.database_type,
+        DatabaseType::Postgres | DatabaseType::Mysql
+    ) {
+        return Ok(());
+    }
+
+    eprintln!(
+        "About to set up the {} database:",
+        generators::database_type_name(&database_data.database_type)
+    );
+    eprintln!("  host:     {}", database_data.host);
+    eprintln!("  port:     {}", database_data.port);
+    eprintln!("  database: {}", database_data.name);
+    eprintln!("  action:   create it if it doesn't exist and run the initial SQL");
+
+    // A closed stdin counts as no
+    if prompt::confirm("Continue?").unwrap_or(false) {
+        Ok(())
+    } else {
+        Err(CreateProjectError::DatabaseSetupDeclined {
+            name: database_data.name.clone(),
+            host: database_data.host.clone(),
+        })
+    }
+}
+
+/// Generates the initial SQL for the project and runs it against the project
+/// database described by `database_data`
+/// Shared by the SQL databases so they connect, log and fail the same way
+/// All commands run in one transaction, so a failure leaves the database
+/// untouched. MySQL commits implicitly after DDL statements, so tables it
+/// already created may survive the rollback
+/// The SQL is also written to the initial migration together with a down.sql
+/// dropping its tables, and that migration is recorded as applied
+/// Returns the version reported by Postgres and MySQL servers
+async fn load_and_run_sql(
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+    sink: &FileSink,
+) -> Result<Option<String>, CreateProjectError> {
+    // Generate the SQL content for the new project in the database's dialect
+    let statements = initial_sql::load_initial_sql(
+        project,
+        database_data,
+        &options.postgres,
+        &options.tables.prefix,
+    )
+    .await
+    .map_err(|source| CreateProjectError::SqlGeneration { source })?;
+
+    // Establish a connection to the new database
+    let mut connection = connect_with_retry(
+        database_data,
+        ConnectionTarget::Project,
+        &options.tls,
+        options.retry,
+    )
+    .await
+    .map_err(|source| CreateProjectError::DatabaseConnection { source })?;
 
-        DatabaseType::Mysql => {
-            // Create the database URL for the default "mysql" database
-            let admin_database_url = format!(
-                "mysql://{}:{}@{}:{}/mysql",
-                database_data.username,
-                database_data.password,
-                database_data.host,
-                database_data.port,
+    // Refuse servers the schema is known to fail on before running any of it
+    let version = match minimum_server_version(&database_data.database_type) {
+        // CockroachDB reports its own version numbers, not Postgres ones
+        Some(_) if options.postgres.is_cockroachdb() => {
+            info!(
+                "Skipping the server version check, CockroachDB doesn't report Postgres versions"
             );
+            None
+        }
+        Some(minimum) => {
+            Some(check_server_version(&mut connection, database_data, minimum, options).await?)
+        }
+        None => None,
+    };
 
-            // Call the function with the admin_database_url
-            create_database_if_not_exists(&admin_database_url, database_data.clone())
-                .await
-                .unwrap_or_else(|why| {
-                    panic!("Failed to create database: {:?}", why);
+    // Tables created without a schema land in the configured one
+    use_schema(&mut connection, database_data, &options.postgres)
+        .await
+        .map_err(|source| CreateProjectError::DatabaseConnection { source })?;
+
+    let mut transaction = connection
+        .begin()
+        .await
+        .map_err(|source| CreateProjectError::DatabaseConnection { source })?;
+
+    // Iterate through the statements and execute them one at a time
+    // The SQL reveals the schema, so it is only logged with -v
+    let mut failed = 0;
+    for statement in &statements {
+        debug!("Executing SQL for {}: {}", statement.label, statement.sql);
+        match sqlx::query(&statement.sql).execute(&mut *transaction).await {
+            Ok(_) => {}
+            // Power users can ask to keep going past failing statements
+            Err(why) if options.ignore_sql_errors => {
+                warn!("Failed while {}: {why}", statement.label);
+                failed += 1;
+            }
+            // Dropping the transaction rolls it back
+            Err(source) => {
+                return Err(CreateProjectError::SqlExecution {
+                    label: statement.label.clone(),
+                    source,
                 });
+            }
+        }
+    }
 
-            // Create the database URL for the new database
-            let database_url = format!(
-                "mysql://{}:{}@{}:{}/{}",
-                database_data.username,
-                database_data.password,
-                database_data.host,
-                database_data.port,
-                database_data.name
-            );
+    // The schema is in place, so `rustyroad migrate` must not run it again
+    let migrations_dir = Path::new(&project.name).join(options.migrations_dir());
+    let migration_dir =
+        generators::migration_directory(&migrations_dir, migrate::INITIAL_MIGRATION);
+    let migration_version = migration_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    migrate::mark_applied(&mut transaction, database_data, &migration_version)
+        .await
+        .map_err(|source| CreateProjectError::SqlExecution {
+            label: format!("recording the migration {migration_version}"),
+            source,
+        })?;
 
-            // Update the DATABASE_URL environment variable to point to the new 'test' database
-            env::set_var(
-                "DATABASE_URL",
-                database_url.replace(&database_data.name, "test"),
-            );
+    transaction
+        .commit()
+        .await
+        .map_err(|source| CreateProjectError::DatabaseConnection { source })?;
+    if failed == 0 {
+        info!("Database initialized ({} statements)", statements.len());
+    } else {
+        info!(
+            "Database initialized ({} statements, {failed} failed)",
+            statements.len()
+        );
+    }
 
-            project.config_dev_db = database_url.clone();
+    sink.create_dir_all(&migration_dir)
+        .map_err(CreateProjectError::file_write(&migration_dir))?;
+    let sql = initial_sql::sql_of(&statements);
+    for (file, contents) in [
+        ("up.sql", migrate::up_sql(&sql)),
+        ("down.sql", migrate::down_sql(&sql)),
+    ] {
+        let path = migration_dir.join(file);
+        sink.write(&path, &contents)
+            .map_err(CreateProjectError::file_write(&path))?;
+    }
+
+    Ok(version)
+}
 
-            println!("database_url: {database_url}");
-
-            // Generate the SQL content for the new project
-            let sql_content =
-                initial_sql_loader::load_sql_for_new_project(&project, database_data.clone())
-                    .await?;
-
-            // Establish a connection to the new database
-            let connection_result = MySqlConnectOptions::new()
-                .username(&database_data.username)
-                .password(&database_data.password)
-                .host(&database_data.host)
-                .port(database_data.port.parse::<u16>().unwrap())
-                .database(&database_data.name)
-                .connect()
-                .await;
-
-            // Check if the connection was successful
-            let mut connection = match connection_result {
-                Ok(conn) => conn,
-                Err(why) => {
-                    panic!("Failed to establish connection: {why}");
+/// Queries the server version and compares it with `minimum`
+/// An older server is an error unless `ignore_sql_errors` is set, in which
+/// case only a warning is logged
+/// Returns the version string as reported by the server
+async fn check_server_version(
+    connection: &mut sqlx::any::AnyConnection,
+    database_data: &Database,
+    minimum: ServerVersion,
+    options: &NewProjectOptions,
+) -> Result<String, CreateProjectError> {
+    let database = generators::database_type_name(&database_data.database_type);
+    let version = server_version(connection)
+        .await
+        .map_err(|source| CreateProjectError::DatabaseConnection { source })?;
+    info!("Connected to {database} {version}");
+
+    match ServerVersion::parse(&version) {
+        Some(parsed) if parsed < minimum => {
+            if options.ignore_sql_errors {
+                warn!("{database} {parsed} is older than {minimum}, the initial SQL may fail");
+            } else {
+                return Err(CreateProjectError::UnsupportedServerVersion {
+                    database,
+                    version,
+                    minimum: minimum.to_string(),
+                });
+            }
+        }
+        Some(_) => {}
+        None => warn!("Could not parse the {database} version `{version}`"),
+    }
+
+    Ok(version)
+}
+
+/// Writes the files of the built-in project template through `sink`
+/// Used unless `--template` points at a custom template
+fn write_builtin_template(
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+    workspace: Option<&Workspace>,
+    sink: &FileSink,
+    progress: &Progress,
+    resume: &ResumeState,
+) -> Result<(), CreateProjectError> {
+    // An existing project only gets its files regenerated
+    if !sink.is_regenerating() && !resume.is_complete(Step::Skeleton) {
+        progress.start(Step::Skeleton);
+        create_project_skeleton(project, options, sink)?;
+        complete_step(resume, Step::Skeleton)?;
+    }
+
+    // Views can be overridden from the user config directory
+    let views = Views::global();
+    let view_context = template::template_context(project, database_data, options);
+    let views = &views;
+    let view_context = &view_context;
+
+    // Every group below writes its own files, so once the skeleton exists
+    // they run concurrently. Files touched twice, like Cargo.toml and
+    // routes/mod.rs, are written within a single group
+    let mut jobs: Vec<(Step, WriteJob<'_, CreateProjectError>)> = Vec::new();
+
+    jobs.push((
+        Step::Config,
+        Box::new(move || write_rustyroad_toml(project, database_data, options, sink)),
+    ));
+
+    // Write to the cargo.toml file
+    jobs.push((
+        Step::CargoToml,
+        Box::new(move || {
+            sink.run(&project.cargo_toml, || {
+                match options.framework {
+                    Framework::Actix => {
+                        rustyroad::writers::write_to_cargo_toml(project, database_data)?
+                    }
+                    Framework::Axum => {
+                        writers::axum::write_to_cargo_toml(sink.fs(), project, database_data)?
+                    }
                 }
-            };
+                // The upstream writer enables the sqlx drivers of every database
+                writers::cargo::use_sqlx_driver(
+                    sink.fs(),
+                    Path::new(&project.cargo_toml),
+                    &database_data.database_type,
+                )?;
+                // Trimmed before the feature dependencies below are added
+                if options.minimal_cargo {
+                    writers::cargo::trim_dependencies(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                        database_data,
+                        options,
+                    )?;
+                }
+                if let Some(author) = &options.author {
+                    writers::cargo::set_authors(sink.fs(), Path::new(&project.cargo_toml), author)?;
+                }
+                // Cargo only builds a project below a workspace root as one
+                // of its members or as a workspace of its own
+                match workspace {
+                    Some(workspace) if workspace.join => writers::cargo::remove_root_only_tables(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                    )?,
+                    Some(_) => writers::cargo::declare_own_workspace(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                    )?,
+                    None => {}
+                }
+                // main.rs loads DATABASE_URL from .env
+                writers::cargo::add_dependency(
+                    sink.fs(),
+                    Path::new(&project.cargo_toml),
+                    writers::env::DOTENVY_DEPENDENCY,
+                )?;
+                if writers::tests::writes_tests(database_data, options) {
+                    writers::cargo::add_dev_dependency(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                        writers::tests::TOKIO_DEV_DEPENDENCY,
+                    )?;
+                }
+                // The token handlers sign with jsonwebtoken and speak JSON
+                if options.auth == AuthScheme::Jwt {
+                    let cargo_toml = Path::new(&project.cargo_toml);
+                    for dependency in [
+                        writers::auth::JSONWEBTOKEN_DEPENDENCY,
+                        "serde = { version = \"1\", features = [\"derive\"] }",
+                        "serde_json = \"1\"",
+                    ] {
+                        writers::cargo::add_dependency(sink.fs(), cargo_toml, dependency)?;
+                    }
+                }
+                // Passwords of the user models and token handlers are hashed
+                if options.auth.has_user_models() || options.auth == AuthScheme::Jwt {
+                    writers::password::add_password_dependencies(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                    )?;
+                }
+                if !options.no_telemetry {
+                    writers::telemetry::add_tracing_dependencies(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                        options.framework,
+                    )?;
+                }
+                if writers::csrf::uses_csrf(options) {
+                    writers::csrf::add_csrf_dependencies(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                    )?;
+                }
+                if writers::settings::uses_config_module(options) {
+                    writers::settings::add_config_dependencies(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                    )?;
+                }
+                if writers::session::uses_redis_sessions(options) {
+                    writers::session::add_redis_session_dependencies(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                    )?;
+                }
+                // Dependencies from the command line go last so they can pin
+                // the versions of the ones above
+                for dependency in &options.dependencies {
+                    writers::cargo::merge_dependency(
+                        sink.fs(),
+                        Path::new(&project.cargo_toml),
+                        dependency,
+                    )?;
+                }
+                Ok(())
+            })
+            .map_err(CreateProjectError::file_write(&project.cargo_toml))
+        }),
+    ));
 
-            // Iterate through the vector of SQL commands and execute them one at a time
-            for sql_command in sql_content {
-                println!("Executing SQL command: {sql_command}"); // Log the SQL command being executed
-                                                                  // Execute the SQL command
-                match sqlx::query(&sql_command).execute(&mut connection).await {
-                    Ok(_) => {
-                        println!("Successfully executed SQL command: {sql_command}");
+    // Write to main.rs file
+    jobs.push((
+        Step::MainRs,
+        Box::new(move || {
+            sink.run(&project.main_rs, || {
+                match (options.framework, options.no_frontend) {
+                    (Framework::Actix, false) => {
+                        rustyroad::writers::write_to_main_rs(project)?;
+                        if !options.no_health {
+                            writers::actix::mount_health_routes(sink.fs(), project, database_data)?;
+                        }
                     }
-                    Err(why) => {
-                        println!("Failed to execute SQL command: {sql_command}, Error: {why}");
-                        // Optionally, return an error instead of panicking
-                        // return Err(why.into());
+                    (Framework::Actix, true) => writers::actix::write_to_api_main_rs(
+                        sink.fs(),
+                        project,
+                        database_data,
+                        &generated_routes(options),
+                    )?,
+                    (Framework::Axum, false) => {
+                        writers::axum::write_to_main_rs(sink.fs(), project, database_data)?
+                    }
+                    (Framework::Axum, true) => {
+                        writers::axum::write_to_api_main_rs(sink.fs(), project, database_data)?
                     }
                 }
-            }
+                if !options.no_telemetry {
+                    writers::telemetry::add_tracing(
+                        sink.fs(),
+                        Path::new(&project.main_rs),
+                        options.framework,
+                    )?;
+                }
+                // Wrapped last so it runs innermost, after the request logging
+                if writers::csrf::uses_csrf(options) {
+                    writers::csrf::add_csrf(sink.fs(), Path::new(&project.main_rs))?;
+                }
+                if writers::session::uses_redis_sessions(options) {
+                    writers::session::use_redis_sessions(
+                        sink.fs(),
+                        Path::new(&project.main_rs),
+                        writers::settings::uses_config_module(options),
+                    )?;
+                }
+                // The token handlers look users up through the pool
+                if options.auth == AuthScheme::Jwt && writers::pool::uses_pool(database_data) {
+                    writers::declare_module(sink.fs(), Path::new(&project.main_rs), "pool")
+                        .map_err(io::Error::other)?;
+                }
+                if writers::settings::uses_config_module(options) {
+                    writers::settings::use_settings(sink.fs(), Path::new(&project.main_rs))?;
+                } else if options.bind.is_some() {
+                    writers::settings::bind_to(
+                        sink.fs(),
+                        Path::new(&project.main_rs),
+                        options.bind_address(),
+                    )?;
+                }
+                Ok(())
+            })
+            .map_err(CreateProjectError::file_write(&project.main_rs))
+        }),
+    ));
+
+    // Write the CSRF middleware the session login is wrapped in
+    if writers::csrf::uses_csrf(options) {
+        jobs.push((
+            Step::Csrf,
+            Box::new(move || {
+                let csrf_module = writers::csrf::csrf_module_path(project);
+                sink.run(&csrf_module.display().to_string(), || {
+                    writers::csrf::write_to_csrf_module(sink.fs(), project)
+                })
+                .map_err(CreateProjectError::file_write(&csrf_module))
+            }),
+        ));
+    }
+
+    // Write the typed settings module that main.rs and the pool read from
+    if writers::settings::uses_config_module(options) {
+        jobs.push((
+            Step::Settings,
+            Box::new(move || {
+                let config_module = writers::settings::config_module_path(project);
+                sink.run(&config_module.display().to_string(), || {
+                    writers::settings::write_to_config_module(
+                        sink.fs(),
+                        project,
+                        database_data,
+                        options,
+                    )
+                })
+                .map_err(CreateProjectError::file_write(&config_module))
+            }),
+        ));
+    }
+
+    // Write the database pool module, sized by the pool settings
+    // The upstream Actix main.rs only declares it for the health routes,
+    // without them it is ready to be wired in with `mod pool;`
+    if writers::pool::uses_pool(database_data) {
+        jobs.push((
+            Step::Pool,
+            Box::new(move || {
+                let pool_module = layout::pool_module(project);
+                sink.run(&pool_module.display().to_string(), || {
+                    writers::pool::write_to_pool_module(
+                        sink.fs(),
+                        project,
+                        database_data,
+                        &options.pool,
+                        writers::settings::uses_config_module(options),
+                    )
+                })
+                .map_err(CreateProjectError::file_write(&pool_module))
+            }),
+        ));
+    }
+
+    // Write to README.md file
+    jobs.push((
+        Step::Readme,
+        Box::new(move || {
+            sink.run(&project.readme, || {
+                writers::readme::write_to_readme(sink.fs(), project)
+            })
+            .map_err(CreateProjectError::file_write(&project.readme))
+        }),
+    ));
+
+    // Write to seeds.sql file, MongoDB has no SQL to seed with
+    if !matches!(database_data.database_type, DatabaseType::Mongo) {
+        jobs.push((
+            Step::Seeds,
+            Box::new(move || {
+                let seeds = layout::project_file(project, seed::SEEDS_FILE);
+                sink.run(&seeds.display().to_string(), || {
+                    writers::seeds::write_to_seeds(sink.fs(), project, database_data, options)
+                })
+                .map_err(CreateProjectError::file_write(&seeds))
+            }),
+        ));
+    }
 
-            write_to_mysql_user_models(&project).unwrap_or_else(|why| {
-                println!("Failed to write to user models: {:?}", why.kind());
+    // The frontend, skipped for JSON only projects
+    if !options.no_frontend {
+        jobs.push((
+            Step::Frontend,
+            Box::new(move || {
+                write_frontend(project, options, sink, views, view_context)
+                    .map_err(CreateProjectError::file_write(&project.package_json))
+            }),
+        ));
+    }
+
+    // Write to index.html route
+    jobs.push((
+        Step::Routes,
+        Box::new(move || {
+            sink.run(&project.index_route, || {
+                match (options.framework, options.no_frontend) {
+                    (Framework::Actix, false) => rustyroad::writers::write_to_index_route(project),
+                    (Framework::Actix, true) => {
+                        writers::actix::write_to_api_index_route(sink.fs(), project)
+                    }
+                    (Framework::Axum, false) => {
+                        writers::axum::write_to_index_route(sink.fs(), project)
+                    }
+                    (Framework::Axum, true) => {
+                        writers::axum::write_to_api_index_route(sink.fs(), project)
+                    }
+                }
+            })
+            .unwrap_or_else(|why| {
+                error!("Failed to write to index.html: {:?}", why.kind());
+            });
+
+            sink.run(&project.routes_module, || {
+                write_routes_module(sink.fs(), project, options)
+            })
+            .unwrap_or_else(|why| {
+                error!("Failed to write to routes/mod: {:?}", why.kind());
+            });
+            Ok(())
+        }),
+    ));
+
+    // The liveness and readiness routes. The upstream Actix main.rs mounts
+    // only its own routes, so they are configured there after it is written
+    if !options.no_health {
+        jobs.push((
+            Step::HealthRoutes,
+            Box::new(move || {
+                let health_route = writers::health::health_route_path(project);
+                sink.run(&health_route.display().to_string(), || {
+                    writers::health::write_to_health_routes(
+                        sink.fs(),
+                        project,
+                        options.framework,
+                        database_data,
+                    )
+                })
+                .map_err(CreateProjectError::file_write(&health_route))
+            }),
+        ));
+    }
+
+    // Write to gitignore file
+    jobs.push((
+        Step::Env,
+        Box::new(move || {
+            sink.run(&project.gitignore, || {
+                writers::gitignore::write_to_gitignore(sink.fs(), project, database_data, options)
+            })
+            .unwrap_or_else(|why| {
+                error!("Failed to write to .gitignore: {:?}", why.kind());
             });
+
+            // Write the runtime settings the app loads at startup, the real ones
+            // in the ignored .env and placeholders in the committed .env.example
+            let env_file = layout::project_file(project, writers::env::ENV_FILE);
+            sink.run(&env_file.display().to_string(), || {
+                writers::env::write_to_env(sink.fs(), project, database_data, options)
+            })
+            .map_err(CreateProjectError::file_write(&env_file))?;
+            let env_example = layout::project_file(project, writers::env::ENV_EXAMPLE_FILE);
+            sink.run(&env_example.display().to_string(), || {
+                writers::env::write_to_env_example(sink.fs(), project, database_data, options)
+            })
+            .map_err(CreateProjectError::file_write(&env_example))
+        }),
+    ));
+
+    // Write the token handlers
+    if options.auth == AuthScheme::Jwt {
+        jobs.push((
+            Step::AuthRoutes,
+            Box::new(move || {
+                let auth_routes = writers::auth::auth_route_path(project);
+                sink.run(&auth_routes.display().to_string(), || {
+                    writers::auth::write_to_auth_routes(
+                        sink.fs(),
+                        project,
+                        options.framework,
+                        &database_data.database_type,
+                        &qualified_table(
+                            &database_data.database_type,
+                            &options.postgres,
+                            &options.tables.table("users"),
+                        ),
+                    )
+                })
+                .map_err(CreateProjectError::file_write(&auth_routes))
+            }),
+        ));
+    }
+
+    // The layout partials, skipped for JSON only projects
+    if !options.no_frontend && !options.no_layout {
+        jobs.push((
+            Step::Layout,
+            Box::new(move || {
+                write_layout(project, sink, views, view_context);
+                Ok(())
+            }),
+        ));
+    }
+
+    // The pages of the session login
+    if !options.no_frontend && options.auth.has_login_pages() {
+        jobs.push((
+            Step::LoginPages,
+            Box::new(move || {
+                // write to the dashboard page
+                if !options.no_dashboard {
+                    sink.run(&project.dashboard_page_html, || {
+                        views.write_view(
+                            project,
+                            &project.dashboard_page_html,
+                            view_context,
+                            || rustyroad::writers::write_to_dashboard(project.clone()),
+                        )
+                    })
+                    .unwrap_or_else(|why| {
+                        error!("Failed to write to dashboard: {:?}", why.kind());
+                    });
+                }
+
+                // write to the login page, its form gets the CSRF token
+                // field and a standalone one the script of the layout
+                sink.run(&project.login_page_html, || {
+                    views.write_view(project, &project.login_page_html, view_context, || {
+                        rustyroad::writers::write_to_login_page(project.clone())
+                    })?;
+                    if writers::csrf::uses_csrf(options) {
+                        writers::csrf::add_form_script(
+                            sink.fs(),
+                            Path::new(&project.login_page_html),
+                        )?;
+                    }
+                    Ok(())
+                })
+                .unwrap_or_else(|why| {
+                    error!("Failed to write to login: {:?}", why.kind());
+                });
+                Ok(())
+            }),
+        ));
+    }
+
+    // Write the Docker files
+    if options.docker {
+        jobs.push((
+            Step::Docker,
+            Box::new(move || {
+                let dockerfile = layout::project_file(project, "Dockerfile");
+                sink.run(&dockerfile.display().to_string(), || {
+                    writers::docker::write_to_dockerfile(sink.fs(), project, database_data, options)
+                })
+                .map_err(CreateProjectError::file_write(&dockerfile))?;
+
+                let compose = layout::project_file(project, "docker-compose.yml");
+                sink.run(&compose.display().to_string(), || {
+                    writers::docker::write_to_docker_compose(
+                        sink.fs(),
+                        project,
+                        database_data,
+                        options.bind_address().port(),
+                    )
+                })
+                .map_err(CreateProjectError::file_write(&compose))
+            }),
+        ));
+    }
+
+    // Write the CI workflow
+    if let Some(CiProvider::Github) = options.ci {
+        jobs.push((
+            Step::Ci,
+            Box::new(move || {
+                let workflow = writers::ci::github_workflow_path(project);
+                if let Some(parent) = workflow.parent() {
+                    sink.create_dir_all(parent)
+                        .map_err(CreateProjectError::file_write(parent))?;
+                }
+                sink.write(
+                    &workflow,
+                    &writers::ci::github_workflow_source(
+                        database_data,
+                        options.auth,
+                        options.migrations_dir(),
+                    ),
+                )
+                .map_err(CreateProjectError::file_write(&workflow))
+            }),
+        ));
+    }
+
+    // Write the task file
+    if let Some(runner) = options.tasks {
+        jobs.push((
+            Step::Tasks,
+            Box::new(move || {
+                let path = writers::tasks::tasks_path(project, runner);
+                writers::tasks::tasks_source(project, database_data, options, runner)
+                    .and_then(|source| sink.write(&path, &source))
+                    .map_err(CreateProjectError::file_write(&path))
+            }),
+        ));
+    }
+
+    // Write the integration tests
+    if writers::tests::writes_tests(database_data, options) {
+        jobs.push((
+            Step::Tests,
+            Box::new(move || {
+                let common = writers::tests::common_module_path(project);
+                if let Some(parent) = common.parent() {
+                    sink.create_dir_all(parent)
+                        .map_err(CreateProjectError::file_write(parent))?;
+                }
+                let Some(source) = writers::tests::common_module_source(
+                    project,
+                    &database_data.database_type,
+                    options,
+                ) else {
+                    return Ok(());
+                };
+                sink.write(&common, &source)
+                    .map_err(CreateProjectError::file_write(&common))?;
+
+                let app_test = writers::tests::app_test_path(project);
+                sink.write(&app_test, writers::tests::app_test_source())
+                    .map_err(CreateProjectError::file_write(&app_test))
+            }),
+        ));
+    }
+
+    // Groups a resumed run already finished are skipped, the others record
+    // themselves once their files are written
+    let jobs = jobs
+        .into_iter()
+        .filter(|(step, _)| !resume.is_complete(*step))
+        .map(|(step, job)| -> WriteJob<'_, CreateProjectError> {
+            Box::new(move || {
+                progress.start(step);
+                job()?;
+                complete_step(resume, step)
+            })
+        })
+        .collect();
+    sink.run_all(jobs)
+}
+
+/// Records in the state file that `step` is finished
+fn complete_step(resume: &ResumeState, step: Step) -> Result<(), CreateProjectError> {
+    resume
+        .complete(step)
+        .map_err(CreateProjectError::file_write(resume.path()))
+}
+
+/// Writes the frontend files, the package, the entry points and the
+/// stylesheets of the chosen CSS framework
+/// Only a failed package.json is an error, the other files are logged
+pub(crate) fn write_frontend(
+    project: &Project,
+    options: &NewProjectOptions,
+    sink: &FileSink,
+    views: &Views,
+    view_context: &tera::Context,
+) -> io::Result<()> {
+    // Write to package.json file, only Tailwind needs a build step
+    if options.css == CssFramework::Tailwind {
+        sink.run(&project.package_json, || {
+            writers::tailwind::write_to_package_json(sink.fs(), project)
+        })?;
+    }
+
+    // Write to index.js file
+    sink.run(&project.index_js, || {
+        writers::scripts::write_to_index_js(sink.fs(), project)
+    })
+    .unwrap_or_else(|why| {
+        error!("Failed to write to index.js: {:?}", why.kind());
+    });
+    // Write to index.html.tera file
+    sink.run(&project.index_html, || {
+        views.write_view(project, &project.index_html, view_context, || {
+            rustyroad::writers::write_to_index_html(project)
+        })
+    })
+    .unwrap_or_else(|why| {
+        error!("Failed to write to index.html: {:?}", why.kind());
+    });
+    // Write to base.html.tera file, linking the chosen CSS framework
+    sink.run(&project.base_html, || {
+        views.write_view(
+            project,
+            &project.base_html,
+            view_context,
+            || match options.css {
+                CssFramework::Tailwind => {
+                    rustyroad::writers::write_to_base_html(&project.base_html)
+                }
+                css => writers::css::write_to_base_html(sink.fs(), project, css),
+            },
+        )?;
+        if writers::csrf::uses_csrf(options) {
+            writers::csrf::add_form_script(sink.fs(), Path::new(&project.base_html))?;
         }
+        Ok(())
+    })
+    .unwrap_or_else(|why| {
+        error!("Failed to write to base.html: {:?}", why.kind());
+    });
 
-        DatabaseType::Mongo => {
-            // Create the database
-            let database_url = format!(
-                "DATABASE_URL=mongodb://localhost:27017/{}",
-                &database_data.clone().name
-            );
-            println!("database_url: {database_url}");
-            let output = std::process::Command::new("diesel")
-                .arg("setup")
-                .env("DATABASE_URL", database_url)
-                .output()
-                .expect("Failed to execute process");
-            println!("output: {:?}", output);
+    match options.css {
+        CssFramework::Tailwind => {
+            // Write to tailwind.css file
+            sink.run(&project.tailwind_css, || {
+                writers::tailwind::write_to_tailwind_css(sink.fs(), project)
+            })
+            .unwrap_or_else(|why| {
+                error!("Failed to write to tailwind.css: {:?}", why.kind());
+            });
+            // Write to tailwind.config.js file, with content paths matching the layout
+            sink.run(&project.tailwind_config, || {
+                writers::tailwind::write_to_tailwind_config(sink.fs(), project)
+            })
+            .unwrap_or_else(|why| {
+                error!("Failed to write to tailwind.config.js: {:?}", why.kind());
+            });
+
+            // Write to postcss.config.js file
+            sink.run(&project.postcss_config, || {
+                writers::tailwind::write_to_postcss_config(sink.fs(), project)
+            })
+            .unwrap_or_else(|why| {
+                error!("Failed to write to postcss.config.js: {:?}", why.kind());
+            });
+        }
+        // Bootstrap is loaded from its CDN by base.html
+        CssFramework::Bootstrap => {}
+        CssFramework::None => {
+            // Write the plain stylesheet
+            let stylesheet = writers::css::stylesheet_path(project);
+            sink.run(&stylesheet.display().to_string(), || {
+                writers::css::write_to_stylesheet(sink.fs(), project)
+            })
+            .unwrap_or_else(|why| {
+                error!("Failed to write to style.css: {:?}", why.kind());
+            });
         }
     }
 
-    println!("Project {} created!", &project.name);
+    Ok(())
+}
+
+/// Writes the header and navbar partials, failures are only logged
+pub(crate) fn write_layout(
+    project: &Project,
+    sink: &FileSink,
+    views: &Views,
+    view_context: &tera::Context,
+) {
+    // Write to Header
+    sink.run(&project.header_section, || {
+        views.write_view(project, &project.header_section, view_context, || {
+            rustyroad::writers::write_to_header(&project.header_section)
+        })
+    })
+    .unwrap_or_else(|why| {
+        error!("Failed to write to header: {:?}", why.kind());
+    });
+
+    // write to navbar
+    sink.run(&project.navbar_component, || {
+        views.write_view(project, &project.navbar_component, view_context, || {
+            rustyroad::writers::write_to_navbar(project)
+        })
+    })
+    .unwrap_or_else(|why| {
+        error!("Failed to write to navbar: {:?}", why.kind());
+    });
+}
+
+/// Writes rustyroad.toml with the database settings and the generator choices
+pub(crate) fn write_rustyroad_toml(
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+    sink: &FileSink,
+) -> Result<(), CreateProjectError> {
+    // Record the generator choices so later generators can match them
+    let settings = GeneratorSettings {
+        framework: options.framework,
+        frontend: !options.no_frontend,
+        css: options.css,
+        auth: options.auth,
+        bind: options.bind_address().to_string(),
+        session_store: options.session_store,
+        migrations_dir: options.migrations_dir().to_string(),
+        generated_by: Some(rustyroad_toml::generated_by()),
+    };
+
+    // Write to rustyroad.toml file, in one go so a regenerated file can be
+    // compared with the previous one
+    sink.run(&project.rustyroad_toml, || {
+        // The upstream writer always writes to disk, the sections are added
+        // on the filesystem of the sink
+        Project::write_to_rustyroad_toml(project, database_data)?;
+        sink.import(&project.rustyroad_toml)?;
+
+        let (fs, path) = (sink.fs(), Path::new(&project.rustyroad_toml));
+        rustyroad_toml::write_generator_settings(fs, path, &settings)?;
+        rustyroad_toml::write_pool_settings(fs, path, &options.pool)?;
+        rustyroad_toml::write_environments(fs, path, database_data)?;
+        if let DatabaseType::Mysql = database_data.database_type {
+            rustyroad_toml::write_charset_settings(fs, path, &options.charset)?;
+        }
+        if let DatabaseType::Postgres = database_data.database_type {
+            if options.postgres.is_cockroachdb() {
+                rustyroad_toml::set_database_type(fs, path, cockroach::DATABASE_TYPE)?;
+            }
+            if !options.postgres.is_default() {
+                rustyroad_toml::write_postgres_settings(fs, path, &options.postgres)?;
+            }
+        }
+        if !options.tables.is_default() {
+            rustyroad_toml::write_table_settings(fs, path, &options.tables)?;
+        }
+        if options.password_from_env {
+            rustyroad_toml::use_password_placeholder(fs, path)?;
+        }
+        if options.tls.is_default() {
+            Ok(())
+        } else {
+            rustyroad_toml::write_tls_settings(fs, path, &options.tls)
+        }
+    })
+    .map_err(CreateProjectError::file_write(&project.rustyroad_toml))?;
+
+    Ok(())
+}
+
+/// The route modules a project is generated with, in registration order
+fn generated_routes(options: &NewProjectOptions) -> Vec<&'static str> {
+    let mut routes = vec!["index"];
+    if options.auth == AuthScheme::Jwt {
+        routes.push(writers::auth::AUTH_MODULE);
+    }
+    if !options.no_health {
+        routes.push(writers::health::HEALTH_MODULE);
+    }
+    routes
+}
+
+/// Writes routes/mod.rs declaring exactly the generated route modules
+fn write_routes_module(
+    fs: &dyn FileSystem,
+    project: &Project,
+    options: &NewProjectOptions,
+) -> io::Result<()> {
+    let routes = generated_routes(options);
+    match options.framework {
+        Framework::Actix => routes.iter().try_for_each(|route| {
+            rustyroad::writers::write_to_routes_mod(&project.routes_module, route.to_string())
+        }),
+        Framework::Axum if options.no_frontend => {
+            writers::axum::write_to_api_routes_mod(fs, project, &routes)
+        }
+        Framework::Axum => writers::axum::write_to_routes_mod(fs, project, &routes),
+    }
+}
+
+/// Creates the project directory and the empty files of the upstream layout
+fn create_project_skeleton(
+    project: &Project,
+    options: &NewProjectOptions,
+    sink: &FileSink,
+) -> Result<(), CreateProjectError> {
+    // Create the project directory
+    sink.run(&project.name, || create_directory(project))
+        .map_err(|why| match why.kind() {
+            ErrorKind::AlreadyExists => CreateProjectError::DirectoryExists {
+                path: PathBuf::from(&project.name),
+            },
+            _ => CreateProjectError::FileWrite {
+                path: PathBuf::from(&project.name),
+                source: why,
+            },
+        })?;
+
+    // Create the files
+    sink.run(&project.name, || create_files(project))
+        .map_err(CreateProjectError::file_write(&project.name))?;
+
+    // The upstream scaffolding creates the frontend files up front, remove
+    // the ones the chosen options don't use
+    let mut unused_files = if options.no_frontend {
+        layout::frontend_files(project).to_vec()
+    } else if options.css != CssFramework::Tailwind {
+        layout::tailwind_files(project).to_vec()
+    } else {
+        Vec::new()
+    };
+    if !options.no_frontend {
+        if !options.auth.has_login_pages() {
+            unused_files.extend(layout::login_files(project));
+        } else if options.no_dashboard {
+            unused_files.push(project.dashboard_page_html.as_str());
+        }
+        if options.no_layout {
+            unused_files.extend(layout::layout_files(project));
+        }
+    }
+    // The upstream scaffolding wrote them to disk, not onto the sink
+    if !sink.is_dry_run() {
+        for path in unused_files {
+            match fs::remove_file(path) {
+                Err(why) if why.kind() != ErrorKind::NotFound => {
+                    return Err(CreateProjectError::file_write(path)(why))
+                }
+                _ => {}
+            }
+        }
+    }
 
-    // Create the database
-    Ok(project)
-} // End of create_new_project function
+    Ok(())
+}
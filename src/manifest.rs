@@ -0,0 +1,83 @@
+//! The manifest of generated files
+//! Records the hash of every file as the generator wrote it, so a later run
+//! can tell files the user edited apart from untouched ones
+//! The generators also record what they made for each resource, which
+//! `rustyroad destroy` removes again
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::filesystem::FileSystem;
+
+/// Location of the manifest, relative to the project root
+pub const MANIFEST_PATH: &str = ".rustyroad/manifest.json";
+
+/// The generated files of a project and the hashes of their generated content
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Paths relative to the project root mapped to SHA-256 hashes
+    pub files: BTreeMap<String, String>,
+    /// What the generators made, by `<generator> <name>` such as `model Post`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub generated: BTreeMap<String, Generated>,
+}
+
+/// What a generator made for one resource
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Generated {
+    /// The created files, relative to the project root
+    pub files: Vec<String>,
+    /// Lines added to files that existed before, such as the module
+    /// declarations in a mod.rs, by the path of the file
+    pub lines: BTreeMap<String, Vec<String>>,
+}
+
+impl Manifest {
+    /// Reads the manifest of the project at `root` from `fs`
+    /// Projects generated before the manifest existed get an empty one
+    pub fn load(fs: &dyn FileSystem, root: &Path) -> io::Result<Self> {
+        match fs.read_to_string(&root.join(MANIFEST_PATH)) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why)),
+            Err(why) if why.kind() == io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(why) => Err(why),
+        }
+    }
+
+    /// Writes the manifest into the project at `root` on `fs`
+    pub fn save(&self, fs: &dyn FileSystem, root: &Path) -> io::Result<()> {
+        let path = root.join(MANIFEST_PATH);
+        if let Some(parent) = path.parent() {
+            fs.create_dir(parent)?;
+        }
+
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+        fs.write(&path, (contents + "\n").as_bytes())
+    }
+
+    /// Remembers `contents` as the generated content of `relative`
+    pub fn record(&mut self, relative: &str, contents: &[u8]) {
+        self.files.insert(relative.to_string(), hash(contents));
+    }
+
+    /// The key `generated` records the resource `name` of `generator` under
+    pub fn generated_key(generator: &str, name: &str) -> String {
+        format!("{generator} {name}")
+    }
+
+    /// Whether `contents` differ from what the generator last wrote to `relative`
+    /// Files the manifest doesn't know about count as edited
+    pub fn is_edited(&self, relative: &str, contents: &[u8]) -> bool {
+        self.files.get(relative) != Some(&hash(contents))
+    }
+}
+
+/// The hex encoded SHA-256 hash of `contents`
+pub fn hash(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
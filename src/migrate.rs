@@ -0,0 +1,605 @@
+//! Applies and reverts the SQL migrations of an existing project
+//! Migrations live in `migrations/<version>_<name>/` with an `up.sql` and a
+//! `down.sql`, and applied versions are tracked in `_rustyroad_migrations`
+//! The `migrations_dir` of rustyroad.toml can move them, e.g. to `db/migrate`
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::{Database, DatabaseType};
+use sqlx::any::AnyConnection;
+use sqlx::{Connection, Executor, Row};
+use tracing::info;
+
+use crate::database::{connect, placeholder, use_schema, ConnectionTarget, PostgresSettings, Tls};
+use crate::rustyroad_toml::{self, find_project_root, Environment};
+use crate::MigrateError;
+
+/// Table that records which migrations have been applied
+pub const MIGRATIONS_TABLE: &str = "_rustyroad_migrations";
+
+/// Name of the migration holding the schema created with the project
+pub const INITIAL_MIGRATION: &str = "initial_schema";
+
+/// A migration found on disk
+#[derive(Debug, Clone)]
+pub struct Migration {
+    /// The directory name, e.g. `20230701120000_create_posts`
+    pub version: String,
+    pub directory: PathBuf,
+}
+
+/// Whether a migration has been applied to the database
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub migration: Migration,
+    pub applied: bool,
+}
+
+impl Migration {
+    fn read(&self, file: &str) -> Result<String, MigrateError> {
+        let path = self.directory.join(file);
+        fs::read_to_string(&path).map_err(|source| MigrateError::FileRead { path, source })
+    }
+}
+
+/// Lists the migrations in `migrations_dir`, ordered by version
+/// Directory names start with a timestamp, so sorting them lexicographically
+/// gives the order they were created in
+pub fn list_migrations(migrations_dir: &Path) -> Result<Vec<Migration>, MigrateError> {
+    if !migrations_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(migrations_dir).map_err(|source| MigrateError::FileRead {
+        path: migrations_dir.to_path_buf(),
+        source,
+    })?;
+
+    let mut migrations = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|source| MigrateError::FileRead {
+            path: migrations_dir.to_path_buf(),
+            source,
+        })?;
+        let directory = entry.path();
+        if directory.join("up.sql").is_file() {
+            migrations.push(Migration {
+                version: entry.file_name().to_string_lossy().into_owned(),
+                directory,
+            });
+        }
+    }
+
+    migrations.sort_by(|a, b| a.version.cmp(&b.version));
+    Ok(migrations)
+}
+
+/// Applies every pending migration of the project containing `current_dir`
+/// to the database of `environment`
+/// All pending migrations run in a single transaction, so either all of them
+/// are applied or none are
+/// Note that MySQL commits implicitly after DDL statements, so a failure
+/// there can leave earlier migrations of the batch applied
+/// Returns the versions that were applied
+pub async fn migrate_up(
+    current_dir: &Path,
+    environment: Environment,
+) -> Result<Vec<String>, MigrateError> {
+    let (root, database_data, tls, postgres) = load_project(current_dir, environment)?;
+    let mut connection = open(&database_data, &tls, &postgres).await?;
+    let applied = applied_versions(&mut connection, &database_data).await?;
+
+    let pending: Vec<Migration> = list_migrations(&migrations_dir(&root)?)?
+        .into_iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .collect();
+
+    if pending.is_empty() {
+        info!("No pending migrations");
+        return Ok(Vec::new());
+    }
+
+    let insert = format!(
+        "INSERT INTO {MIGRATIONS_TABLE} (version) VALUES ({})",
+        placeholder(&database_data.database_type, 1)
+    );
+
+    let mut transaction = connection
+        .begin()
+        .await
+        .map_err(|source| MigrateError::DatabaseConnection { source })?;
+
+    for migration in &pending {
+        info!("Applying {}", migration.version);
+        let up = migration.read("up.sql")?;
+        (&mut *transaction)
+            .execute(up.as_str())
+            .await
+            .map_err(|source| MigrateError::Migration {
+                version: migration.version.clone(),
+                source,
+            })?;
+        sqlx::query(&insert)
+            .bind(&migration.version)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|source| MigrateError::Migration {
+                version: migration.version.clone(),
+                source,
+            })?;
+    }
+
+    transaction
+        .commit()
+        .await
+        .map_err(|source| MigrateError::DatabaseConnection { source })?;
+
+    Ok(pending
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect())
+}
+
+/// Reverts the last `steps` applied migrations by running their down.sql
+/// Returns the versions that were reverted
+pub async fn migrate_down(
+    current_dir: &Path,
+    environment: Environment,
+    steps: usize,
+) -> Result<Vec<String>, MigrateError> {
+    let (root, database_data, tls, postgres) = load_project(current_dir, environment)?;
+    let mut connection = open(&database_data, &tls, &postgres).await?;
+    let applied = applied_versions(&mut connection, &database_data).await?;
+
+    let migrations = list_migrations(&migrations_dir(&root)?)?;
+
+    // Revert the most recently applied migrations first
+    let mut to_revert: Vec<&Migration> = migrations
+        .iter()
+        .filter(|migration| applied.contains(&migration.version))
+        .collect();
+    to_revert.reverse();
+    to_revert.truncate(steps);
+
+    if to_revert.is_empty() {
+        info!("No migrations to revert");
+        return Ok(Vec::new());
+    }
+
+    let delete = format!(
+        "DELETE FROM {MIGRATIONS_TABLE} WHERE version = {}",
+        placeholder(&database_data.database_type, 1)
+    );
+
+    let mut transaction = connection
+        .begin()
+        .await
+        .map_err(|source| MigrateError::DatabaseConnection { source })?;
+
+    for migration in &to_revert {
+        info!("Reverting {}", migration.version);
+        if !migration.directory.join("down.sql").is_file() {
+            return Err(MigrateError::MissingDown {
+                version: migration.version.clone(),
+            });
+        }
+        let down = migration.read("down.sql")?;
+        (&mut *transaction)
+            .execute(down.as_str())
+            .await
+            .map_err(|source| MigrateError::Migration {
+                version: migration.version.clone(),
+                source,
+            })?;
+        sqlx::query(&delete)
+            .bind(&migration.version)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|source| MigrateError::Migration {
+                version: migration.version.clone(),
+                source,
+            })?;
+    }
+
+    transaction
+        .commit()
+        .await
+        .map_err(|source| MigrateError::DatabaseConnection { source })?;
+
+    Ok(to_revert
+        .into_iter()
+        .map(|migration| migration.version.clone())
+        .collect())
+}
+
+/// Lists every migration on disk and whether it has been applied
+pub async fn migrate_status(
+    current_dir: &Path,
+    environment: Environment,
+) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let (root, database_data, tls, postgres) = load_project(current_dir, environment)?;
+    let mut connection = open(&database_data, &tls, &postgres).await?;
+    let applied = applied_versions(&mut connection, &database_data).await?;
+
+    Ok(list_migrations(&migrations_dir(&root)?)?
+        .into_iter()
+        .map(|migration| MigrationStatus {
+            applied: applied.contains(&migration.version),
+            migration,
+        })
+        .collect())
+}
+
+/// The migrations directory of the project at `root`, as rustyroad.toml
+/// names it
+pub(crate) fn migrations_dir(root: &Path) -> Result<PathBuf, MigrateError> {
+    Ok(rustyroad_toml::read(root)?.generator.migrations_path(root))
+}
+
+/// Finds the project root and reads its database and TLS settings, with the
+/// connection settings of `environment`
+/// SQLite paths in rustyroad.toml are relative to the project root
+pub(crate) fn load_project(
+    current_dir: &Path,
+    environment: Environment,
+) -> Result<(PathBuf, Database, Tls, PostgresSettings), MigrateError> {
+    let root = find_project_root(current_dir)?;
+    let section = rustyroad_toml::read(&root)?
+        .database
+        .for_environment(environment);
+    let database_data = section.database_in(&root)?;
+
+    if let DatabaseType::Mongo = database_data.database_type {
+        return Err(MigrateError::UnsupportedDatabase);
+    }
+
+    let postgres = section.postgres_settings();
+    Ok((root, database_data, section.tls, postgres))
+}
+
+/// Connects to the project database, inside the schema of a Postgres project
+pub(crate) async fn open(
+    database_data: &Database,
+    tls: &Tls,
+    postgres: &PostgresSettings,
+) -> Result<AnyConnection, MigrateError> {
+    let mut connection = connect(database_data, ConnectionTarget::Project, tls)
+        .await
+        .map_err(|source| MigrateError::DatabaseConnection { source })?;
+    use_schema(&mut connection, database_data, postgres)
+        .await
+        .map_err(|source| MigrateError::DatabaseConnection { source })?;
+    Ok(connection)
+}
+
+/// Creates the tracking table if needed and returns the applied versions
+async fn applied_versions(
+    connection: &mut AnyConnection,
+    database_data: &Database,
+) -> Result<Vec<String>, MigrateError> {
+    create_migrations_table(connection, database_data)
+        .await
+        .map_err(|source| MigrateError::DatabaseConnection { source })?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT version FROM {MIGRATIONS_TABLE} ORDER BY version"
+    ))
+    .fetch_all(&mut *connection)
+    .await
+    .map_err(|source| MigrateError::DatabaseConnection { source })?;
+
+    rows.iter()
+        .map(|row| row.try_get::<String, _>("version"))
+        .collect::<Result<_, _>>()
+        .map_err(|source| MigrateError::DatabaseConnection { source })
+}
+
+/// Creates the tracking table unless it exists
+async fn create_migrations_table(
+    connection: &mut AnyConnection,
+    database_data: &Database,
+) -> Result<(), sqlx::Error> {
+    let applied_at_default = match database_data.database_type {
+        DatabaseType::Postgres => "TIMESTAMP NOT NULL DEFAULT now()",
+        _ => "TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP",
+    };
+
+    sqlx::query(&format!(
+        "CREATE TABLE IF NOT EXISTS {MIGRATIONS_TABLE} (\
+         version VARCHAR(255) PRIMARY KEY, \
+         applied_at {applied_at_default})"
+    ))
+    .execute(&mut *connection)
+    .await?;
+    Ok(())
+}
+
+/// Records `version` as applied without running it
+/// Used for the initial schema, which runs while the project is created
+/// A version recorded by an earlier setup is left alone
+pub(crate) async fn mark_applied(
+    connection: &mut AnyConnection,
+    database_data: &Database,
+    version: &str,
+) -> Result<(), sqlx::Error> {
+    create_migrations_table(connection, database_data).await?;
+
+    let recorded = sqlx::query(&format!(
+        "SELECT version FROM {MIGRATIONS_TABLE} WHERE version = {}",
+        placeholder(&database_data.database_type, 1)
+    ))
+    .bind(version)
+    .fetch_optional(&mut *connection)
+    .await?;
+    if recorded.is_some() {
+        return Ok(());
+    }
+
+    sqlx::query(&format!(
+        "INSERT INTO {MIGRATIONS_TABLE} (version) VALUES ({})",
+        placeholder(&database_data.database_type, 1)
+    ))
+    .bind(version)
+    .execute(&mut *connection)
+    .await?;
+    Ok(())
+}
+
+/// Rewrites the statements of the initial schema so they can run again
+/// against a database an interrupted setup already created part of
+/// Tables are only created when missing, and so are indexes on Postgres and
+/// SQLite, extensions on Postgres and triggers on SQLite. Postgres functions
+/// are replaced. MySQL has no `IF NOT EXISTS` for indexes, so its indexes
+/// are left as they are
+pub fn if_not_exists(statements: Vec<String>, database_type: &DatabaseType) -> Vec<String> {
+    let (guarded, replaced): (&[&str], &[&str]) = match database_type {
+        DatabaseType::Postgres => (
+            &["TABLE", "INDEX", "UNIQUE INDEX", "EXTENSION"],
+            &["FUNCTION"],
+        ),
+        DatabaseType::Sqlite => (&["TABLE", "INDEX", "UNIQUE INDEX", "TRIGGER"], &[]),
+        DatabaseType::Mysql | DatabaseType::Mongo => (&["TABLE"], &[]),
+    };
+
+    statements
+        .into_iter()
+        .map(|statement| {
+            let trimmed = statement.trim_start();
+            let upper = trimmed.to_ascii_uppercase();
+
+            for kind in guarded {
+                let prefix = format!("CREATE {kind} ");
+                if upper.starts_with(&prefix)
+                    && !upper[prefix.len()..]
+                        .trim_start()
+                        .starts_with("IF NOT EXISTS")
+                {
+                    let (create, rest) = trimmed.split_at(prefix.len());
+                    return format!("{create}IF NOT EXISTS {rest}");
+                }
+            }
+            for kind in replaced {
+                let prefix = format!("CREATE {kind} ");
+                if upper.starts_with(&prefix) {
+                    return format!("CREATE OR REPLACE {}", &trimmed["CREATE ".len()..]);
+                }
+            }
+            statement
+        })
+        .collect()
+}
+
+/// The SQL keywords a table name follows, in `CREATE TABLE users`,
+/// `REFERENCES users`, `CREATE INDEX ... ON users` and in queries
+const TABLE_KEYWORDS: [&str; 8] = [
+    "TABLE",
+    "EXISTS",
+    "INTO",
+    "REFERENCES",
+    "ON",
+    "FROM",
+    "UPDATE",
+    "JOIN",
+];
+
+/// Prefixes the tables created by `statements` with `prefix` wherever the
+/// statements create or refer to them
+pub fn with_table_prefix(statements: Vec<String>, prefix: &str) -> Vec<String> {
+    if prefix.is_empty() {
+        return statements;
+    }
+
+    let tables: Vec<String> = created_tables(&statements)
+        .iter()
+        .map(|table| table.to_ascii_lowercase())
+        .collect();
+    statements
+        .iter()
+        .map(|statement| prefix_tables(statement, &tables, prefix))
+        .collect()
+}
+
+/// Prefixes every word of `statement` naming one of `tables` that follows
+/// one of the `TABLE_KEYWORDS`
+fn prefix_tables(statement: &str, tables: &[String], prefix: &str) -> String {
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let mut prefixed = String::with_capacity(statement.len());
+    let mut previous = "";
+    let mut rest = statement;
+    while let Some(start) = rest.find(is_word_char) {
+        prefixed.push_str(&rest[..start]);
+        let word_rest = &rest[start..];
+        let end = word_rest
+            .find(|c: char| !is_word_char(c))
+            .unwrap_or(word_rest.len());
+        let word = &word_rest[..end];
+
+        let follows_keyword = TABLE_KEYWORDS
+            .iter()
+            .any(|keyword| previous.eq_ignore_ascii_case(keyword));
+        if follows_keyword && tables.contains(&word.to_ascii_lowercase()) {
+            prefixed.push_str(prefix);
+        }
+        prefixed.push_str(word);
+
+        previous = word;
+        rest = &word_rest[end..];
+    }
+    prefixed.push_str(rest);
+    prefixed
+}
+
+/// The tables created by `statements`, in the order they are created
+pub fn created_tables(statements: &[String]) -> Vec<String> {
+    statements
+        .iter()
+        .filter_map(|statement| created_table(statement))
+        .collect()
+}
+
+/// The table a `CREATE TABLE` statement creates, `None` for other statements
+pub(crate) fn created_table(statement: &str) -> Option<String> {
+    let mut words = statement.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("CREATE") {
+        return None;
+    }
+    if !words.next()?.eq_ignore_ascii_case("TABLE") {
+        return None;
+    }
+
+    let mut name = words.next()?;
+    if name.eq_ignore_ascii_case("IF") {
+        // Skip `NOT EXISTS`
+        words.next()?;
+        words.next()?;
+        name = words.next()?;
+    }
+
+    // The column list may follow the name without a space
+    let name = name.split('(').next().unwrap_or(name);
+    let name = name.trim_matches(|c| c == '"' || c == '`');
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// The down.sql reverting `statements`
+/// Tables are dropped in the reverse order they were created, so tables
+/// referencing others by foreign key are gone before the tables they point at
+pub fn down_sql(statements: &[String]) -> String {
+    created_tables(statements)
+        .iter()
+        .rev()
+        .map(|table| format!("DROP TABLE IF EXISTS {table};\n"))
+        .collect()
+}
+
+/// The up.sql holding `statements`, one per paragraph
+pub fn up_sql(statements: &[String]) -> String {
+    statements
+        .iter()
+        .map(|statement| {
+            let statement = statement.trim().trim_end_matches(';');
+            format!("{statement};\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+
+    use super::*;
+    use crate::initial_sql::load_initial_sql;
+    use crate::layout::project_at;
+
+    /// The initial schema of a new SQLite project in `dir`, as
+    /// `load_initial_sql` adapts it
+    /// The upstream loader appends to its own initial migration, so the
+    /// files have to exist
+    async fn sqlite_schema(dir: &Path) -> Vec<String> {
+        let project = project_at(dir);
+        for file in [
+            &project.initial_migration_up,
+            &project.initial_migration_down,
+        ] {
+            let file = Path::new(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, "").unwrap();
+        }
+        let database_data = Database {
+            name: project.config_dev_db.clone(),
+            username: String::new(),
+            password: String::new(),
+            host: String::new(),
+            port: String::new(),
+            database_type: DatabaseType::Sqlite,
+        };
+
+        load_initial_sql(&project, &database_data, &PostgresSettings::default(), "")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|statement| statement.sql)
+            .collect()
+    }
+
+    #[test]
+    fn guards_every_create_per_dialect() {
+        let statements = || {
+            vec![
+                "CREATE TABLE users (id INTEGER)".to_string(),
+                "CREATE INDEX users_id ON users (id)".to_string(),
+                "CREATE FUNCTION f() RETURNS TRIGGER AS $$ BEGIN END; $$ LANGUAGE plpgsql"
+                    .to_string(),
+            ]
+        };
+
+        assert_eq!(
+            if_not_exists(statements(), &DatabaseType::Postgres),
+            [
+                "CREATE TABLE IF NOT EXISTS users (id INTEGER)",
+                "CREATE INDEX IF NOT EXISTS users_id ON users (id)",
+                "CREATE OR REPLACE FUNCTION f() RETURNS TRIGGER AS $$ BEGIN END; $$ LANGUAGE plpgsql",
+            ]
+        );
+        let mysql = if_not_exists(statements(), &DatabaseType::Mysql);
+        assert_eq!(mysql[0], "CREATE TABLE IF NOT EXISTS users (id INTEGER)");
+        assert_eq!(mysql[1], "CREATE INDEX users_id ON users (id)");
+    }
+
+    #[test]
+    fn leaves_guarded_statements_alone() {
+        let statements = vec!["CREATE TABLE IF NOT EXISTS users (id INTEGER)".to_string()];
+        assert_eq!(
+            if_not_exists(statements.clone(), &DatabaseType::Sqlite),
+            statements
+        );
+    }
+
+    #[tokio::test]
+    async fn sets_up_the_same_sqlite_file_twice() {
+        let dir =
+            std::env::temp_dir().join(format!("rustyroad-if-not-exists-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let schema = sqlite_schema(&dir).await;
+        let path = dir.join("twice.db");
+
+        for _ in 0..2 {
+            let mut connection = SqliteConnectOptions::new()
+                .filename(&path)
+                .create_if_missing(true)
+                .connect()
+                .await
+                .unwrap();
+            for statement in &schema {
+                connection.execute(statement.as_str()).await.unwrap();
+            }
+            connection.close().await.unwrap();
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+use std::fs::File;
+use std::io::Write;
+use std::time::Duration;
+
+use mongodb::bson::doc;
+use mongodb::error::ErrorKind;
+use mongodb::options::ClientOptions;
+use mongodb::Client;
+use rustyroad::database::Database;
+use rustyroad::Project;
+
+use crate::database::{database_url, ConnectionTarget};
+use crate::writers::password::PASSWORD_FUNCTIONS;
+use crate::CreateProjectError;
+
+/// MongoDB error code returned when a collection already exists
+const NAMESPACE_EXISTS: i32 = 48;
+
+/// Connects to the MongoDB server, creates the project database and the
+/// `users` collection
+/// MongoDB creates databases lazily, so creating the first collection is
+/// what actually creates the database on the server
+/// Finding the server gives up after `connect_timeout`
+pub async fn setup_database(
+    database_data: &Database,
+    connect_timeout: Duration,
+) -> Result<(), CreateProjectError> {
+    let mut client_options =
+        ClientOptions::parse(database_url(database_data, ConnectionTarget::Project))
+            .await
+            .map_err(|source| CreateProjectError::Mongo { source })?;
+    client_options.connect_timeout = Some(connect_timeout);
+    client_options.server_selection_timeout = Some(connect_timeout);
+    let client = Client::with_options(client_options)
+        .map_err(|source| CreateProjectError::Mongo { source })?;
+
+    let database = client.database(&database_data.name);
+
+    // Make sure the server is actually reachable before going any further
+    database
+        .run_command(doc! { "ping": 1 }, None)
+        .await
+        .map_err(|source| CreateProjectError::Mongo { source })?;
+
+    match database.create_collection("users", None).await {
+        Ok(()) => Ok(()),
+        // The collection is already there, nothing to do
+        Err(source) => match *source.kind {
+            ErrorKind::Command(ref command_error) if command_error.code == NAMESPACE_EXISTS => {
+                Ok(())
+            }
+            _ => Err(CreateProjectError::Mongo { source }),
+        },
+    }
+}
+
+/// Writes the MongoDB flavored user model
+/// Unlike the SQL models this uses the `mongodb` collection API and an
+/// `ObjectId` primary key. Passwords are stored as Argon2 hashes like there
+pub fn write_to_mongo_user_models(project: &Project) -> Result<(), std::io::Error> {
+    let contents = r#"use futures::stream::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub username: String,
+    pub email: String,
+    pub password_hash: String,
+}
+
+impl User {
+    /// A user with the hash of `password`, for `create`
+    pub fn new(
+        username: &str,
+        email: &str,
+        password: &str,
+    ) -> Result<User, argon2::password_hash::Error> {
+        Ok(User {
+            id: None,
+            username: username.to_string(),
+            email: email.to_string(),
+            password_hash: hash_password(password)?,
+        })
+    }
+
+    /// Whether `password` is the password of this user
+    pub fn verify_password(&self, password: &str) -> bool {
+        verify_password(password, &self.password_hash)
+    }
+
+    fn collection(database: &Database) -> Collection<User> {
+        database.collection::<User>("users")
+    }
+
+    pub async fn create(database: &Database, user: User) -> mongodb::error::Result<ObjectId> {
+        let result = Self::collection(database).insert_one(user, None).await?;
+        Ok(result
+            .inserted_id
+            .as_object_id()
+            .expect("MongoDB always returns an ObjectId for generated ids"))
+    }
+
+    pub async fn find_by_username(
+        database: &Database,
+        username: &str,
+    ) -> mongodb::error::Result<Option<User>> {
+        Self::collection(database)
+            .find_one(doc! { "username": username }, None)
+            .await
+    }
+
+    pub async fn all(database: &Database) -> mongodb::error::Result<Vec<User>> {
+        Self::collection(database)
+            .find(None, None)
+            .await?
+            .try_collect()
+            .await
+    }
+
+    pub async fn delete(database: &Database, id: ObjectId) -> mongodb::error::Result<()> {
+        Self::collection(database)
+            .delete_one(doc! { "_id": id }, None)
+            .await?;
+        Ok(())
+    }
+}
+"#;
+
+    let mut file = File::create(&project.user_model)?;
+    file.write_all(contents.as_bytes())?;
+    file.write_all(format!("\n{PASSWORD_FUNCTIONS}").as_bytes())?;
+    Ok(())
+}
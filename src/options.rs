@@ -0,0 +1,207 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+use crate::database::{Charset, PoolSettings, PostgresSettings, RetryPolicy, TableSettings, Tls};
+use crate::layout::DEFAULT_MIGRATIONS_DIR;
+use crate::workspace::WorkspaceMode;
+use crate::{
+    AuthScheme, CiProvider, CssFramework, Dependency, Example, Framework, OverwritePolicy,
+    SessionStore, TaskRunner, TemplateSource,
+};
+
+/// The address the generated server listens on unless `bind` says otherwise
+pub const DEFAULT_BIND: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8000);
+
+/// Options that control how `create_new_project` generates a project
+/// Every field defaults to the historical behavior so callers can use
+/// `NewProjectOptions::default()` and only opt in to what they need
+#[derive(Debug, Clone, Default)]
+pub struct NewProjectOptions {
+    /// The directory the project directory is created in, the current
+    /// directory when unset
+    pub parent_dir: Option<PathBuf>,
+
+    /// Where the SQLite database file is created, relative to the project
+    /// directory unless absolute, e.g. a data volume outside the repository
+    /// Stored as the database name in rustyroad.toml and .env
+    pub sqlite_path: Option<PathBuf>,
+
+    /// Keep the partially generated directory when generation fails
+    /// Useful for debugging a failing template or database setup
+    pub keep_on_failure: bool,
+
+    /// What happens to the files of an existing project, `Abort` refuses
+    /// the existing directory and the other policies regenerate it
+    pub overwrite: OverwritePolicy,
+
+    /// Print the files that would be written instead of writing them
+    /// and skip every database connection
+    pub dry_run: bool,
+
+    /// Keep the directory of a failed run and, when it is run again, skip
+    /// the steps it finished, recorded in `.rustyroad-progress.json`
+    pub resume: bool,
+
+    /// Keep executing the initial SQL when a command fails instead of
+    /// rolling back and aborting
+    pub ignore_sql_errors: bool,
+
+    /// Where the project files come from, the built-in writers by default
+    /// The database is set up the same way for every template
+    pub template: TemplateSource,
+
+    /// The web framework the generated project is built on
+    pub framework: Framework,
+
+    /// The CSS framework the templates are styled with
+    pub css: CssFramework,
+
+    /// Skip the templates and the Tailwind, PostCSS and npm setup and
+    /// generate a JSON only main.rs and index route
+    pub no_frontend: bool,
+
+    /// Skip the dashboard page behind the login
+    pub no_dashboard: bool,
+
+    /// Skip the header and navbar partials, leaving the base template as
+    /// the only layout
+    pub no_layout: bool,
+
+    /// Skip the integration test under tests/ that starts the app against
+    /// a migrated test database and requests the index route
+    pub no_tests: bool,
+
+    /// Skip the `/health` and `/ready` routes for liveness and readiness
+    /// probes
+    pub no_health: bool,
+
+    /// Skip the `tracing` setup of main.rs and the request logging
+    /// middleware
+    pub no_telemetry: bool,
+
+    /// Skip the CSRF middleware of the Actix session login and the script
+    /// sending its token with every form
+    pub no_csrf: bool,
+
+    /// Skip src/config.rs, the typed settings main.rs and the pool read
+    /// from the environment
+    pub no_config_module: bool,
+
+    /// The address the generated server listens on, `DEFAULT_BIND` when
+    /// unset, e.g. `0.0.0.0:8080` in a container
+    /// The default of the settings in src/config.rs, stored in rustyroad.toml
+    pub bind: Option<SocketAddr>,
+
+    /// The directory the migrations are kept in, relative to the project
+    /// root, `DEFAULT_MIGRATIONS_DIR` when unset, e.g. `db/migrate`
+    /// Stored in rustyroad.toml for `migrate` and the generators
+    pub migrations_dir: Option<String>,
+
+    /// How users log in, a session login page by default
+    /// `Jwt` replaces the login page and dashboard with token issuing
+    /// handlers and `None` skips the user models as well
+    pub auth: AuthScheme,
+
+    /// Where the Actix session login keeps its sessions, a signed cookie by
+    /// default, `Redis` for apps running more than one instance
+    pub session_store: SessionStore,
+
+    /// Extra dependencies of the generated Cargo.toml, a dependency it
+    /// declares already is pinned to the given version
+    pub dependencies: Vec<Dependency>,
+
+    /// Only declare the dependencies the enabled features need, e.g. no
+    /// Tera without a frontend and no sqlx for MongoDB
+    pub minimal_cargo: bool,
+
+    /// Written to the `authors` of the generated Cargo.toml, such as
+    /// `Jane Doe <jane@example.com>`
+    pub author: Option<String>,
+
+    /// Initialize a git repository and commit the generated files
+    pub git: bool,
+
+    /// Whether a project created inside a Cargo workspace joins its
+    /// `members` or becomes a workspace of its own, asked by default
+    pub workspace: WorkspaceMode,
+
+    /// Run `cargo fmt` and `cargo check` in the generated project, only
+    /// reporting failures
+    pub verify: bool,
+
+    /// Like `verify`, but a failing `cargo check` fails the generation
+    pub check: bool,
+
+    /// Also write a Dockerfile and a docker-compose.yml wiring the app to
+    /// a database service
+    pub docker: bool,
+
+    /// Write a CI workflow that builds and tests the project against a
+    /// database service matching its database type
+    pub ci: Option<CiProvider>,
+
+    /// A demo app scaffolded, migrated and seeded on top of the project
+    pub example: Option<Example>,
+
+    /// Write a `justfile` or `Makefile` with the common project tasks
+    pub tasks: Option<TaskRunner>,
+
+    /// How often connecting to the database server is retried before the
+    /// setup gives up, defaults to 5 attempts of at most 10s starting with
+    /// a 1s delay
+    pub retry: RetryPolicy,
+
+    /// TLS settings for Postgres and MySQL connections, stored in
+    /// rustyroad.toml so migrations and seeds connect the same way
+    pub tls: Tls,
+
+    /// The connection pool of the generated application, 10 connections
+    /// by default
+    pub pool: PoolSettings,
+
+    /// The character set and collation of a created MySQL database,
+    /// utf8mb4 by default
+    pub charset: Charset,
+
+    /// The Postgres schema the tables are created in, `public` by default
+    pub postgres: PostgresSettings,
+
+    /// The prefix of every created table, none by default
+    pub tables: TableSettings,
+
+    /// Store `${RR_DB_PASSWORD}` instead of the database password in
+    /// rustyroad.toml, later commands read it from the environment
+    pub password_from_env: bool,
+
+    /// Show a step counter on stderr while the project is generated
+    pub progress: bool,
+
+    /// Ask before creating the database on a Postgres or MySQL server and
+    /// running the initial SQL against it, a typo in the name could
+    /// otherwise alter an existing database
+    pub confirm_database: bool,
+}
+
+impl NewProjectOptions {
+    /// The address the generated server listens on
+    pub fn bind_address(&self) -> SocketAddr {
+        self.bind.unwrap_or(DEFAULT_BIND)
+    }
+
+    /// The directory the migrations are kept in, relative to the project root
+    pub fn migrations_dir(&self) -> &str {
+        self.migrations_dir
+            .as_deref()
+            .unwrap_or(DEFAULT_MIGRATIONS_DIR)
+    }
+
+    /// The `--minimal` preset, only the index route and a plain base
+    /// template: no users, dashboard, header, navbar or CSS framework
+    pub fn minimal(mut self) -> Self {
+        self.auth = AuthScheme::None;
+        self.css = CssFramework::None;
+        self.no_dashboard = true;
+        self.no_layout = true;
+        self
+    }
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// What happens to an existing file a generator would write with other
+/// content, for `--overwrite-policy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OverwritePolicy {
+    /// Fail without touching it, `rustyroad new` refuses an existing
+    /// project directory
+    #[default]
+    Abort,
+    /// Keep the existing file
+    Skip,
+    /// Replace the existing file
+    Overwrite,
+    /// Show the changes and ask before replacing a file the user edited
+    /// since it was generated, without a terminal or with `--non-interactive`
+    /// the file is kept and with `--yes` it is replaced
+    Prompt,
+}
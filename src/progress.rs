@@ -0,0 +1,212 @@
+//! The step counter shown while a project is generated
+//! Generation writes a few dozen files and sets up the database, which can
+//! take a while on a slow disk or server. The counter shows which step is
+//! running, e.g. `[7/16] Writing the frontend`
+
+use std::time::{Duration, Instant};
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use rustyroad::database::{Database, DatabaseType};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AuthScheme;
+use crate::options::NewProjectOptions;
+use crate::template::TemplateSource;
+use crate::workspace::Workspace;
+use crate::writers;
+
+/// A step of generating a project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    Skeleton,
+    Template,
+    Config,
+    CargoToml,
+    MainRs,
+    Csrf,
+    Settings,
+    Pool,
+    Readme,
+    Seeds,
+    Frontend,
+    Routes,
+    HealthRoutes,
+    Env,
+    AuthRoutes,
+    Layout,
+    LoginPages,
+    Docker,
+    Ci,
+    Tasks,
+    Tests,
+    Database,
+    Example,
+    Workspace,
+    Verify,
+    Git,
+}
+
+impl Step {
+    /// What the step does, shown next to the counter
+    pub fn label(self) -> &'static str {
+        match self {
+            Step::Skeleton => "Creating the project directory",
+            Step::Template => "Rendering the template",
+            Step::Config => "Writing rustyroad.toml",
+            Step::CargoToml => "Writing Cargo.toml",
+            Step::MainRs => "Writing main.rs",
+            Step::Csrf => "Writing the CSRF middleware",
+            Step::Settings => "Writing the config module",
+            Step::Pool => "Writing the database pool",
+            Step::Readme => "Writing the README",
+            Step::Seeds => "Writing seeds.sql",
+            Step::Frontend => "Writing the frontend",
+            Step::Routes => "Writing the routes",
+            Step::HealthRoutes => "Writing the health routes",
+            Step::Env => "Writing .gitignore and .env",
+            Step::AuthRoutes => "Writing the token handlers",
+            Step::Layout => "Writing the header and navbar",
+            Step::LoginPages => "Writing the login and dashboard pages",
+            Step::Docker => "Writing the Docker files",
+            Step::Ci => "Writing the CI workflow",
+            Step::Tasks => "Writing the task file",
+            Step::Tests => "Writing the integration tests",
+            Step::Database => "Setting up the database",
+            Step::Example => "Adding the example app",
+            Step::Workspace => "Adding the project to the workspace",
+            Step::Verify => "Running cargo fmt and cargo check",
+            Step::Git => "Initializing the git repository",
+        }
+    }
+}
+
+/// The steps generating a project with `options` goes through, in order
+/// Steps of disabled features are left out, so `--no-frontend` counts fewer
+/// `workspace` is the Cargo workspace the project is created in, if any
+pub fn planned_steps(
+    database_data: &Database,
+    options: &NewProjectOptions,
+    workspace: Option<&Workspace>,
+    regenerating: bool,
+) -> Vec<Step> {
+    let mut steps = Vec::new();
+
+    if options.template != TemplateSource::Builtin {
+        steps.push(Step::Template);
+    } else {
+        if !regenerating {
+            steps.push(Step::Skeleton);
+        }
+        steps.extend([Step::Config, Step::CargoToml, Step::MainRs]);
+        if writers::csrf::uses_csrf(options) {
+            steps.push(Step::Csrf);
+        }
+        if writers::settings::uses_config_module(options) {
+            steps.push(Step::Settings);
+        }
+        if writers::pool::uses_pool(database_data) {
+            steps.push(Step::Pool);
+        }
+        steps.push(Step::Readme);
+        if !matches!(database_data.database_type, DatabaseType::Mongo) {
+            steps.push(Step::Seeds);
+        }
+        if !options.no_frontend {
+            steps.push(Step::Frontend);
+        }
+        steps.push(Step::Routes);
+        if !options.no_health {
+            steps.push(Step::HealthRoutes);
+        }
+        steps.push(Step::Env);
+        if options.auth == AuthScheme::Jwt {
+            steps.push(Step::AuthRoutes);
+        }
+        if !options.no_frontend {
+            if !options.no_layout {
+                steps.push(Step::Layout);
+            }
+            if options.auth.has_login_pages() {
+                steps.push(Step::LoginPages);
+            }
+        }
+        if options.docker {
+            steps.push(Step::Docker);
+        }
+        if options.ci.is_some() {
+            steps.push(Step::Ci);
+        }
+        if options.tasks.is_some() {
+            steps.push(Step::Tasks);
+        }
+        if writers::tests::writes_tests(database_data, options) {
+            steps.push(Step::Tests);
+        }
+    }
+
+    // Dry runs and regenerated projects leave the database alone
+    if !options.dry_run && !regenerating {
+        steps.push(Step::Database);
+        if options.example.is_some() {
+            steps.push(Step::Example);
+        }
+        if workspace.is_some_and(|workspace| workspace.join) {
+            steps.push(Step::Workspace);
+        }
+        if options.verify || options.check {
+            steps.push(Step::Verify);
+        }
+        if options.git {
+            steps.push(Step::Git);
+        }
+    }
+
+    steps
+}
+
+/// Shows the running step on stderr, hidden when stderr isn't a terminal
+pub struct Progress {
+    bar: ProgressBar,
+    steps: Vec<Step>,
+    started: Instant,
+}
+
+impl Progress {
+    /// A counter over `steps`, drawn only when `visible`
+    pub fn new(steps: Vec<Step>, visible: bool) -> Self {
+        let target = if visible {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        let bar = ProgressBar::with_draw_target(Some(steps.len() as u64), target);
+        bar.set_style(
+            ProgressStyle::with_template("{spinner} [{pos}/{len}] {msg}")
+                .expect("the progress template is valid"),
+        );
+        bar.enable_steady_tick(Duration::from_millis(100));
+
+        Progress {
+            bar,
+            steps,
+            started: Instant::now(),
+        }
+    }
+
+    /// Shows that `step` is running, steps that weren't planned are ignored
+    /// The counter counts started steps, since independent steps run
+    /// concurrently and start in any order
+    pub fn start(&self, step: Step) {
+        if self.steps.contains(&step) {
+            self.bar.inc(1);
+            self.bar.set_message(step.label());
+        }
+    }
+
+    /// Removes the counter and returns how long generation took
+    pub fn finish(&self) -> Duration {
+        self.bar.finish_and_clear();
+        self.started.elapsed()
+    }
+}
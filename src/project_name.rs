@@ -0,0 +1,139 @@
+//! Validation of project names
+//! The project name becomes both the directory name and the Cargo package
+//! name, so it has to be something Cargo accepts
+
+use tracing::warn;
+
+use crate::CreateProjectError;
+
+/// Rust keywords, which Cargo refuses as package names
+const KEYWORDS: [&str; 51] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Names Cargo refuses besides keywords, the built-in `test` crate and the
+/// directories of its build output
+const REJECTED: [&str; 5] = ["test", "build", "deps", "examples", "incremental"];
+
+/// Names that shadow crates shipped with Rust and make imports confusing,
+/// Cargo accepts them with a warning
+const SHADOWED: [&str; 5] = ["core", "std", "alloc", "proc_macro", "proc-macro"];
+
+/// Checks that `name` can be used as a project directory and Cargo package name
+/// Names Cargo refuses are rejected with a sanitized suggestion before
+/// anything is written to disk, names that shadow built-in crates only print
+/// a warning
+pub fn validate_project_name(name: &str) -> Result<(), CreateProjectError> {
+    let invalid = |reason: &str| CreateProjectError::InvalidName {
+        name: name.to_string(),
+        reason: reason.to_string(),
+        suggestion: sanitize_project_name(name),
+    };
+
+    if name.is_empty() {
+        return Err(invalid("the name is empty"));
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(invalid("the name must not start with a digit"));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(invalid(
+            "the name may only contain letters, digits, `_` and `-`",
+        ));
+    }
+    if KEYWORDS.contains(&name) {
+        return Err(invalid("the name is a Rust keyword"));
+    }
+
+    if REJECTED.contains(&name) {
+        return Err(invalid("Cargo reserves the name"));
+    }
+
+    if SHADOWED.contains(&name) {
+        warn!("`{name}` shadows a crate that ships with Rust, consider a different name");
+    }
+
+    Ok(())
+}
+
+/// Turns an arbitrary string into a valid project name
+/// `my project!` becomes `my_project`
+pub fn sanitize_project_name(name: &str) -> String {
+    let mut sanitized = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            sanitized.push(c.to_ascii_lowercase());
+        } else if !sanitized.ends_with('_') {
+            sanitized.push('_');
+        }
+    }
+
+    let sanitized = sanitized.trim_matches('_');
+    let sanitized = if sanitized.is_empty() {
+        "rustyroad".to_string()
+    } else if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("app_{sanitized}")
+    } else {
+        sanitized.to_string()
+    };
+
+    if KEYWORDS.contains(&sanitized.as_str()) || REJECTED.contains(&sanitized.as_str()) {
+        format!("{sanitized}_app")
+    } else {
+        sanitized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The reason and the suggestion `name` is rejected with
+    fn rejection(name: &str) -> (String, String) {
+        match validate_project_name(name) {
+            Err(CreateProjectError::InvalidName {
+                reason, suggestion, ..
+            }) => (reason, suggestion),
+            other => panic!("`{name}` was accepted: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn suggests_a_sanitized_name() {
+        assert_eq!(
+            rejection("my project!"),
+            (
+                "the name may only contain letters, digits, `_` and `-`".to_string(),
+                "my_project".to_string()
+            )
+        );
+        assert_eq!(rejection("1blog").1, "app_1blog");
+        assert_eq!(rejection("").1, "rustyroad");
+    }
+
+    #[test]
+    fn rejects_names_cargo_refuses() {
+        for (name, suggestion) in [
+            ("fn", "fn_app"),
+            ("test", "test_app"),
+            ("build", "build_app"),
+        ] {
+            assert_eq!(rejection(name).1, suggestion);
+        }
+    }
+
+    #[test]
+    fn accepts_names_that_shadow_built_in_crates() {
+        for name in ["core", "std", "alloc", "proc_macro"] {
+            assert!(validate_project_name(name).is_ok(), "{name}");
+        }
+        assert!(validate_project_name("blog").is_ok());
+    }
+}
@@ -0,0 +1,77 @@
+//! Interactive questions on the terminal
+//! How they are answered is set once for the process, so automation can
+//! run every command with `--yes` or `--non-interactive` without stdin
+
+use std::io::{self, IsTerminal, Write};
+use std::sync::OnceLock;
+
+use tracing::debug;
+
+/// How questions are answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PromptMode {
+    /// Asks on the terminal, without one every question is answered no
+    #[default]
+    Interactive,
+    /// Answers every question yes without asking, `--yes`
+    AssumeYes,
+    /// Answers every question no without asking, `--non-interactive`
+    /// Questions without a safe answer make their command fail
+    NonInteractive,
+}
+
+/// The mode of this process, `Interactive` until set
+static MODE: OnceLock<PromptMode> = OnceLock::new();
+
+/// Sets how questions are answered for the rest of the process
+/// Only the first call has an effect
+pub fn set_mode(mode: PromptMode) {
+    if MODE.set(mode).is_err() {
+        debug!("The prompt mode is already set");
+    }
+}
+
+/// How questions are answered
+pub fn mode() -> PromptMode {
+    MODE.get().copied().unwrap_or_default()
+}
+
+/// Whether `confirm` can answer other than no, because it answers yes
+/// or there is a terminal to ask on
+pub fn can_answer() -> bool {
+    match mode() {
+        PromptMode::Interactive => io::stdin().is_terminal(),
+        PromptMode::AssumeYes => true,
+        PromptMode::NonInteractive => false,
+    }
+}
+
+/// Asks a yes/no question on stderr and reads the answer from stdin
+/// Anything but an explicit yes counts as no. Without a terminal on stdin
+/// or in `--non-interactive` mode nothing is read and the answer is no,
+/// in `--yes` mode it is yes
+pub fn confirm(question: &str) -> io::Result<bool> {
+    match mode() {
+        PromptMode::AssumeYes => {
+            debug!("{question} Answered yes by --yes");
+            return Ok(true);
+        }
+        PromptMode::NonInteractive => {
+            debug!("{question} Answered no by --non-interactive");
+            return Ok(false);
+        }
+        PromptMode::Interactive if !io::stdin().is_terminal() => {
+            debug!("{question} Answered no, stdin isn't a terminal");
+            return Ok(false);
+        }
+        PromptMode::Interactive => {}
+    }
+
+    eprint!("{question} [y/N] ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,120 @@
+//! The state file that lets a failed `rustyroad new` continue where it stopped
+//! Every finished step is recorded in `.rustyroad-progress.json` inside the
+//! project. `--resume` skips the recorded steps on the next run, and the file
+//! is removed once the project is complete
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+use crate::progress::Step;
+
+/// Location of the state file, relative to the project root
+pub const STATE_FILE: &str = ".rustyroad-progress.json";
+
+/// What is stored in the state file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    completed: Vec<Step>,
+}
+
+/// The steps of the current run that are done, shared by the threads
+/// writing the project files
+/// Dry runs and regenerated projects are never resumed and get a disabled
+/// state, which records nothing
+#[derive(Debug)]
+pub struct ResumeState {
+    path: PathBuf,
+    recording: bool,
+    state: Mutex<State>,
+}
+
+impl ResumeState {
+    /// A state that records nothing
+    pub fn disabled() -> Self {
+        ResumeState {
+            path: PathBuf::from(STATE_FILE),
+            recording: false,
+            state: Mutex::default(),
+        }
+    }
+
+    /// A fresh state for the project at `root`, forgetting any earlier run
+    pub fn new(root: &Path) -> Self {
+        ResumeState {
+            path: root.join(STATE_FILE),
+            recording: true,
+            state: Mutex::default(),
+        }
+    }
+
+    /// The state a previous run of the project at `root` left behind, a
+    /// fresh one if there is none
+    pub fn load(root: &Path) -> io::Result<Self> {
+        let path = root.join(STATE_FILE);
+        let state = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?,
+            Err(why) if why.kind() == io::ErrorKind::NotFound => State::default(),
+            Err(why) => return Err(why),
+        };
+
+        Ok(ResumeState {
+            path,
+            recording: true,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// The state file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether a previous run got past any step
+    pub fn is_resuming(&self) -> bool {
+        !self.lock().completed.is_empty()
+    }
+
+    /// Whether `step` was finished by a previous run
+    pub fn is_complete(&self, step: Step) -> bool {
+        self.lock().completed.contains(&step)
+    }
+
+    /// Records that `step` is finished
+    pub fn complete(&self, step: Step) -> io::Result<()> {
+        if !self.recording {
+            return Ok(());
+        }
+
+        let mut state = self.lock();
+        if !state.completed.contains(&step) {
+            state.completed.push(step);
+        }
+        let contents = serde_json::to_string_pretty(&*state)
+            .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+        fs::write(&self.path, contents + "\n")
+    }
+
+    /// Removes the state file once the project is complete
+    pub fn finish(&self) -> io::Result<()> {
+        if !self.recording {
+            return Ok(());
+        }
+
+        match fs::remove_file(&self.path) {
+            Err(why) if why.kind() != io::ErrorKind::NotFound => Err(why),
+            _ => Ok(()),
+        }
+    }
+
+    /// The recorded steps, shared by the threads of `FileSink::run_all`
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("no write panics while holding the resume state")
+    }
+}
@@ -0,0 +1,613 @@
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::{Database, DatabaseType};
+use rustyroad::Project;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AuthScheme;
+use crate::cockroach;
+use crate::css::CssFramework;
+use crate::database::{
+    is_sqlite_memory, Charset, PoolSettings, PostgresFlavor, PostgresSettings, TableSettings, Tls,
+};
+use crate::filesystem::{FileSystem, RealFileSystem};
+use crate::framework::Framework;
+use crate::layout::{project_at, DEFAULT_MIGRATIONS_DIR};
+use crate::options::DEFAULT_BIND;
+use crate::session::SessionStore;
+use crate::GenerateError;
+
+/// Name of the file that marks the root of a RustyRoad project
+pub const RUSTYROAD_TOML: &str = "rustyroad.toml";
+
+/// The environment variable the password is read from when rustyroad.toml
+/// doesn't store one
+pub const PASSWORD_VARIABLE: &str = "RR_DB_PASSWORD";
+
+/// The environment a command runs in, selecting the database of the
+/// matching `[database.<environment>]` section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Environment {
+    #[default]
+    Development,
+    Test,
+    Production,
+}
+
+impl Environment {
+    /// The name of the environment's section in rustyroad.toml
+    pub fn name(self) -> &'static str {
+        match self {
+            Environment::Development => "development",
+            Environment::Test => "test",
+            Environment::Production => "production",
+        }
+    }
+}
+
+/// A `[database.<environment>]` section, the connection settings that
+/// differ from the `[database]` section in one environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvironmentSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_port: Option<String>,
+}
+
+/// The parts of rustyroad.toml the CLI reads back
+#[derive(Debug, Clone, Deserialize)]
+pub struct RustyRoadToml {
+    pub database: DatabaseSection,
+    /// Missing in projects generated before the section existed
+    #[serde(default)]
+    pub generator: GeneratorSettings,
+}
+
+/// The `[generator]` section, the choices made when the project was created
+/// Later generators read it so they produce code that matches the project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorSettings {
+    pub framework: Framework,
+    /// Whether the project has templates and a Tailwind frontend
+    /// `false` for projects generated with `--no-frontend`
+    pub frontend: bool,
+    /// The CSS framework the templates are styled with
+    pub css: CssFramework,
+    /// How users log in
+    pub auth: AuthScheme,
+    /// The address the server listens on by default
+    pub bind: String,
+    /// Where the sessions of the login are kept
+    pub session_store: SessionStore,
+    /// The directory the migrations are kept in, relative to the root
+    pub migrations_dir: String,
+    /// The CLI that generated the project, e.g. `rustyroad 0.1.0`
+    /// Missing in projects generated before it was recorded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_by: Option<String>,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        GeneratorSettings {
+            framework: Framework::default(),
+            frontend: true,
+            css: CssFramework::default(),
+            auth: AuthScheme::default(),
+            bind: DEFAULT_BIND.to_string(),
+            session_store: SessionStore::default(),
+            migrations_dir: DEFAULT_MIGRATIONS_DIR.to_string(),
+            generated_by: None,
+        }
+    }
+}
+
+impl GeneratorSettings {
+    /// The migrations directory of the project at `root`
+    pub fn migrations_path(&self, root: &Path) -> PathBuf {
+        root.join(&self.migrations_dir)
+    }
+}
+
+/// How this CLI signs the projects it generates, `rustyroad x.y.z`
+pub fn generated_by() -> String {
+    format!("rustyroad {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// The `[database]` section written by `Project::write_to_rustyroad_toml`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatabaseSection {
+    pub database_name: String,
+    #[serde(default)]
+    pub database_user: String,
+    /// A `${RR_DB_PASSWORD}` placeholder for projects generated with
+    /// `--db-password-from-env`, missing in the older ones
+    #[serde(default)]
+    pub database_password: Option<String>,
+    #[serde(default)]
+    pub database_host: String,
+    #[serde(default)]
+    pub database_port: String,
+    pub database_type: String,
+    /// The `[database.tls]` section, only written when TLS was configured
+    #[serde(default)]
+    pub tls: Tls,
+    /// The `[database.pool]` section, missing in older projects
+    #[serde(default)]
+    pub pool: PoolSettings,
+    /// The `[database.charset]` section, only written for MySQL
+    #[serde(default)]
+    pub charset: Charset,
+    /// The `[database.postgres]` section, only written for a schema other
+    /// than `public`
+    #[serde(default)]
+    pub postgres: PostgresSettings,
+    /// The `[database.tables]` section, only written for a table prefix
+    #[serde(default)]
+    pub tables: TableSettings,
+    /// The `[database.development]` section, missing in older projects
+    #[serde(default)]
+    pub development: Option<EnvironmentSettings>,
+    /// The `[database.test]` section, missing in older projects
+    #[serde(default)]
+    pub test: Option<EnvironmentSettings>,
+    /// The `[database.production]` section, never written by the generator
+    #[serde(default)]
+    pub production: Option<EnvironmentSettings>,
+}
+
+/// Walks up from `start` until a directory containing rustyroad.toml is found
+/// Returns `GenerateError::NotInProject` if the filesystem root is reached
+pub fn find_project_root(start: &Path) -> Result<PathBuf, GenerateError> {
+    find_project_root_on(&RealFileSystem, start)
+}
+
+/// `find_project_root` on `fs`
+pub fn find_project_root_on(fs: &dyn FileSystem, start: &Path) -> Result<PathBuf, GenerateError> {
+    start
+        .ancestors()
+        .find(|dir| fs.is_file(&dir.join(RUSTYROAD_TOML)))
+        .map(Path::to_path_buf)
+        .ok_or_else(|| GenerateError::NotInProject {
+            start: start.to_path_buf(),
+        })
+}
+
+/// Reads and parses the rustyroad.toml in the project root
+pub fn read(root: &Path) -> Result<RustyRoadToml, GenerateError> {
+    read_on(&RealFileSystem, root)
+}
+
+/// `read` on `fs`
+pub fn read_on(fs: &dyn FileSystem, root: &Path) -> Result<RustyRoadToml, GenerateError> {
+    let path = root.join(RUSTYROAD_TOML);
+    let contents = fs
+        .read_to_string(&path)
+        .map_err(|source| GenerateError::FileRead {
+            path: path.clone(),
+            source,
+        })?;
+
+    toml::from_str(&contents).map_err(|source| GenerateError::InvalidConfig { path, source })
+}
+
+/// Loads the project containing `start` together with the database it was
+/// generated with, so commands run inside a project don't need the database
+/// settings again
+pub fn load_from_rustyroad_toml(start: &Path) -> Result<(Project, Database), GenerateError> {
+    let root = find_project_root(start)?;
+    let database_data = read(&root)?.database.database_in(&root)?;
+    Ok((project_at(&root), database_data))
+}
+
+/// Parses the database type as stored in rustyroad.toml
+pub fn parse_database_type(value: &str) -> Option<DatabaseType> {
+    match value.to_lowercase().as_str() {
+        "sqlite" => Some(DatabaseType::Sqlite),
+        "postgres" | "postgresql" => Some(DatabaseType::Postgres),
+        // CockroachDB is reached through the Postgres driver
+        "cockroachdb" | "cockroach" => Some(DatabaseType::Postgres),
+        "mysql" => Some(DatabaseType::Mysql),
+        "mongo" | "mongodb" => Some(DatabaseType::Mongo),
+        _ => None,
+    }
+}
+
+impl DatabaseSection {
+    /// The section with the settings of `environment` applied
+    /// Without a section for the environment the `[database]` settings are
+    /// used as they are
+    pub fn for_environment(&self, environment: Environment) -> DatabaseSection {
+        let overrides = match environment {
+            Environment::Development => &self.development,
+            Environment::Test => &self.test,
+            Environment::Production => &self.production,
+        };
+        let Some(overrides) = overrides.clone() else {
+            return self.clone();
+        };
+
+        DatabaseSection {
+            database_name: overrides
+                .database_name
+                .unwrap_or_else(|| self.database_name.clone()),
+            database_user: overrides
+                .database_user
+                .unwrap_or_else(|| self.database_user.clone()),
+            database_password: overrides
+                .database_password
+                .or_else(|| self.database_password.clone()),
+            database_host: overrides
+                .database_host
+                .unwrap_or_else(|| self.database_host.clone()),
+            database_port: overrides
+                .database_port
+                .unwrap_or_else(|| self.database_port.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// Converts the stored section back into the `Database` the writers expect
+    /// `${VARIABLE}` placeholders in the connection settings are replaced
+    /// with the environment variable, a password left out of the file is
+    /// read from `RR_DB_PASSWORD`
+    pub fn to_database(&self) -> Result<Database, GenerateError> {
+        let database_type = parse_database_type(&self.database_type).ok_or_else(|| {
+            GenerateError::UnknownDatabaseType {
+                value: self.database_type.clone(),
+            }
+        })?;
+
+        let password = match &self.database_password {
+            Some(password) => expand_variables("database_password", password)?,
+            None => env::var(PASSWORD_VARIABLE).unwrap_or_default(),
+        };
+        Ok(Database {
+            name: expand_variables("database_name", &self.database_name)?,
+            username: expand_variables("database_user", &self.database_user)?,
+            password,
+            host: expand_variables("database_host", &self.database_host)?,
+            port: expand_variables("database_port", &self.database_port)?,
+            database_type,
+        })
+    }
+
+    /// The `[database.postgres]` settings together with the server flavor,
+    /// which is stored as the database type
+    pub fn postgres_settings(&self) -> PostgresSettings {
+        let flavor = if self
+            .database_type
+            .eq_ignore_ascii_case(cockroach::DATABASE_TYPE)
+        {
+            PostgresFlavor::Cockroachdb
+        } else {
+            PostgresFlavor::Postgres
+        };
+        PostgresSettings {
+            flavor,
+            ..self.postgres.clone()
+        }
+    }
+
+    /// Like `to_database`, with a relative SQLite path resolved against the
+    /// project `root` so the database is found from any working directory
+    pub fn database_in(&self, root: &Path) -> Result<Database, GenerateError> {
+        let mut database_data = self.to_database()?;
+        if matches!(database_data.database_type, DatabaseType::Sqlite)
+            && !is_sqlite_memory(&database_data)
+            && Path::new(&database_data.name).is_relative()
+        {
+            database_data.name = root.join(&database_data.name).display().to_string();
+        }
+        Ok(database_data)
+    }
+}
+
+/// Replaces the `database_password` the upstream writer stored in the
+/// rustyroad.toml at `path` with a `${RR_DB_PASSWORD}` placeholder, commands
+/// then read the password from the environment
+pub fn use_password_placeholder(fs: &dyn FileSystem, path: &Path) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+    let placeholder = format!("database_password = \"${{{PASSWORD_VARIABLE}}}\"");
+    let lines: Vec<&str> = contents
+        .lines()
+        .map(|line| {
+            if line.split('=').next().map(str::trim) == Some("database_password") {
+                placeholder.as_str()
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    fs.write(path, (lines.join("\n") + "\n").as_bytes())
+}
+
+/// Replaces every `${VARIABLE}` in the `setting` value `value` with the
+/// environment variable, which must be set
+/// A `$` not followed by `{` is kept as is
+fn expand_variables(setting: &'static str, value: &str) -> Result<String, GenerateError> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(length) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let variable = &rest[start + 2..start + 2 + length];
+        let resolved = env::var(variable).map_err(|_| GenerateError::UnsetVariable {
+            setting,
+            variable: variable.to_string(),
+        })?;
+
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&resolved);
+        rest = &rest[start + 2 + length + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Replaces the `database_type` the upstream writer stored in the
+/// rustyroad.toml at `path`, for servers its `DatabaseType` can't name
+pub fn set_database_type(fs: &dyn FileSystem, path: &Path, database_type: &str) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+    let lines: Vec<String> = contents
+        .lines()
+        .map(|line| {
+            if line.split('=').next().map(str::trim) == Some("database_type") {
+                format!("database_type = \"{database_type}\"")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+
+    fs.write(path, (lines.join("\n") + "\n").as_bytes())
+}
+
+/// Appends the `[generator]` section to the rustyroad.toml at `path`
+/// The upstream writer owns the rest of the file, so the section is added
+/// after it rather than rewriting the whole file
+pub fn write_generator_settings(
+    fs: &dyn FileSystem,
+    path: &Path,
+    settings: &GeneratorSettings,
+) -> io::Result<()> {
+    #[derive(Serialize)]
+    struct Section<'a> {
+        generator: &'a GeneratorSettings,
+    }
+
+    let section = toml::to_string(&Section {
+        generator: settings,
+    })
+    .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+
+    append(fs, path, &format!("\n{section}"))
+}
+
+/// Appends the `[database.tls]` section to the rustyroad.toml at `path`
+/// TOML allows a sub-table to follow other tables, so the upstream
+/// `[database]` section can stay untouched
+pub fn write_tls_settings(fs: &dyn FileSystem, path: &Path, tls: &Tls) -> io::Result<()> {
+    append_section(fs, path, "database.tls", tls)
+}
+
+/// Appends the `[database.pool]` section with the connection pool settings
+/// the generated pool module was written with
+pub fn write_pool_settings(
+    fs: &dyn FileSystem,
+    path: &Path,
+    pool: &PoolSettings,
+) -> io::Result<()> {
+    append_section(fs, path, "database.pool", pool)
+}
+
+/// Appends the `[database.charset]` section with the character set and
+/// collation the MySQL database was created with
+pub fn write_charset_settings(
+    fs: &dyn FileSystem,
+    path: &Path,
+    charset: &Charset,
+) -> io::Result<()> {
+    append_section(fs, path, "database.charset", charset)
+}
+
+/// Appends the `[database.postgres]` section with the schema the tables of
+/// the project were created in
+pub fn write_postgres_settings(
+    fs: &dyn FileSystem,
+    path: &Path,
+    postgres: &PostgresSettings,
+) -> io::Result<()> {
+    append_section(fs, path, "database.postgres", postgres)
+}
+
+/// Appends the `[database.tables]` section with the prefix of the tables
+pub fn write_table_settings(
+    fs: &dyn FileSystem,
+    path: &Path,
+    tables: &TableSettings,
+) -> io::Result<()> {
+    append_section(fs, path, "database.tables", tables)
+}
+
+/// Appends the `[database.development]` and `[database.test]` sections,
+/// the project database and a separate one for the tests
+pub fn write_environments(
+    fs: &dyn FileSystem,
+    path: &Path,
+    database_data: &Database,
+) -> io::Result<()> {
+    let development = EnvironmentSettings {
+        database_name: Some(database_data.name.clone()),
+        ..EnvironmentSettings::default()
+    };
+    let test = EnvironmentSettings {
+        database_name: Some(test_database_name(database_data)),
+        ..EnvironmentSettings::default()
+    };
+
+    append_section(fs, path, "database.development", &development)?;
+    append_section(fs, path, "database.test", &test)
+}
+
+/// The name of the test database next to `database_data`, `blog_test` for
+/// `blog` and `blog_test.db` for a SQLite `blog.db`
+pub fn test_database_name(database_data: &Database) -> String {
+    if is_sqlite_memory(database_data) {
+        return database_data.name.clone();
+    }
+
+    let name = Path::new(&database_data.name);
+    match (
+        matches!(database_data.database_type, DatabaseType::Sqlite),
+        name.file_stem(),
+        name.extension(),
+    ) {
+        (true, Some(stem), Some(extension)) => name
+            .with_file_name(format!(
+                "{}_test.{}",
+                stem.to_string_lossy(),
+                extension.to_string_lossy()
+            ))
+            .display()
+            .to_string(),
+        _ => format!("{}_test", database_data.name),
+    }
+}
+
+/// Appends the `[database.server]` section with the server version detected
+/// while setting up the database, kept for debugging connection problems
+pub fn write_server_version(fs: &dyn FileSystem, path: &Path, version: &str) -> io::Result<()> {
+    #[derive(Serialize)]
+    struct Server<'a> {
+        version: &'a str,
+    }
+
+    append_section(fs, path, "database.server", &Server { version })
+}
+
+/// Appends `value` as the table `header` to the rustyroad.toml at `path`
+fn append_section(
+    fs: &dyn FileSystem,
+    path: &Path,
+    header: &str,
+    value: &impl Serialize,
+) -> io::Result<()> {
+    let section =
+        toml::to_string(value).map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+
+    append(fs, path, &format!("\n[{header}]\n{section}"))
+}
+
+/// Appends `text` to the file at `path`, which has to exist
+fn append(fs: &dyn FileSystem, path: &Path, text: &str) -> io::Result<()> {
+    let mut contents = fs.read(path)?;
+    contents.extend_from_slice(text.as_bytes());
+    fs.write(path, &contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+
+    /// The `[database]` section as the upstream writer stores it
+    const UPSTREAM: &str = r#"[database]
+database_name = "blog"
+database_user = "app"
+database_password = "secret"
+database_host = "localhost"
+database_port = "26257"
+database_type = "postgres"
+"#;
+
+    /// A memory filesystem with the upstream rustyroad.toml at the returned path
+    fn upstream_toml() -> (MemoryFileSystem, &'static Path) {
+        let fs = MemoryFileSystem::new();
+        let path = Path::new("rustyroad.toml");
+        fs.write(path, UPSTREAM.as_bytes()).unwrap();
+        (fs, path)
+    }
+
+    fn parse(fs: &MemoryFileSystem, path: &Path) -> RustyRoadToml {
+        toml::from_str(&fs.contents(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn cockroachdb_round_trips_through_the_database_type() {
+        let (fs, path) = upstream_toml();
+        let postgres = PostgresSettings {
+            schema: "billing".to_string(),
+            flavor: PostgresFlavor::Cockroachdb,
+        };
+        set_database_type(&fs, path, cockroach::DATABASE_TYPE).unwrap();
+        write_postgres_settings(&fs, path, &postgres).unwrap();
+
+        let database = parse(&fs, path).database;
+        assert_eq!(database.database_type, "cockroachdb");
+        assert_eq!(database.postgres_settings(), postgres);
+        assert!(matches!(
+            database.to_database().unwrap().database_type,
+            DatabaseType::Postgres
+        ));
+    }
+
+    #[test]
+    fn postgres_keeps_its_flavor() {
+        let (fs, path) = upstream_toml();
+        let database = parse(&fs, path).database;
+        assert_eq!(database.postgres_settings(), PostgresSettings::default());
+    }
+
+    #[test]
+    fn expands_set_variables() {
+        env::set_var("RUSTYROAD_TEST_HOST", "db.internal");
+        assert_eq!(
+            expand_variables("database_host", "${RUSTYROAD_TEST_HOST}:5432").unwrap(),
+            "db.internal:5432"
+        );
+        assert_eq!(
+            expand_variables("database_host", "$HOST ${RUSTYROAD_TEST_HOST} ${").unwrap(),
+            "$HOST db.internal ${"
+        );
+    }
+
+    #[test]
+    fn rejects_unset_variables() {
+        env::remove_var("RUSTYROAD_TEST_UNSET");
+        assert!(matches!(
+            expand_variables("database_user", "${RUSTYROAD_TEST_UNSET}"),
+            Err(GenerateError::UnsetVariable {
+                setting: "database_user",
+                variable,
+            }) if variable == "RUSTYROAD_TEST_UNSET"
+        ));
+    }
+
+    #[test]
+    fn password_placeholder_round_trips_through_the_environment() {
+        let (fs, path) = upstream_toml();
+        use_password_placeholder(&fs, path).unwrap();
+
+        let contents = fs.contents(path).unwrap();
+        assert!(contents.contains("database_password = \"${RR_DB_PASSWORD}\""));
+        assert!(!contents.contains("secret"));
+
+        env::set_var(PASSWORD_VARIABLE, "from the environment");
+        let database = parse(&fs, path).database.to_database().unwrap();
+        assert_eq!(database.password, "from the environment");
+    }
+}
@@ -0,0 +1,95 @@
+//! Loads development data into the database of an existing project
+//! Seeds are plain SQL in `seeds.sql` at the project root. A
+//! `seeds.<dialect>.sql` next to it, e.g. `seeds.postgres.sql`, takes
+//! precedence for that database, so one project can carry seeds for every
+//! dialect it runs on
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::DatabaseType;
+use sqlx::{Connection, Executor};
+use tracing::info;
+
+use crate::migrate::{self, load_project, open};
+use crate::rustyroad_toml::Environment;
+use crate::SeedError;
+
+/// Name of the seeds file shared by every dialect
+pub const SEEDS_FILE: &str = "seeds.sql";
+
+/// The name of the dialect used in `seeds.<dialect>.sql`
+pub fn dialect_name(database_type: &DatabaseType) -> &'static str {
+    match database_type {
+        DatabaseType::Sqlite => "sqlite",
+        DatabaseType::Postgres => "postgres",
+        DatabaseType::Mysql => "mysql",
+        DatabaseType::Mongo => "mongo",
+    }
+}
+
+/// The seeds file to run for `database_type`, the dialect specific one if
+/// the project has it
+pub fn seeds_file(root: &Path, database_type: &DatabaseType) -> PathBuf {
+    let dialect_file = root.join(format!("seeds.{}.sql", dialect_name(database_type)));
+    if dialect_file.is_file() {
+        dialect_file
+    } else {
+        root.join(SEEDS_FILE)
+    }
+}
+
+/// Runs the seeds of the project containing `current_dir` against the
+/// database of `environment`
+/// With `reset` every applied migration is reverted and applied again first,
+/// so the seeds land in freshly created tables
+/// The seeds run in a single transaction
+/// Returns the seeds file that was run
+pub async fn seed(
+    current_dir: &Path,
+    environment: Environment,
+    reset: bool,
+) -> Result<PathBuf, SeedError> {
+    let (root, database_data, tls, postgres) = load_project(current_dir, environment)?;
+
+    let path = seeds_file(&root, &database_data.database_type);
+    if !path.is_file() {
+        return Err(SeedError::MissingSeeds { path });
+    }
+    let seeds = fs::read_to_string(&path).map_err(|source| SeedError::FileRead {
+        path: path.clone(),
+        source,
+    })?;
+
+    if reset {
+        let reverted = migrate::migrate_down(current_dir, environment, usize::MAX).await?;
+        let applied = migrate::migrate_up(current_dir, environment).await?;
+        info!(
+            "Reset the database, reverted {} and applied {} migration(s)",
+            reverted.len(),
+            applied.len()
+        );
+    }
+
+    let mut connection = open(&database_data, &tls, &postgres).await?;
+    let mut transaction = connection
+        .begin()
+        .await
+        .map_err(|source| SeedError::DatabaseConnection { source })?;
+
+    info!("Seeding from {}", path.display());
+    (&mut *transaction)
+        .execute(seeds.as_str())
+        .await
+        .map_err(|source| SeedError::Seed {
+            path: path.clone(),
+            source,
+        })?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|source| SeedError::DatabaseConnection { source })?;
+
+    Ok(path)
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Where the sessions of the Actix login are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStore {
+    /// In a signed cookie in the browser, nothing to run next to the app
+    #[default]
+    Cookie,
+    /// In Redis, so sessions survive restarts and are shared between
+    /// instances
+    Redis,
+}
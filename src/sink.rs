@@ -0,0 +1,509 @@
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Instant;
+
+use similar::TextDiff;
+use tracing::{debug, info, warn};
+
+use crate::filesystem::{FileSystem, RealFileSystem};
+use crate::manifest::Manifest;
+use crate::overwrite::OverwritePolicy;
+use crate::prompt::{can_answer, confirm};
+
+/// A group of writes that doesn't depend on any other group
+pub type WriteJob<'a, E> = Box<dyn FnOnce() -> Result<(), E> + Send + 'a>;
+
+/// How a `FileSink` treats the files it is asked to write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SinkMode {
+    /// Write files to disk
+    #[default]
+    Disk,
+    /// Only log the paths that would be written
+    DryRun,
+    /// Regenerate the files of an existing project, changed files are
+    /// handled by the `OverwritePolicy` of the sink
+    Force,
+}
+
+/// Where the generator sends the files it produces
+/// Every write in `create_new_project` goes through a sink so that
+/// `--dry-run` can report what would be written without touching disk and
+/// `--force` can regenerate an existing project safely
+/// The sink records the hash of every file it writes in the project manifest
+#[derive(Debug)]
+pub struct FileSink {
+    mode: SinkMode,
+    policy: OverwritePolicy,
+    root: PathBuf,
+    fs: Box<dyn FileSystem>,
+    manifest: Mutex<Manifest>,
+    created: Mutex<Vec<PathBuf>>,
+}
+
+impl FileSink {
+    /// A sink for the project at `root` on the real filesystem
+    /// When regenerating, the manifest of the previous run is loaded so
+    /// edited files can be told apart from untouched ones
+    pub fn new(root: impl Into<PathBuf>, mode: SinkMode) -> io::Result<Self> {
+        FileSink::on(Box::new(RealFileSystem), root, mode)
+    }
+
+    /// A sink for the project at `root` on `fs`, such as a
+    /// `MemoryFileSystem` to generate files without touching disk
+    pub fn on(
+        fs: Box<dyn FileSystem>,
+        root: impl Into<PathBuf>,
+        mode: SinkMode,
+    ) -> io::Result<Self> {
+        let root = root.into();
+        let manifest = match mode {
+            SinkMode::Force => Manifest::load(fs.as_ref(), &root)?,
+            SinkMode::Disk | SinkMode::DryRun => Manifest::default(),
+        };
+
+        Ok(FileSink {
+            mode,
+            policy: OverwritePolicy::Prompt,
+            root,
+            fs,
+            manifest: Mutex::new(manifest),
+            created: Mutex::default(),
+        })
+    }
+
+    /// A sink continuing an interrupted run on the project at `root`
+    /// Files are written to disk like `SinkMode::Disk`, but the manifest of
+    /// the interrupted run is kept so the files it wrote stay recorded
+    pub fn resuming(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        let manifest = Manifest::load(&RealFileSystem, &root)?;
+
+        Ok(FileSink {
+            mode: SinkMode::Disk,
+            policy: OverwritePolicy::Prompt,
+            root,
+            fs: Box::new(RealFileSystem),
+            manifest: Mutex::new(manifest),
+            created: Mutex::default(),
+        })
+    }
+
+    /// The filesystem the writers of this sink write to
+    pub fn fs(&self) -> &dyn FileSystem {
+        self.fs.as_ref()
+    }
+
+    /// The sink with `policy` for the files a regeneration changes,
+    /// `Prompt` by default
+    pub fn with_policy(mut self, policy: OverwritePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Whether files are only being reported, not written
+    pub fn is_dry_run(&self) -> bool {
+        self.mode == SinkMode::DryRun
+    }
+
+    /// Whether the files of an existing project are being regenerated
+    pub fn is_regenerating(&self) -> bool {
+        self.mode == SinkMode::Force
+    }
+
+    /// Runs `write`, a writer that writes `path` on its own
+    /// In dry-run mode the writer is skipped and the target path logged instead
+    /// When regenerating, a changed file is replaced or its previous content
+    /// restored as the overwrite policy says
+    pub fn run(&self, path: &str, write: impl FnOnce() -> io::Result<()>) -> io::Result<()> {
+        match self.mode {
+            SinkMode::Disk => {
+                write()?;
+                // The step counter reports progress, every file is only debug output
+                debug!("Created {path}");
+                self.track(path);
+                self.record(path)
+            }
+            SinkMode::DryRun => {
+                info!("Would write {path}");
+                self.track(path);
+                Ok(())
+            }
+            // Directories have nothing to compare
+            SinkMode::Force if self.fs.is_dir(Path::new(path)) => {
+                write()?;
+                self.track(path);
+                Ok(())
+            }
+            SinkMode::Force => {
+                let previous = self.fs.read(Path::new(path)).ok();
+                write()?;
+                let current = self.fs.read(Path::new(path))?;
+
+                match previous {
+                    None => info!("Created {path}"),
+                    Some(previous) if previous == current => info!("Unchanged {path}"),
+                    Some(previous) => {
+                        let overwrite = match self.policy {
+                            OverwritePolicy::Abort => {
+                                self.fs.write(Path::new(path), &previous)?;
+                                return Err(io::Error::new(
+                                    io::ErrorKind::AlreadyExists,
+                                    format!("{path} exists with other content"),
+                                ));
+                            }
+                            OverwritePolicy::Skip => false,
+                            OverwritePolicy::Overwrite => {
+                                print_diff(path, &previous, &current);
+                                true
+                            }
+                            OverwritePolicy::Prompt => {
+                                print_diff(path, &previous, &current);
+
+                                // Without anyone to ask, edits are always kept
+                                let edited =
+                                    self.manifest().is_edited(&self.relative(path), &previous);
+                                if !edited {
+                                    true
+                                } else if can_answer() {
+                                    confirm(&format!(
+                                        "{path} was edited since it was generated, overwrite it?"
+                                    ))?
+                                } else {
+                                    warn!("{path} was edited since it was generated");
+                                    false
+                                }
+                            }
+                        };
+                        if !overwrite {
+                            self.fs.write(Path::new(path), &previous)?;
+                            info!("Kept {path}");
+                            return Ok(());
+                        }
+                        info!("Updated {path}");
+                    }
+                }
+                self.track(path);
+                self.record(path)
+            }
+        }
+    }
+
+    /// Writes `contents` to `path` through `run`, so it is tracked and
+    /// only logged in dry-run mode like any other write
+    pub fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        self.run(&path.display().to_string(), || {
+            self.fs.write(path, contents.as_bytes())
+        })
+    }
+
+    /// Runs independent groups of writes, each on its own thread when
+    /// writing to disk, which hides the latency of network filesystems
+    /// Dry runs and regeneration keep the order of `jobs`, so their log
+    /// lines, diffs and prompts don't interleave
+    /// Every job runs even if another fails, the first error is returned
+    pub fn run_all<E: Send>(&self, jobs: Vec<WriteJob<'_, E>>) -> Result<(), E> {
+        let count = jobs.len();
+        let started = Instant::now();
+
+        let results: Vec<Result<(), E>> = match self.mode {
+            SinkMode::Disk => thread::scope(|scope| {
+                let handles: Vec<_> = jobs.into_iter().map(|job| scope.spawn(job)).collect();
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|why| panic::resume_unwind(why))
+                    })
+                    .collect()
+            }),
+            SinkMode::DryRun | SinkMode::Force => jobs.into_iter().map(|job| job()).collect(),
+        };
+
+        debug!(
+            "Ran {count} groups of writes {} in {:.1?}",
+            if self.mode == SinkMode::Disk {
+                "concurrently"
+            } else {
+                "in order"
+            },
+            started.elapsed()
+        );
+        results.into_iter().collect()
+    }
+
+    /// Creates `path` and any missing parent directories
+    pub fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        match self.mode {
+            SinkMode::Disk | SinkMode::Force => self.fs.create_dir(path)?,
+            SinkMode::DryRun => info!("Would create directory {}", path.display()),
+        }
+        self.track(path);
+        Ok(())
+    }
+
+    /// Adds `path` to the created paths, for files a writer outside the
+    /// sink produced such as the SQLite database
+    pub fn track(&self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        let mut created = self
+            .created
+            .lock()
+            .expect("no write panics while holding the created paths");
+        if !created.contains(&path) {
+            created.push(path);
+        }
+    }
+
+    /// Tracks and records `path`, a file a writer outside the sink wrote,
+    /// so regenerating the project can tell whether it was edited since
+    pub fn adopt(&self, path: &str) -> io::Result<()> {
+        self.track(path);
+        self.record(path)
+    }
+
+    /// Copies `path`, a file an upstream writer wrote to disk, onto the
+    /// filesystem of the sink so the writers after it can edit it there
+    pub fn import(&self, path: &str) -> io::Result<()> {
+        let contents = fs::read(path)?;
+        if let Some(parent) = Path::new(path).parent() {
+            self.fs.create_dir(parent)?;
+        }
+        self.fs.write(Path::new(path), &contents)
+    }
+
+    /// Every file and directory written so far, in the order they were
+    /// first written, or that would have been written in a dry run
+    pub fn created_paths(&self) -> Vec<PathBuf> {
+        self.created
+            .lock()
+            .expect("no write panics while holding the created paths")
+            .clone()
+    }
+
+    /// Saves the hashes of the written files to the project manifest
+    pub fn save_manifest(&self) -> io::Result<()> {
+        if self.is_dry_run() {
+            return Ok(());
+        }
+        self.manifest().save(self.fs(), &self.root)
+    }
+
+    /// Records the current content of `path` in the manifest
+    /// Directories aren't recorded, only the files in them
+    /// Files changed after generation, such as rustyroad.toml once the
+    /// database is set up, are recorded again so they don't look edited
+    pub fn record(&self, path: &str) -> io::Result<()> {
+        if !self.fs.is_file(Path::new(path)) {
+            return Ok(());
+        }
+
+        let contents = self.fs.read(Path::new(path))?;
+        self.manifest().record(&self.relative(path), &contents);
+        Ok(())
+    }
+
+    /// The manifest, shared by the threads of `run_all`
+    fn manifest(&self) -> MutexGuard<'_, Manifest> {
+        self.manifest
+            .lock()
+            .expect("no write panics while holding the manifest")
+    }
+
+    /// `path` relative to the project root, the key used in the manifest
+    fn relative(&self, path: &str) -> String {
+        Path::new(path)
+            .strip_prefix(&self.root)
+            .unwrap_or(Path::new(path))
+            .display()
+            .to_string()
+    }
+}
+
+/// Prints a unified diff between the previous and the regenerated content
+fn print_diff(path: &str, previous: &[u8], current: &[u8]) {
+    let previous = String::from_utf8_lossy(previous);
+    let current = String::from_utf8_lossy(current);
+
+    eprint!(
+        "{}",
+        TextDiff::from_lines(previous.as_ref(), current.as_ref())
+            .unified_diff()
+            .header(path, path)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Condvar};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+
+    /// How long a write waits at a `Gate` before it gives up, only reached
+    /// when the writes don't overlap
+    const GATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// How many writes are at a `Gate`, and the most that were at once
+    #[derive(Debug, Default)]
+    struct InFlight {
+        current: usize,
+        most: usize,
+    }
+
+    /// Holds every write until `expected` writes were in flight at once,
+    /// so concurrent writes meet there and writes in order pass one by one
+    #[derive(Debug, Default)]
+    struct Gate {
+        expected: usize,
+        in_flight: Mutex<InFlight>,
+        changed: Condvar,
+    }
+
+    impl Gate {
+        fn new(expected: usize) -> Arc<Self> {
+            Arc::new(Gate {
+                expected,
+                ..Gate::default()
+            })
+        }
+
+        /// Waits until `expected` writes were in flight at once
+        fn pass(&self) {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            in_flight.current += 1;
+            in_flight.most = in_flight.most.max(in_flight.current);
+            self.changed.notify_all();
+
+            let (mut in_flight, _) = self
+                .changed
+                .wait_timeout_while(in_flight, GATE_TIMEOUT, |in_flight| {
+                    in_flight.most < self.expected
+                })
+                .unwrap();
+            in_flight.current -= 1;
+        }
+
+        /// The most writes that were in flight at once
+        fn most_in_flight(&self) -> usize {
+            self.in_flight.lock().unwrap().most
+        }
+    }
+
+    /// A filesystem in memory whose writes pass through `gate`
+    #[derive(Debug)]
+    struct GatedFileSystem {
+        fs: MemoryFileSystem,
+        gate: Arc<Gate>,
+    }
+
+    impl FileSystem for GatedFileSystem {
+        fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+            self.gate.pass();
+            self.fs.write(path, contents)
+        }
+
+        fn create_dir(&self, path: &Path) -> io::Result<()> {
+            self.fs.create_dir(path)
+        }
+
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.fs.read(path)
+        }
+
+        fn is_file(&self, path: &Path) -> bool {
+            self.fs.is_file(path)
+        }
+
+        fn is_dir(&self, path: &Path) -> bool {
+            self.fs.is_dir(path)
+        }
+
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            self.fs.remove_file(path)
+        }
+
+        fn remove_dir(&self, path: &Path) -> io::Result<()> {
+            self.fs.remove_dir(path)
+        }
+    }
+
+    /// Writes `count` files into blog/ through `gate` with a sink in
+    /// `mode`, each in its own job, and returns the sink
+    fn write_files(gate: &Arc<Gate>, mode: SinkMode, count: usize) -> FileSink {
+        let fs = GatedFileSystem {
+            fs: MemoryFileSystem::new(),
+            gate: Arc::clone(gate),
+        };
+        let sink = FileSink::on(Box::new(fs), "blog", mode).unwrap();
+        sink.create_dir_all(Path::new("blog")).unwrap();
+        let jobs: Vec<WriteJob<'_, io::Error>> = (0..count)
+            .map(|index| {
+                let sink = &sink;
+                Box::new(move || {
+                    sink.write(&Path::new("blog").join(format!("{index}.txt")), "contents")
+                }) as WriteJob<'_, io::Error>
+            })
+            .collect();
+
+        sink.run_all(jobs).unwrap();
+        sink
+    }
+
+    #[test]
+    fn writes_to_disk_concurrently() {
+        const FILES: usize = 8;
+        let concurrent = Gate::new(FILES);
+        let in_order = Gate::new(1);
+
+        let concurrent_sink = write_files(&concurrent, SinkMode::Disk, FILES);
+        let in_order_sink = write_files(&in_order, SinkMode::Force, FILES);
+
+        assert_eq!(concurrent.most_in_flight(), FILES);
+        assert_eq!(in_order.most_in_flight(), 1);
+        for index in 0..FILES {
+            let path = Path::new("blog").join(format!("{index}.txt"));
+            assert!(concurrent_sink.fs().is_file(&path));
+            assert!(in_order_sink.fs().is_file(&path));
+        }
+    }
+
+    #[test]
+    fn runs_every_job_and_returns_the_first_error() {
+        let sink = FileSink::on(Box::new(MemoryFileSystem::new()), "blog", SinkMode::Disk).unwrap();
+        let jobs: Vec<WriteJob<'_, io::Error>> = vec![
+            Box::new(|| sink.write(Path::new("missing/a.txt"), "a")),
+            Box::new(|| sink.write(Path::new("b.txt"), "b")),
+        ];
+
+        let error = sink.run_all(jobs).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(sink.fs().is_file(Path::new("b.txt")));
+    }
+
+    #[test]
+    fn dry_run_reports_the_paths_a_disk_run_writes() {
+        let generate = |mode| {
+            let sink = FileSink::on(Box::new(MemoryFileSystem::new()), "blog", mode).unwrap();
+            sink.create_dir_all(Path::new("blog/src")).unwrap();
+            sink.write(Path::new("blog/src/main.rs"), "fn main() {}\n")
+                .unwrap();
+            sink.write(Path::new("blog/Cargo.toml"), "[package]\n")
+                .unwrap();
+            sink
+        };
+
+        let disk = generate(SinkMode::Disk);
+        let dry_run = generate(SinkMode::DryRun);
+
+        assert_eq!(dry_run.created_paths(), disk.created_paths());
+        assert!(disk.fs().is_file(Path::new("blog/src/main.rs")));
+        assert!(!dry_run.fs().exists(Path::new("blog")));
+    }
+}
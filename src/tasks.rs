@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// The task runner a generated project gets a task file for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskRunner {
+    /// just, `justfile`
+    Just,
+    /// GNU make, `Makefile`
+    Make,
+}
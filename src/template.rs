@@ -0,0 +1,187 @@
+//! Custom project templates for `rustyroad new --template`
+//! A template is a directory tree, local or in a git repository, that is
+//! copied into the new project. Files ending in `.tera` are rendered with the
+//! project variables and saved without the extension, so a runtime Tera
+//! template is shipped as `index.html.tera.tera`
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::Database;
+use rustyroad::Project;
+use tera::{Context, Tera};
+use tracing::info;
+
+use crate::generators::database_type_name;
+use crate::layout::package_name;
+use crate::sink::FileSink;
+use crate::{git, write_rustyroad_toml, CreateProjectError, NewProjectOptions};
+
+/// Extension of the files rendered while scaffolding
+const TEMPLATE_EXTENSION: &str = "tera";
+
+/// Where the files of a new project come from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TemplateSource {
+    /// The writers built into RustyRoad
+    #[default]
+    Builtin,
+    /// A template directory on disk
+    Path(PathBuf),
+    /// A git repository, cloned without its history
+    Git(String),
+}
+
+impl TemplateSource {
+    /// Interprets a `--template` value as a git URL or a local path
+    /// URLs are recognized by their scheme, the scp-like `git@host:` syntax or
+    /// a trailing `.git`
+    pub fn parse(value: &str) -> Self {
+        let is_git = ["https://", "http://", "ssh://", "git://", "git@"]
+            .iter()
+            .any(|prefix| value.starts_with(prefix))
+            || value.ends_with(".git");
+
+        if is_git {
+            TemplateSource::Git(value.to_string())
+        } else {
+            TemplateSource::Path(PathBuf::from(value))
+        }
+    }
+}
+
+impl fmt::Display for TemplateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateSource::Builtin => f.write_str("built-in template"),
+            TemplateSource::Path(path) => write!(f, "{}", path.display()),
+            TemplateSource::Git(url) => f.write_str(url),
+        }
+    }
+}
+
+/// Creates the project directory from a custom template and renders it
+/// Writes rustyroad.toml as well unless the template ships its own
+pub fn scaffold(
+    source: &TemplateSource,
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+    sink: &FileSink,
+) -> Result<(), CreateProjectError> {
+    let destination = Path::new(&project.name);
+    if destination.exists() {
+        return Err(CreateProjectError::DirectoryExists {
+            path: destination.to_path_buf(),
+        });
+    }
+
+    if sink.is_dry_run() {
+        info!("Would copy {source} to {}", destination.display());
+        return Ok(());
+    }
+
+    match source {
+        // Written by `write_builtin_template` instead
+        TemplateSource::Builtin => return Ok(()),
+        TemplateSource::Path(template) => {
+            if !template.is_dir() {
+                return Err(CreateProjectError::TemplateNotFound {
+                    template: source.to_string(),
+                });
+            }
+            copy_tree(template, destination)?;
+        }
+        TemplateSource::Git(url) => {
+            git::clone_repository(url, destination)?;
+            let history = destination.join(".git");
+            fs::remove_dir_all(&history).map_err(CreateProjectError::file_write(history))?;
+        }
+    }
+    info!("Copied {source} to {}", destination.display());
+
+    render_tree(
+        destination,
+        &template_context(project, database_data, options),
+    )?;
+
+    if !Path::new(&project.rustyroad_toml).exists() {
+        write_rustyroad_toml(project, database_data, options, sink)?;
+    }
+
+    Ok(())
+}
+
+/// The variables available to the `.tera` files of a template and to the
+/// view overrides of a new project
+pub(crate) fn template_context(
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> Context {
+    let mut context = Context::new();
+    context.insert("project_name", package_name(project));
+    context.insert(
+        "database_type",
+        &database_type_name(&database_data.database_type).to_lowercase(),
+    );
+    context.insert("database_name", &database_data.name);
+    context.insert("framework", &options.framework);
+    context.insert("css", &options.css);
+    context.insert("host", &options.bind_address().ip().to_string());
+    context.insert("port", &options.bind_address().port());
+    context
+}
+
+/// Copies the directory tree at `from` to `to`, leaving out any `.git` directory
+fn copy_tree(from: &Path, to: &Path) -> Result<(), CreateProjectError> {
+    fs::create_dir_all(to).map_err(CreateProjectError::file_write(to))?;
+
+    let entries = fs::read_dir(from).map_err(CreateProjectError::file_write(from))?;
+    for entry in entries {
+        let entry = entry.map_err(CreateProjectError::file_write(from))?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+
+        if source.is_dir() {
+            if entry.file_name() != ".git" {
+                copy_tree(&source, &target)?;
+            }
+        } else {
+            fs::copy(&source, &target).map_err(CreateProjectError::file_write(&target))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders every `.tera` file below `dir` in place, dropping the extension
+fn render_tree(dir: &Path, context: &Context) -> Result<(), CreateProjectError> {
+    let entries = fs::read_dir(dir).map_err(CreateProjectError::file_write(dir))?;
+    for entry in entries {
+        let path = entry.map_err(CreateProjectError::file_write(dir))?.path();
+
+        if path.is_dir() {
+            render_tree(&path, context)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == TEMPLATE_EXTENSION)
+        {
+            let template =
+                fs::read_to_string(&path).map_err(CreateProjectError::file_write(&path))?;
+            let rendered = Tera::one_off(&template, context, false).map_err(|source| {
+                CreateProjectError::TemplateRender {
+                    path: path.clone(),
+                    source,
+                }
+            })?;
+
+            let target = path.with_extension("");
+            fs::write(&target, rendered).map_err(CreateProjectError::file_write(&target))?;
+            fs::remove_file(&path).map_err(CreateProjectError::file_write(&path))?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,217 @@
+//! The `created_at` and `updated_at` columns of generated schemas
+//! The dialects disagree on how a column keeps the time of the last update:
+//! MySQL has `ON UPDATE CURRENT_TIMESTAMP`, Postgres needs a trigger
+//! function and SQLite a trigger per table. Both the initial schema and the
+//! migrations of `generate model` take their timestamp SQL from here
+
+use rustyroad::database::DatabaseType;
+
+use crate::migrate::created_table;
+
+/// The trigger function Postgres tables with an `updated_at` share
+pub const SET_UPDATED_AT_FUNCTION: &str = "rustyroad_set_updated_at";
+
+/// The `created_at` and `updated_at` column definitions
+/// Both default to the insert time, MySQL also updates `updated_at` itself
+pub fn timestamp_columns(database_type: &DatabaseType) -> [String; 2] {
+    let (column_type, now) = match database_type {
+        DatabaseType::Postgres => ("TIMESTAMP", "now()"),
+        _ => ("TIMESTAMP", "CURRENT_TIMESTAMP"),
+    };
+    let on_update = match database_type {
+        DatabaseType::Mysql => " ON UPDATE CURRENT_TIMESTAMP",
+        _ => "",
+    };
+
+    [
+        format!("created_at {column_type} NOT NULL DEFAULT {now}"),
+        format!("updated_at {column_type} NOT NULL DEFAULT {now}{on_update}"),
+    ]
+}
+
+/// The statements that keep `updated_at` of a freshly created `table`
+/// current, the Postgres trigger function included
+/// MySQL needs none, its column definition does it
+pub fn updated_at_statements(table: &str, database_type: &DatabaseType) -> Vec<String> {
+    let mut statements = Vec::new();
+    if let DatabaseType::Postgres = database_type {
+        statements.push(set_updated_at_function());
+    }
+    statements.extend(updated_at_trigger(table, database_type));
+    statements
+}
+
+/// The trigger keeping `updated_at` of `table` current on Postgres and
+/// SQLite, Postgres tables share the trigger function
+fn updated_at_trigger(table: &str, database_type: &DatabaseType) -> Vec<String> {
+    let trigger = format!("{}_set_updated_at", table.replace('.', "_"));
+    match database_type {
+        DatabaseType::Postgres => vec![
+            format!("DROP TRIGGER IF EXISTS {trigger} ON {table}"),
+            format!(
+                "CREATE TRIGGER {trigger} BEFORE UPDATE ON {table} \
+                 FOR EACH ROW EXECUTE FUNCTION {SET_UPDATED_AT_FUNCTION}()"
+            ),
+        ],
+        // The WHEN clause keeps the trigger's own update from firing it again
+        DatabaseType::Sqlite => vec![format!(
+            "CREATE TRIGGER {trigger} AFTER UPDATE ON {table} FOR EACH ROW \
+             WHEN NEW.updated_at = OLD.updated_at \
+             BEGIN UPDATE {table} SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id; END"
+        )],
+        DatabaseType::Mysql | DatabaseType::Mongo => Vec::new(),
+    }
+}
+
+/// The Postgres function setting `updated_at` to the time of the update
+fn set_updated_at_function() -> String {
+    format!(
+        "CREATE OR REPLACE FUNCTION {SET_UPDATED_AT_FUNCTION}() RETURNS TRIGGER AS $$ \
+         BEGIN NEW.updated_at = now(); RETURN NEW; END; \
+         $$ LANGUAGE plpgsql"
+    )
+}
+
+/// Makes the `updated_at` columns of the tables `statements` create follow
+/// updates in every dialect
+/// MySQL columns get `ON UPDATE CURRENT_TIMESTAMP`, Postgres and SQLite
+/// tables a trigger right after them
+pub fn auto_update_timestamps(
+    statements: Vec<String>,
+    database_type: &DatabaseType,
+) -> Vec<String> {
+    let mut updated = Vec::with_capacity(statements.len());
+    let mut function_written = false;
+
+    for statement in statements {
+        let table = created_table(&statement)
+            .filter(|_| statement.to_ascii_lowercase().contains("updated_at"));
+        let Some(table) = table else {
+            updated.push(statement);
+            continue;
+        };
+
+        match database_type {
+            DatabaseType::Mysql => updated.push(with_on_update(&statement, "CURRENT_TIMESTAMP")),
+            DatabaseType::Postgres if !function_written => {
+                updated.push(statement);
+                updated.extend(updated_at_statements(&table, database_type));
+                function_written = true;
+            }
+            _ => {
+                updated.push(statement);
+                updated.extend(updated_at_trigger(&table, database_type));
+            }
+        }
+    }
+
+    updated
+}
+
+/// Adds `ON UPDATE <now>` to the `updated_at` column of a `CREATE TABLE`
+/// statement, unless it is no timestamp or has it already
+/// MySQL sets it to `CURRENT_TIMESTAMP`, CockroachDB to `now()`
+pub(crate) fn with_on_update(statement: &str, now: &str) -> String {
+    let lower = statement.to_ascii_lowercase();
+    let Some(start) = lower.find("updated_at") else {
+        return statement.to_string();
+    };
+
+    // The definition ends at the next comma or the closing parenthesis of
+    // the column list, whichever comes first outside nested parentheses
+    let mut depth = 0;
+    let mut end = statement.len();
+    for (offset, c) in statement[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                end = start + offset;
+                break;
+            }
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                end = start + offset;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let definition = lower[start..end].to_string();
+    let is_timestamp = definition.contains("timestamp") || definition.contains("datetime");
+    if !is_timestamp || definition.contains("on update") {
+        return statement.to_string();
+    }
+
+    let definition_end = start + statement[start..end].trim_end().len();
+    format!(
+        "{} ON UPDATE {now}{}",
+        &statement[..definition_end],
+        &statement[definition_end..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSTS: &str = "CREATE TABLE posts (id INTEGER PRIMARY KEY, \
+                         updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP)";
+
+    #[test]
+    fn mysql_updates_the_column_itself() {
+        let statements = auto_update_timestamps(vec![POSTS.to_string()], &DatabaseType::Mysql);
+        assert_eq!(
+            statements,
+            [
+                "CREATE TABLE posts (id INTEGER PRIMARY KEY, \
+              updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP)"
+            ]
+        );
+    }
+
+    #[test]
+    fn postgres_shares_one_trigger_function() {
+        let statements = auto_update_timestamps(
+            vec![POSTS.to_string(), POSTS.replace("posts", "users")],
+            &DatabaseType::Postgres,
+        );
+        let functions = statements
+            .iter()
+            .filter(|statement| statement.contains("CREATE OR REPLACE FUNCTION"))
+            .count();
+        assert_eq!(functions, 1);
+        assert!(statements
+            .iter()
+            .any(|statement| statement.starts_with("CREATE TRIGGER users_set_updated_at")));
+    }
+
+    #[test]
+    fn sqlite_gets_a_trigger_per_table() {
+        let statements = auto_update_timestamps(vec![POSTS.to_string()], &DatabaseType::Sqlite);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[1].starts_with("CREATE TRIGGER posts_set_updated_at AFTER UPDATE"));
+    }
+
+    #[test]
+    fn leaves_tables_without_updated_at_alone() {
+        let statement = "CREATE TABLE tags (id INTEGER PRIMARY KEY)".to_string();
+        for database_type in [
+            DatabaseType::Mysql,
+            DatabaseType::Postgres,
+            DatabaseType::Sqlite,
+        ] {
+            assert_eq!(
+                auto_update_timestamps(vec![statement.clone()], &database_type),
+                [statement.as_str()]
+            );
+        }
+    }
+
+    #[test]
+    fn adds_on_update_once() {
+        let once = with_on_update(POSTS, "now()");
+        assert!(once.ends_with("DEFAULT CURRENT_TIMESTAMP ON UPDATE now())"));
+        assert_eq!(with_on_update(&once, "now()"), once);
+    }
+}
@@ -0,0 +1,62 @@
+//! Looks up the external programs the CLI shells out to
+//! Every program is looked up on `PATH` before it is run, so a missing one
+//! is reported with install instructions instead of a bare OS error
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::CreateProjectError;
+
+/// How to install the programs the CLI runs
+const INSTALL_HINTS: [(&str, &str); 5] = [
+    ("cargo", "install Rust from https://rustup.rs"),
+    ("git", "install it from https://git-scm.com/downloads"),
+    ("npx", "install Node.js from https://nodejs.org"),
+    (
+        "docker",
+        "install it from https://docs.docker.com/get-docker",
+    ),
+    ("diesel", "install it with `cargo install diesel_cli`"),
+];
+
+/// The path of `tool` on `PATH`, `None` if it isn't installed
+pub fn find_tool(tool: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .flat_map(|dir| candidates(&dir, tool))
+        .find(|candidate| candidate.is_file())
+}
+
+/// The path of `tool` on `PATH`
+/// Fails with `ToolNotFound` and install instructions if it isn't installed
+pub fn require_tool(tool: &'static str) -> Result<PathBuf, CreateProjectError> {
+    find_tool(tool).ok_or(CreateProjectError::ToolNotFound {
+        tool,
+        hint: install_hint(tool),
+    })
+}
+
+/// How to install `tool`
+pub fn install_hint(tool: &str) -> &'static str {
+    INSTALL_HINTS
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, hint)| *hint)
+        .unwrap_or("install it and make sure it is on PATH")
+}
+
+/// The files in `dir` that would run as `tool`
+/// Windows also tries the extensions of `PATHEXT`
+fn candidates(dir: &Path, tool: &str) -> Vec<PathBuf> {
+    let mut candidates = vec![dir.join(tool)];
+    if cfg!(windows) {
+        let extensions = env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string());
+        candidates.extend(
+            extensions
+                .split(';')
+                .filter(|extension| !extension.is_empty())
+                .map(|extension| dir.join(format!("{tool}{}", extension.to_lowercase()))),
+        );
+    }
+    candidates
+}
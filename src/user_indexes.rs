@@ -0,0 +1,247 @@
+//! The unique indexes on the login columns of the users table
+//! Every login looks a user up by username or email, without an index each
+//! one scans the whole table. The index also keeps two users from sharing
+//! a login, which the upstream schema leaves to the application
+
+use rustyroad::database::DatabaseType;
+
+use crate::migrate::created_table;
+
+/// The columns users log in with
+pub const LOGIN_COLUMNS: [&str; 2] = ["username", "email"];
+
+/// The characters of a MySQL `TEXT` column the index covers
+/// 191 four byte characters stay below the 767 byte key limit of older
+/// InnoDB row formats
+const MYSQL_TEXT_PREFIX: usize = 191;
+
+/// Adds a unique constraint, and with it a unique index, on each login
+/// column to the `CREATE TABLE` statement for `users` among `statements`
+/// Declared inside the table so it is only created along with it, MySQL
+/// has no `CREATE INDEX IF NOT EXISTS` to run again safely
+/// Columns already declared `UNIQUE` or as the primary key are left alone,
+/// as are the statements of a schema without a users table
+pub fn unique_login_indexes(
+    statements: Vec<String>,
+    database_type: &DatabaseType,
+    users: &str,
+) -> Vec<String> {
+    if let DatabaseType::Mongo = database_type {
+        return statements;
+    }
+
+    statements
+        .into_iter()
+        .map(|statement| {
+            let is_users =
+                created_table(&statement).is_some_and(|table| table.eq_ignore_ascii_case(users));
+            if is_users {
+                with_login_constraints(&statement, database_type, users)
+            } else {
+                statement
+            }
+        })
+        .collect()
+}
+
+/// The `CREATE TABLE` statement `statement` of `users` with a `CONSTRAINT
+/// .. UNIQUE` for each login column it defines without one
+fn with_login_constraints(statement: &str, database_type: &DatabaseType, users: &str) -> String {
+    let Some((start, end)) = column_list(statement) else {
+        return statement.to_string();
+    };
+    let definitions = column_definitions(&statement[start + 1..end]);
+    // The index lives in the schema of the table, so it is named without it
+    let name = users.rsplit('.').next().unwrap_or(users);
+
+    let constraints: String = LOGIN_COLUMNS
+        .iter()
+        .filter_map(|column| {
+            let definition = definitions.iter().find(|definition| {
+                let name = definition.split_whitespace().next().unwrap_or_default();
+                name.trim_matches(|c| c == '"' || c == '`')
+                    .eq_ignore_ascii_case(column)
+            })?;
+            if is_unique(definition) || has_unique_constraint(&definitions, column) {
+                return None;
+            }
+
+            let key = match database_type {
+                DatabaseType::Mysql if is_text(definition) => {
+                    format!("{column}({MYSQL_TEXT_PREFIX})")
+                }
+                _ => column.to_string(),
+            };
+            Some(format!(
+                ",\n    CONSTRAINT {name}_{column}_key UNIQUE ({key})"
+            ))
+        })
+        .collect();
+    if constraints.is_empty() {
+        return statement.to_string();
+    }
+
+    // After the last definition, before the whitespace closing the list
+    let last = statement[..end].trim_end().len();
+    format!("{}{constraints}{}", &statement[..last], &statement[last..])
+}
+
+/// The positions of the parentheses around the column list of a `CREATE
+/// TABLE` statement, table options after it may contain parentheses too
+fn column_list(statement: &str) -> Option<(usize, usize)> {
+    let start = statement.find('(')?;
+    let mut depth = 0;
+    for (offset, c) in statement[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start, start + offset));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The column and constraint definitions of the column list `columns`,
+/// split at the commas outside of parentheses
+fn column_definitions(columns: &str) -> Vec<String> {
+    let mut definitions = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in columns.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                definitions.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    definitions.push(current.trim().to_string());
+    definitions
+}
+
+/// Whether the column `definition` makes it unique by itself
+fn is_unique(definition: &str) -> bool {
+    let upper = definition.to_ascii_uppercase();
+    upper.split_whitespace().any(|word| word == "UNIQUE") || upper.contains("PRIMARY KEY")
+}
+
+/// Whether a table constraint among `definitions` makes `column` unique
+/// alone, like `UNIQUE (email)`
+fn has_unique_constraint(definitions: &[String], column: &str) -> bool {
+    definitions.iter().any(|definition| {
+        let upper = definition.to_ascii_uppercase();
+        let is_constraint = upper.starts_with("UNIQUE")
+            || upper.starts_with("CONSTRAINT")
+            || upper.starts_with("PRIMARY KEY");
+        let columns = definition
+            .split_once('(')
+            .and_then(|(_, rest)| rest.split_once(')'))
+            // Without the prefix length of a MySQL text key, `email(191)`
+            .map(|(columns, _)| columns.split('(').next().unwrap_or(columns))
+            .map(|columns| columns.trim().trim_matches(|c| c == '"' || c == '`'));
+        is_constraint
+            && is_unique(definition)
+            && columns.is_some_and(|columns| columns.eq_ignore_ascii_case(column))
+    })
+}
+
+/// Whether the column `definition` has a text type, which MySQL only
+/// indexes by a prefix
+fn is_text(definition: &str) -> bool {
+    definition
+        .split_whitespace()
+        .nth(1)
+        .map(|column_type| column_type.to_ascii_uppercase())
+        .is_some_and(|column_type| column_type.ends_with("TEXT"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USERS: &str = "CREATE TABLE users (\n    id INTEGER PRIMARY KEY,\n    username VARCHAR(255) NOT NULL,\n    email TEXT NOT NULL\n);";
+
+    #[test]
+    fn adds_a_unique_constraint_per_login_column() {
+        for database_type in [DatabaseType::Sqlite, DatabaseType::Postgres] {
+            let statements = unique_login_indexes(vec![USERS.to_string()], &database_type, "users");
+            assert_eq!(
+                statements,
+                ["CREATE TABLE users (\n    id INTEGER PRIMARY KEY,\n    username VARCHAR(255) NOT NULL,\n    email TEXT NOT NULL,\n    CONSTRAINT users_username_key UNIQUE (username),\n    CONSTRAINT users_email_key UNIQUE (email)\n);"]
+            );
+        }
+    }
+
+    #[test]
+    fn indexes_a_prefix_of_mysql_text_columns() {
+        let statements =
+            unique_login_indexes(vec![USERS.to_string()], &DatabaseType::Mysql, "users");
+        assert!(statements[0].contains("CONSTRAINT users_username_key UNIQUE (username)"));
+        assert!(statements[0].contains("CONSTRAINT users_email_key UNIQUE (email(191))"));
+    }
+
+    #[test]
+    fn declares_the_index_inside_the_table() {
+        for database_type in [
+            DatabaseType::Sqlite,
+            DatabaseType::Postgres,
+            DatabaseType::Mysql,
+        ] {
+            let statements = unique_login_indexes(vec![USERS.to_string()], &database_type, "users");
+            assert_eq!(statements.len(), 1);
+            assert!(!statements[0].contains("CREATE UNIQUE INDEX"));
+        }
+    }
+
+    #[test]
+    fn leaves_unique_columns_alone() {
+        let statement = "CREATE TABLE users (username TEXT UNIQUE, email TEXT, UNIQUE (email))";
+        let statements =
+            unique_login_indexes(vec![statement.to_string()], &DatabaseType::Sqlite, "users");
+        assert_eq!(statements, [statement]);
+    }
+
+    #[test]
+    fn runs_again_without_adding_constraints() {
+        let once = unique_login_indexes(vec![USERS.to_string()], &DatabaseType::Mysql, "users");
+        let twice = unique_login_indexes(once.clone(), &DatabaseType::Mysql, "users");
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn names_the_constraint_after_the_prefixed_table() {
+        let statement = "CREATE TABLE app_users (email VARCHAR(255) NOT NULL) ENGINE=InnoDB";
+        let statements = unique_login_indexes(
+            vec![statement.to_string()],
+            &DatabaseType::Mysql,
+            "app_users",
+        );
+        assert_eq!(
+            statements,
+            ["CREATE TABLE app_users (email VARCHAR(255) NOT NULL,\n    CONSTRAINT app_users_email_key UNIQUE (email)) ENGINE=InnoDB"]
+        );
+    }
+
+    #[test]
+    fn skips_other_tables_and_mongo() {
+        let posts = "CREATE TABLE posts (email TEXT)".to_string();
+        assert_eq!(
+            unique_login_indexes(vec![posts.clone()], &DatabaseType::Sqlite, "users"),
+            [posts]
+        );
+        assert_eq!(
+            unique_login_indexes(vec![USERS.to_string()], &DatabaseType::Mongo, "users"),
+            [USERS]
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Formats and checks a freshly generated project with cargo
+//! A typo in a template otherwise only shows once the user builds, running
+//! `cargo fmt` and `cargo check` right away reports it while generating
+
+use std::path::Path;
+use std::process::Command;
+
+use tracing::{info, warn};
+
+use crate::tools::{find_tool, require_tool};
+use crate::CreateProjectError;
+
+/// Runs `cargo fmt` and `cargo check` in `dir`
+/// Failures are only reported, unless `strict`, where a failing or missing
+/// `cargo check` is an error. `cargo fmt` failing is always just reported,
+/// rustfmt is an optional rustup component
+pub fn verify_project(dir: &Path, strict: bool) -> Result<(), CreateProjectError> {
+    if !strict && find_tool("cargo").is_none() {
+        warn!("cargo is not installed, skipping cargo fmt and cargo check");
+        return Ok(());
+    }
+
+    if let Err(why) = cargo(dir, &["fmt"]) {
+        warn!("Failed to format the project: {why}");
+    }
+
+    match cargo(dir, &["check", "--quiet"]) {
+        Ok(()) => {
+            info!("cargo check passed for {}", dir.display());
+            Ok(())
+        }
+        Err(why) if strict => Err(why),
+        Err(why) => {
+            warn!("The generated project doesn't build: {why}");
+            Ok(())
+        }
+    }
+}
+
+/// Runs cargo with `args` in `dir`
+fn cargo(dir: &Path, args: &[&str]) -> Result<(), CreateProjectError> {
+    let program = require_tool("cargo")?;
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|source| CreateProjectError::Process {
+            program: "cargo".to_string(),
+            source,
+        })?;
+
+    if !output.status.success() {
+        return Err(CreateProjectError::CommandFailed {
+            command: format!("cargo {}", args.join(" ")),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(())
+}
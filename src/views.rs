@@ -0,0 +1,121 @@
+//! User overrides for the views RustyRoad generates
+//! A view is looked up by its name, its path below the templates directory
+//! such as `pages/login.html.tera`, or `scaffold/index.html.tera` and
+//! `controller/show.html.tera` for the generators, and `project/<template>`
+//! for the project files of `writers::render`. The project's
+//! `.rustyroad/templates/` is searched first, then `rustyroad/templates/` in
+//! the user config directory, and only then the built-in view is used
+//!
+//! Like custom project templates, an override ending in an extra `.tera`
+//! (`pages/login.html.tera.tera`) is rendered with the generator variables
+//! first, any other override is copied as is
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rustyroad::Project;
+use tera::{Context, Tera};
+use tracing::debug;
+
+use crate::config::global_config_dir;
+use crate::layout::template_name;
+use crate::GenerateError;
+
+/// The override directory inside a project
+pub const OVERRIDE_DIR: &str = ".rustyroad/templates";
+
+/// The directories searched for view overrides, most specific first
+#[derive(Debug, Clone, Default)]
+pub struct Views {
+    dirs: Vec<PathBuf>,
+}
+
+impl Views {
+    /// The overrides of the project rooted at `root`, then the global ones
+    pub fn for_project(root: &Path) -> Self {
+        let mut dirs = vec![root.join(OVERRIDE_DIR)];
+        dirs.extend(global_override_dir());
+        Views { dirs }
+    }
+
+    /// Only the global overrides, for projects that don't exist yet
+    pub fn global() -> Self {
+        Views {
+            dirs: global_override_dir().into_iter().collect(),
+        }
+    }
+
+    /// Renders the view `name`, preferring an override over `builtin`
+    /// `context` holds the variables available to rendered overrides
+    pub fn render_template(
+        &self,
+        name: &str,
+        context: &Context,
+        builtin: impl FnOnce() -> String,
+    ) -> Result<String, GenerateError> {
+        Ok(self.render_override(name, context)?.unwrap_or_else(builtin))
+    }
+
+    /// Writes the view at `path` of a new project, from an override when one
+    /// exists and through the upstream writer `builtin` otherwise
+    pub fn write_view(
+        &self,
+        project: &Project,
+        path: &str,
+        context: &Context,
+        builtin: impl FnOnce() -> io::Result<()>,
+    ) -> io::Result<()> {
+        let name = template_name(project, path);
+        match self
+            .render_override(name, context)
+            .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?
+        {
+            Some(contents) => fs::write(path, contents),
+            None => builtin(),
+        }
+    }
+
+    /// The override of the view `name`, `None` if there is none
+    pub(crate) fn render_override(
+        &self,
+        name: &str,
+        context: &Context,
+    ) -> Result<Option<String>, GenerateError> {
+        for dir in &self.dirs {
+            let rendered = dir.join(format!("{name}.tera"));
+            if rendered.is_file() {
+                debug!("Rendering {name} from {}", rendered.display());
+                let template =
+                    fs::read_to_string(&rendered).map_err(|source| GenerateError::FileRead {
+                        path: rendered.clone(),
+                        source,
+                    })?;
+                return Tera::one_off(&template, context, false)
+                    .map(Some)
+                    .map_err(|source| GenerateError::TemplateRender {
+                        path: rendered,
+                        source,
+                    });
+            }
+
+            let copied = dir.join(name);
+            if copied.is_file() {
+                debug!("Copying {name} from {}", copied.display());
+                return fs::read_to_string(&copied).map(Some).map_err(|source| {
+                    GenerateError::FileRead {
+                        path: copied,
+                        source,
+                    }
+                });
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// `templates` in the global config directory
+fn global_override_dir() -> Option<PathBuf> {
+    global_config_dir().map(|dir| dir.join("templates"))
+}
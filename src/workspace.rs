@@ -0,0 +1,275 @@
+//! Creating a project inside an existing Cargo workspace
+//! Cargo refuses to build a package below a workspace root unless it is a
+//! member, excluded, or a workspace of its own. The project is either added
+//! to the `members` of the root manifest or made its own workspace
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::prompt::confirm;
+use crate::CreateProjectError;
+
+/// What happens to a project created inside a Cargo workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorkspaceMode {
+    /// Ask whether to add the project to the workspace, standalone without
+    /// an answer
+    #[default]
+    Ask,
+    /// Add the project to the `members` of the workspace
+    Join,
+    /// Make the project a workspace of its own
+    Standalone,
+}
+
+/// The Cargo workspace a new project is created in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    /// The Cargo.toml declaring `[workspace]`
+    pub manifest: PathBuf,
+    /// Whether the manifest declares no `[package]` of its own
+    pub is_virtual: bool,
+    /// Whether the project is already a member, e.g. through a glob
+    pub is_member: bool,
+    /// The project directory relative to the workspace root, with `/`
+    pub member: String,
+    /// Whether the project joins the workspace, otherwise it is a
+    /// workspace of its own
+    pub join: bool,
+}
+
+/// The workspace the project at `project_dir` would belong to, `None`
+/// outside of one or when the workspace excludes it
+/// Like Cargo, the closest ancestor declaring `[workspace]` is the root
+pub fn find_workspace(project_dir: &Path) -> Result<Option<Workspace>, CreateProjectError> {
+    let project_dir = if project_dir.is_absolute() {
+        project_dir.to_path_buf()
+    } else {
+        env::current_dir()
+            .map_err(|source| CreateProjectError::Workspace {
+                path: project_dir.to_path_buf(),
+                source,
+            })?
+            .join(project_dir)
+    };
+    let project_dir = normalize(&project_dir);
+
+    for root in project_dir.ancestors().skip(1) {
+        let manifest = root.join("Cargo.toml");
+        if !manifest.is_file() {
+            continue;
+        }
+        let table = read_manifest(&manifest).map_err(|source| CreateProjectError::Workspace {
+            path: manifest.clone(),
+            source,
+        })?;
+        let Some(toml::Value::Table(workspace)) = table.get("workspace") else {
+            continue;
+        };
+
+        let member = project_dir
+            .strip_prefix(root)
+            .expect("ancestors are prefixes")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let listed = |key: &str| match workspace.get(key) {
+            Some(toml::Value::Array(patterns)) => patterns
+                .iter()
+                .filter_map(toml::Value::as_str)
+                .any(|pattern| matches_member(pattern, &member)),
+            _ => false,
+        };
+        if listed("exclude") {
+            return Ok(None);
+        }
+
+        return Ok(Some(Workspace {
+            is_virtual: !table.contains_key("package"),
+            is_member: listed("members"),
+            manifest,
+            member,
+            join: false,
+        }));
+    }
+    Ok(None)
+}
+
+/// The workspace the project at `project_dir` is created in, with whether
+/// it joins it decided by `mode`, asking on the terminal for `Ask`
+/// `None` outside of a workspace
+pub fn resolve_workspace(
+    project_dir: &Path,
+    mode: WorkspaceMode,
+) -> Result<Option<Workspace>, CreateProjectError> {
+    let Some(mut workspace) = find_workspace(project_dir)? else {
+        return Ok(None);
+    };
+
+    workspace.join = workspace.is_member
+        || match mode {
+            WorkspaceMode::Join => true,
+            WorkspaceMode::Standalone => false,
+            // A closed stdin counts as no
+            WorkspaceMode::Ask => confirm(&format!(
+                "{} is inside the workspace of {}, add it to its members?",
+                workspace.member,
+                workspace.manifest.display()
+            ))
+            .unwrap_or(false),
+        };
+    Ok(Some(workspace))
+}
+
+/// Adds the project to the `members` of the root manifest of `workspace`
+/// A virtual workspace without a `resolver` gets resolver 2, the resolver
+/// of the edition 2021 projects are generated with
+/// Fails if the edited manifest is no longer valid TOML
+pub fn add_member(workspace: &Workspace) -> io::Result<()> {
+    let path = &workspace.manifest;
+    let contents = fs::read_to_string(path)?;
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let member = toml::Value::String(workspace.member.clone()).to_string();
+
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let header = lines
+        .iter()
+        .position(|line| line.trim() == "[workspace]")
+        .ok_or_else(|| invalid(format!("{} has no [workspace] table", path.display())))?;
+    let end = lines[header + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |offset| header + 1 + offset);
+    let key_line = |key: &str| {
+        lines[header + 1..end]
+            .iter()
+            .position(|line| line.split('=').next().unwrap_or_default().trim() == key)
+            .map(|offset| header + 1 + offset)
+    };
+    let members = key_line("members");
+    let needs_resolver = workspace.is_virtual && key_line("resolver").is_none();
+
+    if !workspace.is_member {
+        match members {
+            Some(start) => {
+                // The array ends at the first `]`, member paths have none
+                let close = (start..lines.len())
+                    .find(|&index| lines[index].contains(']'))
+                    .ok_or_else(|| {
+                        invalid(format!("{} has an unclosed members", path.display()))
+                    })?;
+                add_to_array(&mut lines, start, close, &member);
+            }
+            None => lines.insert(header + 1, format!("members = [{member}]")),
+        }
+    }
+    if needs_resolver {
+        lines.insert(header + 1, "resolver = \"2\"".to_string());
+    }
+
+    let edited = lines.join("\n") + "\n";
+    toml::from_str::<toml::Table>(&edited).map_err(|why| {
+        invalid(format!(
+            "adding {member} to the members broke {}: {why}",
+            path.display()
+        ))
+    })?;
+    fs::write(path, edited)
+}
+
+/// Adds `member` to the array opened on the line `start` and closed on the
+/// line `close`, keeping the layout of single and multi-line arrays
+fn add_to_array(lines: &mut Vec<String>, start: usize, close: usize, member: &str) {
+    let bracket = lines[close].find(']').expect("the closing line has a `]`");
+    let before = lines[close][..bracket].trim_end();
+
+    // `members = [..]` on one line, or `..]` after the last element
+    if !before.is_empty() && !before.ends_with('[') || start == close {
+        let separator = if before.ends_with('[') || before.ends_with(',') {
+            ""
+        } else {
+            ", "
+        };
+        let line = &mut lines[close];
+        line.insert_str(bracket, &format!("{separator}{member}"));
+        return;
+    }
+
+    // `]` on a line of its own, after one element per line
+    let last = (start + 1..close).rev().find(|&index| {
+        let line = lines[index].trim();
+        !line.is_empty() && !line.starts_with('#')
+    });
+    let indent = match last {
+        Some(last) => {
+            if !lines[last].trim_end().ends_with(',') && !lines[last].trim_end().ends_with('[') {
+                lines[last].push(',');
+            }
+            let line = &lines[last];
+            line[..line.len() - line.trim_start().len()].to_string()
+        }
+        None => "    ".to_string(),
+    };
+    lines.insert(close, format!("{indent}{member},"));
+}
+
+/// Whether the member pattern `pattern`, a path that may contain `*`
+/// wildcards like `crates/*`, matches the relative path `member`
+fn matches_member(pattern: &str, member: &str) -> bool {
+    let pattern: Vec<&str> = pattern
+        .trim_end_matches('/')
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+    let member: Vec<&str> = member.split('/').collect();
+    pattern.len() == member.len()
+        && pattern
+            .iter()
+            .zip(&member)
+            .all(|(pattern, part)| matches_wildcard(pattern, part))
+}
+
+/// Whether `name` matches `pattern`, in which `*` stands for any characters
+fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=name.len())
+                .filter(|&index| name.is_char_boundary(index))
+                .any(|index| matches_wildcard(rest, &name[index..]))
+        }
+    }
+}
+
+/// `path` without `.` and `..` components, which `ancestors` would treat as
+/// directories of their own
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The Cargo.toml at `path`
+fn read_manifest(path: &Path) -> io::Result<toml::Table> {
+    let contents = fs::read_to_string(path)?;
+    toml::from_str(&contents).map_err(|why| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} isn't valid TOML: {why}", path.display()),
+        )
+    })
+}
@@ -0,0 +1,190 @@
+//! Writers for Actix projects generated without a frontend
+//! The upstream writers always mount Tera and the static files, so the API
+//! only variants of main.rs and the index route are generated here
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::database::Database;
+use rustyroad::Project;
+
+use crate::filesystem::FileSystem;
+use crate::layout::{package_name, routes_dir};
+use crate::writers::declare_module;
+use crate::writers::health::HEALTH_MODULE;
+use crate::writers::pool::uses_pool;
+use crate::GenerateError;
+
+/// Writes an Actix main.rs that serves the route modules in `routes`
+/// without templates or static files
+/// SQL projects connect the pool of src/pool.rs and share it as app data
+/// Actix stops on Ctrl-C and SIGTERM by itself, giving running requests
+/// the shutdown timeout to finish, the pool is closed after that
+pub fn write_to_api_main_rs(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+    routes: &[&str],
+) -> io::Result<()> {
+    let configure: String = routes
+        .iter()
+        .map(|module| format!("\n            .configure(routes::{module}::configure)"))
+        .collect();
+
+    let contents = if uses_pool(database_data) {
+        format!(
+            r#"use actix_web::{{web, App, HttpServer}};
+
+mod pool;
+mod routes;
+
+/// How long running requests get to finish once a shutdown signal arrived
+const SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {{
+    dotenvy::dotenv().ok();
+
+    let pool = pool::connect()
+        .await
+        .expect("Failed to connect to the database");
+
+    println!("Listening on http://127.0.0.1:8000");
+
+    let app_pool = pool.clone();
+    HttpServer::new(move || {{
+        App::new()
+            .app_data(web::Data::new(app_pool.clone())){configure}
+    }})
+    .bind(("127.0.0.1", 8000))?
+    .shutdown_timeout(SHUTDOWN_TIMEOUT_SECS)
+    .run()
+    .await?;
+
+    pool.close().await;
+    Ok(())
+}}
+"#
+        )
+    } else {
+        format!(
+            r#"use actix_web::{{App, HttpServer}};
+
+mod routes;
+
+/// How long running requests get to finish once a shutdown signal arrived
+const SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {{
+    dotenvy::dotenv().ok();
+
+    println!("Listening on http://127.0.0.1:8000");
+
+    HttpServer::new(|| {{
+        App::new(){configure}
+    }})
+    .bind(("127.0.0.1", 8000))?
+    .shutdown_timeout(SHUTDOWN_TIMEOUT_SECS)
+    .run()
+    .await
+}}
+"#
+        )
+    };
+
+    fs.write(Path::new(&project.main_rs), contents.as_bytes())
+}
+
+/// Mounts the health routes in the main.rs of the upstream writer
+/// Their readiness check queries the shared pool, so SQL projects get
+/// `mod pool;` declared as well
+pub fn mount_health_routes(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+) -> io::Result<()> {
+    let main_rs = Path::new(&project.main_rs);
+    configure_route_module(fs, main_rs, HEALTH_MODULE)?;
+    if uses_pool(database_data) {
+        declare_module(fs, main_rs, "pool").map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// Registers a route module in an Actix routes/mod.rs like the upstream
+/// writer does, declaring the module and re-exporting its handlers
+/// A module that is declared already is left alone
+pub fn register_route_module(
+    fs: &dyn FileSystem,
+    routes_mod: &Path,
+    module: &str,
+) -> Result<(), GenerateError> {
+    let declaration = format!("pub mod {module};");
+    let existing = fs.read_to_string(routes_mod).unwrap_or_default();
+    if existing.lines().any(|line| line.trim() == declaration) {
+        return Ok(());
+    }
+
+    // The upstream writer leaves off the trailing newline
+    let separator = if existing.is_empty() || existing.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    fs.write(
+        routes_mod,
+        format!("{existing}{separator}{declaration}\npub use {module}::*;\n").as_bytes(),
+    )
+    .map_err(GenerateError::file_write(routes_mod))
+}
+
+/// Registers the route module `module` in the upstream main.rs at
+/// `main_rs` by configuring the app with it
+/// The upstream writer mounts its own routes, so modules it doesn't know
+/// about are added after `App::new()`
+pub fn configure_route_module(fs: &dyn FileSystem, main_rs: &Path, module: &str) -> io::Result<()> {
+    let contents = fs.read_to_string(main_rs)?;
+    let configure = format!(".configure(routes::{module}::configure)");
+    if contents.contains(&configure) {
+        return Ok(());
+    }
+
+    let Some(position) = contents.find("App::new()") else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} doesn't create an `App`", main_rs.display()),
+        ));
+    };
+    let insert_at = position + "App::new()".len();
+    let contents = format!(
+        "{}\n            {configure}{}",
+        &contents[..insert_at],
+        &contents[insert_at..]
+    );
+
+    fs.write(main_rs, contents.as_bytes())
+}
+
+/// Writes an index route answering with JSON instead of a rendered page
+pub fn write_to_api_index_route(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    let contents = format!(
+        r#"use actix_web::{{get, web, HttpResponse, Responder}};
+use serde_json::json;
+
+/// Registers the routes of this module
+pub fn configure(cfg: &mut web::ServiceConfig) {{
+    cfg.service(index);
+}}
+
+#[get("/")]
+async fn index() -> impl Responder {{
+    HttpResponse::Ok().json(json!({{ "name": "{name}", "status": "ok" }}))
+}}
+"#,
+        name = package_name(project)
+    );
+
+    fs.create_dir(&routes_dir(project))?;
+    fs.write(&routes_dir(project).join("index.rs"), contents.as_bytes())
+}
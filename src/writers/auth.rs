@@ -0,0 +1,257 @@
+//! Writers for the authentication scheme of a generated project
+//! Sessions come from the upstream login page, JSON Web Tokens are issued
+//! by a route module generated here. SQL projects register users through
+//! it too, storing an Argon2 hash of their password
+
+use std::io;
+use std::path::PathBuf;
+
+use rustyroad::database::DatabaseType;
+use rustyroad::Project;
+
+use crate::database::placeholder;
+use crate::filesystem::FileSystem;
+use crate::framework::Framework;
+use crate::layout::routes_dir;
+use crate::writers::password::PASSWORD_FUNCTIONS;
+
+/// The route module holding the token handlers
+pub const AUTH_MODULE: &str = "auth";
+
+/// The dependency signing and verifying the tokens
+pub const JSONWEBTOKEN_DEPENDENCY: &str = "jsonwebtoken = \"9\"";
+
+/// The path of the token route module
+pub fn auth_route_path(project: &Project) -> PathBuf {
+    routes_dir(project).join(format!("{AUTH_MODULE}.rs"))
+}
+
+/// The claims, credentials and token helpers shared by both frameworks
+const TOKEN_HELPERS: &str = r#"
+/// How long an issued token stays valid
+const TOKEN_LIFETIME_SECS: u64 = 60 * 60;
+
+/// The claims signed into every token
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// The user the token was issued to
+    pub sub: String,
+    /// Expiry as seconds since the Unix epoch
+    pub exp: u64,
+}
+
+/// The body of a token or registration request
+#[derive(Deserialize)]
+pub struct Credentials {
+    username: String,
+    password: String,
+}
+
+/// The signing key, read from `JWT_SECRET`
+fn secret() -> Vec<u8> {
+    std::env::var("JWT_SECRET")
+        .expect("JWT_SECRET must be set")
+        .into_bytes()
+}
+
+/// Signs a token for `subject`
+pub fn issue_token(subject: &str) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let claims = Claims {
+        sub: subject.to_string(),
+        exp: now + TOKEN_LIFETIME_SECS,
+    };
+
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(&secret()))
+}
+
+/// Verifies the signature and expiry of `token` and returns its claims
+pub fn verify_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(&secret()),
+        &Validation::default(),
+    )
+    .map(|data| data.claims)
+}
+"#;
+
+/// Checks the credentials of a project without a SQL users table
+const CREDENTIALS_STUB: &str = r#"
+/// Checks the credentials against the users
+/// Rejects everyone until it is implemented for your user model, store
+/// passwords with `hash_password` and check them with `verify_password`
+async fn verify_credentials(_username: &str, _password: &str) -> bool {
+    false
+}
+"#;
+
+/// The Actix handler creating users at `POST /auth/register`
+const ACTIX_REGISTER: &str = r#"
+/// Creates a user, storing only the hash of the password
+#[post("/auth/register")]
+async fn register(credentials: web::Json<Credentials>) -> impl Responder {
+    match create_user(&credentials.username, &credentials.password).await {
+        Ok(()) => HttpResponse::Created().json(json!({ "username": credentials.username })),
+        Err(why) => HttpResponse::BadRequest().json(json!({ "error": why })),
+    }
+}
+"#;
+
+/// The Axum handler creating users at `POST /auth/register`
+const AXUM_REGISTER: &str = r#"
+/// Creates a user, storing only the hash of the password
+async fn register(Json(credentials): Json<Credentials>) -> (StatusCode, Json<Value>) {
+    match create_user(&credentials.username, &credentials.password).await {
+        Ok(()) => (
+            StatusCode::CREATED,
+            Json(json!({ "username": credentials.username })),
+        ),
+        Err(why) => (StatusCode::BAD_REQUEST, Json(json!({ "error": why }))),
+    }
+}
+"#;
+
+/// Checks credentials against and adds users to the `users` table of
+/// `database_type`, through the pool of src/pool.rs
+fn user_queries(database_type: &DatabaseType, users: &str) -> String {
+    let first = placeholder(database_type, 1);
+    let second = placeholder(database_type, 2);
+    format!(
+        r#"
+/// Checks the credentials against the password hash in the users table
+async fn verify_credentials(username: &str, password: &str) -> bool {{
+    let password_hash: Option<String> =
+        sqlx::query_scalar("SELECT password_hash FROM {users} WHERE username = {first}")
+            .bind(username)
+            .fetch_optional(pool::shared())
+            .await
+            .ok()
+            .flatten();
+
+    password_hash.is_some_and(|password_hash| verify_password(password, &password_hash))
+}}
+
+/// Stores a new user with the hash of `password`
+async fn create_user(username: &str, password: &str) -> Result<(), String> {{
+    let password_hash = hash_password(password).map_err(|why| why.to_string())?;
+    sqlx::query("INSERT INTO {users} (username, password_hash) VALUES ({first}, {second})")
+        .bind(username)
+        .bind(password_hash)
+        .execute(pool::shared())
+        .await
+        .map(|_| ())
+        .map_err(|why| why.to_string())
+}}
+
+{PASSWORD_FUNCTIONS}"#
+    )
+}
+
+/// Writes the route module issuing tokens at `POST /auth/token`
+/// `users` is the users table as the SQL refers to it, SQL projects check
+/// credentials against it and register users at `POST /auth/register`
+pub fn write_to_auth_routes(
+    fs: &dyn FileSystem,
+    project: &Project,
+    framework: Framework,
+    database_type: &DatabaseType,
+    users: &str,
+) -> io::Result<()> {
+    let sql = !matches!(database_type, DatabaseType::Mongo);
+    let pool = if sql { "\nuse crate::pool;\n" } else { "" };
+    let handlers = match framework {
+        Framework::Actix => {
+            let services = if sql {
+                "cfg.service(token).service(register);"
+            } else {
+                "cfg.service(token);"
+            };
+            format!(
+                r#"use std::time::{{SystemTime, UNIX_EPOCH}};
+
+use actix_web::{{post, web, HttpResponse, Responder}};
+use jsonwebtoken::{{decode, encode, DecodingKey, EncodingKey, Header, Validation}};
+use serde::{{Deserialize, Serialize}};
+use serde_json::json;
+{pool}
+/// Registers the routes of this module
+pub fn configure(cfg: &mut web::ServiceConfig) {{
+    {services}
+}}
+
+/// Exchanges valid credentials for a signed token
+#[post("/auth/token")]
+async fn token(credentials: web::Json<Credentials>) -> impl Responder {{
+    if !verify_credentials(&credentials.username, &credentials.password).await {{
+        return HttpResponse::Unauthorized().json(json!({{ "error": "invalid credentials" }}));
+    }}
+
+    match issue_token(&credentials.username) {{
+        Ok(token) => HttpResponse::Ok().json(json!({{ "token": token }})),
+        Err(why) => HttpResponse::InternalServerError().body(why.to_string()),
+    }}
+}}
+{register}"#,
+                register = if sql { ACTIX_REGISTER } else { "" },
+            )
+        }
+        Framework::Axum => {
+            let register = if sql {
+                "\n        .route(\"/auth/register\", post(register))"
+            } else {
+                ""
+            };
+            format!(
+                r#"use std::time::{{SystemTime, UNIX_EPOCH}};
+
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{{Json, Router}};
+use jsonwebtoken::{{decode, encode, DecodingKey, EncodingKey, Header, Validation}};
+use serde::{{Deserialize, Serialize}};
+use serde_json::{{json, Value}};
+{pool}
+/// The routes of this module
+pub fn router() -> Router {{
+    Router::new()
+        .route("/auth/token", post(token)){register}
+}}
+
+/// Exchanges valid credentials for a signed token
+async fn token(Json(credentials): Json<Credentials>) -> (StatusCode, Json<Value>) {{
+    if !verify_credentials(&credentials.username, &credentials.password).await {{
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({{ "error": "invalid credentials" }})),
+        );
+    }}
+
+    match issue_token(&credentials.username) {{
+        Ok(token) => (StatusCode::OK, Json(json!({{ "token": token }}))),
+        Err(why) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({{ "error": why.to_string() }})),
+        ),
+    }}
+}}
+{register_handler}"#,
+                register_handler = if sql { AXUM_REGISTER } else { "" },
+            )
+        }
+    };
+    let credentials = if sql {
+        user_queries(database_type, users)
+    } else {
+        format!("{CREDENTIALS_STUB}\n{PASSWORD_FUNCTIONS}")
+    };
+
+    fs.create_dir(&routes_dir(project))?;
+    fs.write(
+        &auth_route_path(project),
+        format!("{handlers}{TOKEN_HELPERS}{credentials}").as_bytes(),
+    )
+}
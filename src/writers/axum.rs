@@ -0,0 +1,513 @@
+//! Writers for Axum projects
+//! The upstream writers only know Actix, so the files that differ between the
+//! frameworks are generated here when `--framework axum` is selected
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::database::{Database, DatabaseType};
+use rustyroad::Project;
+
+use crate::filesystem::FileSystem;
+use crate::layout::{package_name, relative_to_project, routes_dir, template_name};
+use crate::writers::pool::uses_pool;
+use crate::GenerateError;
+
+/// Marker in routes/mod.rs before which generated routers are merged
+pub const ROUTES_MARKER: &str = "// rustyroad:routes";
+
+/// Waits for Ctrl-C or, on Unix, for the SIGTERM containers are stopped with
+/// Windows has no SIGTERM, there the signal future never resolves
+const SHUTDOWN_SIGNAL: &str = r#"
+/// Resolves once the server should stop, on Ctrl-C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl-C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+    println!("Shutting down");
+}
+"#;
+
+/// Writes an Axum flavored main.rs
+/// SQL projects connect the pool of src/pool.rs and share it as an extension
+/// On Ctrl-C or SIGTERM the server stops accepting connections, finishes the
+/// running requests and closes the pool before exiting
+pub fn write_to_main_rs(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+) -> io::Result<()> {
+    let templates = relative_to_project(project, &project.templates);
+    let static_dir = relative_to_project(project, &project.static_dir);
+    let (pool_mod, pool_setup, pool_layer, pool_close) = pool_fragments(database_data);
+
+    let contents = format!(
+        r#"use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::Extension;
+use tera::Tera;
+use tower_http::services::ServeDir;
+
+{pool_mod}mod routes;
+
+#[tokio::main]
+async fn main() {{
+    dotenvy::dotenv().ok();
+
+{pool_setup}    let tera = Tera::new("{templates}/**/*").expect("Failed to load templates");
+
+    let app = routes::router()
+        .nest_service("/static", ServeDir::new("{static_dir}"))
+        .layer(Extension(Arc::new(tera))){pool_layer};
+
+    let address = SocketAddr::from(([127, 0, 0, 1], 8000));
+    println!("Listening on http://{{address}}");
+
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .expect("Server error");
+{pool_close}}}
+{SHUTDOWN_SIGNAL}"#
+    );
+
+    fs.write(Path::new(&project.main_rs), contents.as_bytes())
+}
+
+/// The `mod pool;` declaration, the pool setup, the layer sharing it and
+/// the closing of its connections once the server stopped
+/// Empty for projects without a sqlx pool
+fn pool_fragments(
+    database_data: &Database,
+) -> (&'static str, &'static str, &'static str, &'static str) {
+    if uses_pool(database_data) {
+        (
+            "mod pool;\n",
+            "    let pool = pool::connect()\n        .await\n        .expect(\"Failed to connect to the database\");\n\n",
+            "\n        .layer(Extension(pool.clone()))",
+            "\n    pool.close().await;\n",
+        )
+    } else {
+        ("", "", "", "")
+    }
+}
+
+/// Writes routes/mod.rs with the route modules in `routes` and the shared
+/// render helper
+pub fn write_to_routes_mod(
+    fs: &dyn FileSystem,
+    project: &Project,
+    routes: &[&str],
+) -> io::Result<()> {
+    let (modules, merges) = route_declarations(routes);
+    let contents = format!(
+        r#"use axum::http::StatusCode;
+use axum::response::{{Html, IntoResponse, Response}};
+use axum::Router;
+use tera::{{Context, Tera}};
+
+{modules}
+/// Every route of the application
+pub fn router() -> Router {{
+    Router::new()
+{merges}        {ROUTES_MARKER}
+}}
+
+/// Renders a template, turning template errors into a 500 response
+pub fn render(tera: &Tera, template: &str, context: &Context) -> Response {{
+    match tera.render(template, context) {{
+        Ok(body) => Html(body).into_response(),
+        Err(why) => (StatusCode::INTERNAL_SERVER_ERROR, why.to_string()).into_response(),
+    }}
+}}
+"#
+    );
+
+    fs.create_dir(&routes_dir(project))?;
+    fs.write(Path::new(&project.routes_module), contents.as_bytes())
+}
+
+/// Writes the route serving the index page
+pub fn write_to_index_route(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    let index_template = template_name(project, &project.index_html);
+
+    let contents = format!(
+        r#"use std::sync::Arc;
+
+use axum::response::Response;
+use axum::routing::get;
+use axum::{{Extension, Router}};
+use tera::{{Context, Tera}};
+
+use super::render;
+
+/// The routes of the index page
+pub fn router() -> Router {{
+    Router::new().route("/", get(index))
+}}
+
+async fn index(Extension(tera): Extension<Arc<Tera>>) -> Response {{
+    let context = Context::new();
+    render(&tera, "{index_template}", &context)
+}}
+"#
+    );
+
+    fs.write(&routes_dir(project).join("index.rs"), contents.as_bytes())
+}
+
+/// Writes an Axum main.rs for a project without a frontend
+/// No templates are loaded and no static files served, shutting down works
+/// like in `write_to_main_rs`
+pub fn write_to_api_main_rs(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+) -> io::Result<()> {
+    let (pool_mod, pool_setup, pool_layer, pool_close) = pool_fragments(database_data);
+    let extension = if uses_pool(database_data) {
+        "\nuse axum::Extension;\n"
+    } else {
+        ""
+    };
+
+    let contents = format!(
+        r#"use std::net::SocketAddr;
+{extension}
+{pool_mod}mod routes;
+
+#[tokio::main]
+async fn main() {{
+    dotenvy::dotenv().ok();
+
+{pool_setup}    let app = routes::router(){pool_layer};
+
+    let address = SocketAddr::from(([127, 0, 0, 1], 8000));
+    println!("Listening on http://{{address}}");
+
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .expect("Server error");
+{pool_close}}}
+{SHUTDOWN_SIGNAL}"#
+    );
+
+    fs.write(Path::new(&project.main_rs), contents.as_bytes())
+}
+
+/// Writes routes/mod.rs for a project without a frontend
+/// Same as `write_to_routes_mod` without the template render helper
+pub fn write_to_api_routes_mod(
+    fs: &dyn FileSystem,
+    project: &Project,
+    routes: &[&str],
+) -> io::Result<()> {
+    let (modules, merges) = route_declarations(routes);
+    let contents = format!(
+        r#"use axum::Router;
+
+{modules}
+/// Every route of the application
+pub fn router() -> Router {{
+    Router::new()
+{merges}        {ROUTES_MARKER}
+}}
+"#
+    );
+
+    fs.create_dir(&routes_dir(project))?;
+    fs.write(Path::new(&project.routes_module), contents.as_bytes())
+}
+
+/// The `pub mod` lines and the `.merge` calls of the route modules in
+/// `routes`, one per line
+fn route_declarations(routes: &[&str]) -> (String, String) {
+    let modules = routes
+        .iter()
+        .map(|route| format!("pub mod {route};\n"))
+        .collect();
+    let merges = routes
+        .iter()
+        .map(|route| format!("        .merge({route}::router())\n"))
+        .collect();
+    (modules, merges)
+}
+
+/// Writes an index route answering with JSON instead of a rendered page
+pub fn write_to_api_index_route(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    let contents = format!(
+        r#"use axum::routing::get;
+use axum::{{Json, Router}};
+use serde_json::{{json, Value}};
+
+/// The routes of the index page
+pub fn router() -> Router {{
+    Router::new().route("/", get(index))
+}}
+
+async fn index() -> Json<Value> {{
+    Json(json!({{ "name": "{name}", "status": "ok" }}))
+}}
+"#,
+        name = package_name(project)
+    );
+
+    fs.create_dir(&routes_dir(project))?;
+    fs.write(&routes_dir(project).join("index.rs"), contents.as_bytes())
+}
+
+/// Writes the Cargo.toml of an Axum project
+/// Only the sqlx driver of the chosen database is enabled
+pub fn write_to_cargo_toml(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+) -> io::Result<()> {
+    let database_dependency = match database_data.database_type {
+        DatabaseType::Sqlite => sqlx_dependency("sqlite"),
+        DatabaseType::Postgres => sqlx_dependency("postgres"),
+        DatabaseType::Mysql => sqlx_dependency("mysql"),
+        DatabaseType::Mongo => "mongodb = \"2\"\nfutures = \"0.3\"\n".to_string(),
+    };
+
+    let contents = format!(
+        r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+axum = "0.6"
+tokio = {{ version = "1", features = ["full"] }}
+tower-http = {{ version = "0.4", features = ["fs"] }}
+tera = "1"
+serde = {{ version = "1", features = ["derive"] }}
+serde_json = "1"
+chrono = {{ version = "0.4", features = ["serde"] }}
+{database_dependency}"#,
+        name = project.name
+    );
+
+    fs.write(Path::new(&project.cargo_toml), contents.as_bytes())
+}
+
+fn sqlx_dependency(driver: &str) -> String {
+    format!(
+        "sqlx = {{ version = \"0.7\", features = [\"runtime-tokio\", \"{driver}\", \"chrono\"] }}\n"
+    )
+}
+
+/// Registers a route module in an Axum routes/mod.rs
+/// Declares the module and merges its router before the routes marker
+pub fn register_route_module(
+    fs: &dyn FileSystem,
+    routes_mod: &Path,
+    module: &str,
+) -> Result<(), GenerateError> {
+    add_route_module(
+        fs,
+        routes_mod,
+        module,
+        &format!(".merge({module}::router())"),
+    )
+}
+
+/// Registers a route module in an Axum routes/mod.rs with its routes
+/// below `prefix`, e.g. `/api`
+/// Declares the module and nests its router before the routes marker
+pub fn nest_route_module(
+    fs: &dyn FileSystem,
+    routes_mod: &Path,
+    module: &str,
+    prefix: &str,
+) -> Result<(), GenerateError> {
+    add_route_module(
+        fs,
+        routes_mod,
+        module,
+        &format!(".nest(\"{prefix}\", {module}::router())"),
+    )
+}
+
+/// Declares `module` in routes/mod.rs and adds `call` to the router chain
+fn add_route_module(
+    fs: &dyn FileSystem,
+    routes_mod: &Path,
+    module: &str,
+    call: &str,
+) -> Result<(), GenerateError> {
+    let contents = fs
+        .read_to_string(routes_mod)
+        .map_err(|source| GenerateError::FileRead {
+            path: routes_mod.to_path_buf(),
+            source,
+        })?;
+
+    let declaration = format!("pub mod {module};");
+    if contents.lines().any(|line| line.trim() == declaration) {
+        return Ok(());
+    }
+
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+
+    let marker = lines
+        .iter()
+        .position(|line| line.trim() == ROUTES_MARKER)
+        .ok_or_else(|| GenerateError::MissingMarker {
+            path: routes_mod.to_path_buf(),
+            marker: ROUTES_MARKER,
+        })?;
+    lines.insert(marker, format!("        {call}"));
+
+    // Keep the module declarations together
+    let after_last_module = lines
+        .iter()
+        .rposition(|line| line.starts_with("pub mod "))
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    lines.insert(after_last_module, declaration);
+
+    fs.write(routes_mod, (lines.join("\n") + "\n").as_bytes())
+        .map_err(GenerateError::file_write(routes_mod))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+    use crate::layout::project_at;
+    use crate::writers::actix;
+
+    fn sqlite() -> Database {
+        Database {
+            name: "blog.db".to_string(),
+            username: String::new(),
+            password: String::new(),
+            host: String::new(),
+            port: String::new(),
+            database_type: DatabaseType::Sqlite,
+        }
+    }
+
+    /// A filesystem with the directories of `project`'s main.rs and routes
+    fn project_fs(project: &Project) -> MemoryFileSystem {
+        let fs = MemoryFileSystem::new();
+        fs.create_dir(&routes_dir(project)).unwrap();
+        fs
+    }
+
+    /// Whether the braces and parentheses of `code` pair up
+    fn is_balanced(code: &str) -> bool {
+        let mut open = Vec::new();
+        for c in code.chars() {
+            match c {
+                '{' | '(' => open.push(c),
+                '}' if open.pop() != Some('{') => return false,
+                ')' if open.pop() != Some('(') => return false,
+                _ => {}
+            }
+        }
+        open.is_empty()
+    }
+
+    #[test]
+    fn main_rs_runs_on_the_chosen_framework() {
+        let project = project_at(Path::new("blog"));
+
+        let axum_fs = project_fs(&project);
+        write_to_api_main_rs(&axum_fs, &project, &sqlite()).unwrap();
+        let axum_main = axum_fs.contents(&project.main_rs).unwrap();
+
+        let actix_fs = project_fs(&project);
+        actix::write_to_api_main_rs(&actix_fs, &project, &sqlite(), &["index"]).unwrap();
+        let actix_main = actix_fs.contents(&project.main_rs).unwrap();
+
+        assert!(axum_main.contains("#[tokio::main]"));
+        assert!(axum_main.contains("axum::Server::bind(&address)"));
+        assert!(!axum_main.contains("actix_web"));
+        assert!(actix_main.contains("#[actix_web::main]"));
+        assert!(actix_main.contains("HttpServer::new"));
+        assert!(!actix_main.contains("axum"));
+        for main in [&axum_main, &actix_main] {
+            assert!(is_balanced(main), "{main}");
+            assert!(main.contains("async fn main()"));
+            assert!(main.contains("mod pool;\nmod routes;"));
+        }
+    }
+
+    #[test]
+    fn main_rs_serves_templates_and_static_files() {
+        let project = project_at(Path::new("blog"));
+        let fs = project_fs(&project);
+        write_to_main_rs(&fs, &project, &sqlite()).unwrap();
+        let main = fs.contents(&project.main_rs).unwrap();
+
+        assert!(is_balanced(&main), "{main}");
+        assert!(main.contains("let tera = Tera::new("));
+        assert!(main.contains(".nest_service(\"/static\", ServeDir::new("));
+        assert!(main.contains(".layer(Extension(pool.clone()))"));
+    }
+
+    #[test]
+    fn mongo_main_rs_has_no_pool() {
+        let project = project_at(Path::new("blog"));
+        let mongo = Database {
+            database_type: DatabaseType::Mongo,
+            ..sqlite()
+        };
+        let fs = project_fs(&project);
+        write_to_api_main_rs(&fs, &project, &mongo).unwrap();
+        let main = fs.contents(&project.main_rs).unwrap();
+
+        assert!(is_balanced(&main), "{main}");
+        assert!(!main.contains("pool"));
+    }
+
+    #[test]
+    fn cargo_toml_depends_on_axum() {
+        let project = project_at(Path::new("blog"));
+        let fs = MemoryFileSystem::new();
+        fs.create_dir(Path::new(&project.name)).unwrap();
+        write_to_cargo_toml(&fs, &project, &sqlite()).unwrap();
+        let cargo_toml = fs.contents(&project.cargo_toml).unwrap();
+
+        assert!(cargo_toml.contains("axum = \"0.6\""));
+        assert!(cargo_toml.contains("features = [\"runtime-tokio\", \"sqlite\", \"chrono\"]"));
+        assert!(!cargo_toml.contains("actix"));
+    }
+
+    #[test]
+    fn routes_are_merged_before_the_marker() {
+        let project = project_at(Path::new("blog"));
+        let fs = project_fs(&project);
+        write_to_api_routes_mod(&fs, &project, &["index"]).unwrap();
+        register_route_module(&fs, Path::new(&project.routes_module), "posts").unwrap();
+        let routes = fs.contents(&project.routes_module).unwrap();
+
+        assert!(routes.contains("pub mod index;\npub mod posts;\n"));
+        assert!(routes.contains(&format!(
+            "        .merge(index::router())\n        .merge(posts::router())\n        {ROUTES_MARKER}"
+        )));
+    }
+}
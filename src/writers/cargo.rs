@@ -0,0 +1,557 @@
+//! Edits the Cargo.toml of a generated project
+//! The upstream writer owns the file, so dependencies the options need are
+//! added to what it wrote
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::database::{Database, DatabaseType};
+use tracing::{debug, warn};
+
+use crate::dependency::Dependency;
+use crate::filesystem::FileSystem;
+use crate::options::NewProjectOptions;
+
+/// The sqlx features of database drivers, `any` choosing one at runtime
+/// Generated code connects through the driver of its database only, see
+/// `use_sqlx_driver`
+const SQLX_DRIVERS: [&str; 4] = ["sqlite", "postgres", "mysql", "any"];
+
+/// Adds `dependency`, a line such as `jsonwebtoken = "9"`, to the
+/// `[dependencies]` table of the Cargo.toml at `path`
+/// A dependency that is already declared is left alone
+pub fn add_dependency(fs: &dyn FileSystem, path: &Path, dependency: &str) -> io::Result<()> {
+    add_to_table(fs, path, "[dependencies]", dependency)
+}
+
+/// Declares `dependency` in the `[dependencies]` table of the Cargo.toml at
+/// `path`, for `--add-dep`
+/// A dependency the generator declared already keeps its other settings and
+/// gets the version of `dependency` and the features of both
+/// Fails if the edited manifest is no longer valid TOML
+pub fn merge_dependency(
+    fs: &dyn FileSystem,
+    path: &Path,
+    dependency: &Dependency,
+) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let header = lines
+        .iter()
+        .position(|line| line.trim() == "[dependencies]");
+    let existing = header.and_then(|header| {
+        lines[header + 1..]
+            .iter()
+            .take_while(|line| !line.trim_start().starts_with('['))
+            .position(|line| dependency_name(line) == dependency.name)
+            .map(|offset| header + 1 + offset)
+    });
+    let Some(existing) = existing else {
+        return add_dependency(fs, path, &dependency.to_line());
+    };
+
+    let declared: toml::Table = toml::from_str(&lines[existing]).map_err(|why| {
+        invalid(format!(
+            "can't merge {dependency} into `{}`: {why}",
+            lines[existing]
+        ))
+    })?;
+    lines[existing] = match declared.get(&dependency.name) {
+        Some(toml::Value::Table(settings)) => {
+            let mut settings = settings.clone();
+            settings.insert(
+                "version".to_string(),
+                toml::Value::String(dependency.version.clone()),
+            );
+
+            let mut features: Vec<toml::Value> = match settings.get("features") {
+                Some(toml::Value::Array(features)) => features.clone(),
+                _ => Vec::new(),
+            };
+            for feature in &dependency.features {
+                let feature = toml::Value::String(feature.clone());
+                if !features.contains(&feature) {
+                    features.push(feature);
+                }
+            }
+            if !features.is_empty() {
+                settings.insert("features".to_string(), toml::Value::Array(features));
+            }
+
+            inline_dependency(&dependency.name, &settings)
+        }
+        _ => dependency.to_line(),
+    };
+
+    let merged = lines.join("\n") + "\n";
+    toml::from_str::<toml::Table>(&merged)
+        .map_err(|why| invalid(format!("merging {dependency} broke the manifest: {why}")))?;
+    fs.write(path, merged.as_bytes())
+}
+
+/// Adds `dependency` to the `[dev-dependencies]` table of the Cargo.toml at
+/// `path`, which is created when the upstream writer didn't declare one
+pub fn add_dev_dependency(fs: &dyn FileSystem, path: &Path, dependency: &str) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+    if !contents
+        .lines()
+        .any(|line| line.trim() == "[dev-dependencies]")
+    {
+        let separator = if contents.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        fs.write(
+            path,
+            format!("{contents}{separator}[dev-dependencies]\n").as_bytes(),
+        )?;
+    }
+    add_to_table(fs, path, "[dev-dependencies]", dependency)
+}
+
+/// Adds `dependency` to the dependency table starting at the line `table`
+fn add_to_table(fs: &dyn FileSystem, path: &Path, table: &str, dependency: &str) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+    let name = dependency_name(dependency);
+
+    let mut lines: Vec<&str> = contents.lines().collect();
+    let Some(header) = lines.iter().position(|line| line.trim() == table) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} has no {table} table", path.display()),
+        ));
+    };
+
+    // The table ends at the next header or the end of the file
+    let end = lines[header + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map(|offset| header + 1 + offset)
+        .unwrap_or(lines.len());
+    if lines[header + 1..end]
+        .iter()
+        .any(|line| dependency_name(line) == name)
+    {
+        return Ok(());
+    }
+
+    // Keep a blank line before the next table
+    let mut insert_at = end;
+    while insert_at > header + 1 && lines[insert_at - 1].trim().is_empty() {
+        insert_at -= 1;
+    }
+    lines.insert(insert_at, dependency);
+
+    fs.write(path, (lines.join("\n") + "\n").as_bytes())
+}
+
+/// Sets the `authors` of the `[package]` table of the Cargo.toml at `path`,
+/// replacing any the upstream writer declared
+pub fn set_authors(fs: &dyn FileSystem, path: &Path, author: &str) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let Some(header) = lines.iter().position(|line| line.trim() == "[package]") else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} has no [package] table", path.display()),
+        ));
+    };
+    let table_end = |lines: &[String]| {
+        lines[header + 1..]
+            .iter()
+            .position(|line| line.trim_start().starts_with('[') || line.trim().is_empty())
+            .map(|offset| header + 1 + offset)
+            .unwrap_or(lines.len())
+    };
+
+    if let Some(offset) = lines[header + 1..table_end(&lines)]
+        .iter()
+        .position(|line| dependency_name(line) == "authors")
+    {
+        lines.remove(header + 1 + offset);
+    }
+    let authors = format!("authors = [{}]", toml::Value::String(author.to_string()));
+    lines.insert(table_end(&lines), authors);
+
+    fs.write(path, (lines.join("\n") + "\n").as_bytes())
+}
+
+/// Removes the tables only a workspace root may declare, `[workspace]`,
+/// `[profile.*]`, `[patch.*]` and `[replace]`, from the Cargo.toml at
+/// `path` of a workspace member
+/// Cargo ignores profiles of members and rejects a nested workspace
+pub fn remove_root_only_tables(fs: &dyn FileSystem, path: &Path) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+    let is_root_only = |header: &str| {
+        let name = header.trim().trim_start_matches('[').trim_end_matches(']');
+        let table = name.split('.').next().unwrap_or_default().trim();
+        matches!(table, "workspace" | "profile" | "patch" | "replace")
+    };
+
+    let mut kept = Vec::new();
+    let mut skipping = false;
+    for line in contents.lines() {
+        if line.trim_start().starts_with('[') {
+            skipping = is_root_only(line);
+            if skipping {
+                warn!(
+                    "Removed {} from {}, the workspace root declares it",
+                    line.trim(),
+                    path.display()
+                );
+            }
+        }
+        if !skipping {
+            kept.push(line);
+        }
+    }
+
+    fs.write(path, (kept.join("\n") + "\n").as_bytes())
+}
+
+/// Declares an empty `[workspace]` in the Cargo.toml at `path`, so a project
+/// below a workspace it isn't a member of builds on its own
+pub fn declare_own_workspace(fs: &dyn FileSystem, path: &Path) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+    if contents.lines().any(|line| line.trim() == "[workspace]") {
+        return Ok(());
+    }
+    let separator = if contents.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    fs.write(
+        path,
+        format!("{contents}{separator}[workspace]\n").as_bytes(),
+    )
+}
+
+/// `--minimal-cargo`: removes the dependencies the Cargo.toml at `path`
+/// was written with that none of the enabled features use
+/// Tera and the static file crates go without a frontend, sqlx without a
+/// SQL database
+/// Features add their own dependencies after this, so only the ones the
+/// framework writers declare are looked at
+pub fn trim_dependencies(
+    fs: &dyn FileSystem,
+    path: &Path,
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let sql = !matches!(database_data.database_type, DatabaseType::Mongo);
+    let unused = |name: &str| match name {
+        "tera" | "actix-files" | "tower-http" => options.no_frontend,
+        "sqlx" => !sql,
+        "mongodb" => sql,
+        _ => false,
+    };
+
+    let lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let Some(header) = lines
+        .iter()
+        .position(|line| line.trim() == "[dependencies]")
+    else {
+        return Ok(());
+    };
+    let end = lines[header + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |offset| header + 1 + offset);
+
+    let mut trimmed = Vec::with_capacity(lines.len());
+    for (index, line) in lines.into_iter().enumerate() {
+        let name = dependency_name(&line);
+        if index <= header || index >= end || name.is_empty() || name.starts_with('#') {
+            trimmed.push(line);
+        } else if unused(name) {
+            debug!("Removing the unused dependency {name}");
+        } else {
+            trimmed.push(line);
+        }
+    }
+
+    let trimmed = trimmed.join("\n") + "\n";
+    toml::from_str::<toml::Table>(&trimmed).map_err(|why| {
+        invalid(format!(
+            "trimming the dependencies broke the manifest: {why}"
+        ))
+    })?;
+    fs.write(path, trimmed.as_bytes())
+}
+
+/// Enables exactly one runtime and the driver of `database_type` on the
+/// sqlx of the Cargo.toml at `path`, declared inline in `[dependencies]`
+/// or as a `[dependencies.sqlx]` table
+/// The drivers of other databases only add to the build time and binary
+/// size. A manifest without sqlx, as of a MongoDB project, is left alone
+pub fn use_sqlx_driver(
+    fs: &dyn FileSystem,
+    path: &Path,
+    database_type: &DatabaseType,
+) -> io::Result<()> {
+    let driver = match database_type {
+        DatabaseType::Sqlite => "sqlite",
+        DatabaseType::Postgres => "postgres",
+        DatabaseType::Mysql => "mysql",
+        DatabaseType::Mongo => return Ok(()),
+    };
+    let contents = fs.read_to_string(path)?;
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    // The line declaring `key` in the table starting at the line `header`
+    let find = |header: &str, key: &str| {
+        let start = lines.iter().position(|line| line.trim() == header)? + 1;
+        let end = lines[start..]
+            .iter()
+            .position(|line| line.trim_start().starts_with('['))
+            .map_or(lines.len(), |offset| start + offset);
+        (start..end).find(|&index| dependency_name(&lines[index]) == key)
+    };
+    // `sqlx = { .. }` in [dependencies], or `features = [..]` in its table
+    let (line, key) = match (
+        find("[dependencies]", "sqlx"),
+        find("[dependencies.sqlx]", "features"),
+    ) {
+        (Some(line), _) => (line, "sqlx"),
+        (None, Some(line)) => (line, "features"),
+        (None, None) => return Ok(()),
+    };
+
+    let declared: toml::Table = toml::from_str(&lines[line]).map_err(|why| {
+        invalid(format!(
+            "can't read the sqlx features of `{}`: {why}",
+            lines[line]
+        ))
+    })?;
+    lines[line] = match declared.get(key) {
+        Some(toml::Value::Table(settings)) => {
+            let features: &[toml::Value] = match settings.get("features") {
+                Some(toml::Value::Array(features)) => features,
+                _ => &[],
+            };
+            let mut settings = settings.clone();
+            settings.insert(
+                "features".to_string(),
+                toml::Value::Array(sqlx_features(features, driver)),
+            );
+            inline_dependency("sqlx", &settings)
+        }
+        Some(toml::Value::Array(features)) => format!(
+            "features = {}",
+            toml::Value::Array(sqlx_features(features, driver))
+        ),
+        _ => return Ok(()),
+    };
+
+    let edited = lines.join("\n") + "\n";
+    toml::from_str::<toml::Table>(&edited)
+        .map_err(|why| invalid(format!("setting the sqlx driver broke the manifest: {why}")))?;
+    fs.write(path, edited.as_bytes())
+}
+
+/// `features` of sqlx with `driver` as the only database driver and a
+/// single runtime, the Tokio one the generated apps run on if there was
+/// none
+fn sqlx_features(features: &[toml::Value], driver: &str) -> Vec<toml::Value> {
+    let mut runtime = None;
+    let mut kept: Vec<toml::Value> = Vec::new();
+    for feature in features {
+        match feature.as_str() {
+            Some(name) if SQLX_DRIVERS.contains(&name) => continue,
+            Some(name) if name.starts_with("runtime-") => {
+                if runtime.is_some() {
+                    continue;
+                }
+                runtime = Some(name);
+            }
+            _ => {}
+        }
+        kept.push(feature.clone());
+    }
+
+    if runtime.is_none() {
+        kept.insert(0, toml::Value::String("runtime-tokio".to_string()));
+    }
+    let runtime_at = kept
+        .iter()
+        .position(|feature| {
+            feature
+                .as_str()
+                .is_some_and(|name| name.starts_with("runtime-"))
+        })
+        .unwrap_or(0);
+    kept.insert(runtime_at + 1, toml::Value::String(driver.to_string()));
+    kept
+}
+
+/// The line declaring the dependency `name` with the inline table `settings`
+fn inline_dependency(name: &str, settings: &toml::Table) -> String {
+    let settings: Vec<String> = settings
+        .iter()
+        .map(|(key, value)| format!("{key} = {value}"))
+        .collect();
+    format!("{name} = {{ {} }}", settings.join(", "))
+}
+
+/// The crate name a dependency line declares
+fn dependency_name(line: &str) -> &str {
+    line.split('=').next().unwrap_or_default().trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+
+    /// The Cargo.toml of an Actix project as the upstream writer declares it
+    const UPSTREAM: &str = r#"[package]
+name = "blog"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+actix-web = "4"
+actix-files = "0.6"
+tera = "1"
+sqlx = { version = "0.7", features = ["runtime-tokio", "runtime-async-std", "sqlite", "postgres", "mysql", "any", "chrono"] }
+mongodb = "2"
+serde = { version = "1", features = ["derive"] }
+"#;
+
+    /// A memory filesystem with `contents` as the Cargo.toml at the
+    /// returned path
+    fn manifest(contents: &str) -> (MemoryFileSystem, &'static Path) {
+        let fs = MemoryFileSystem::new();
+        let path = Path::new("Cargo.toml");
+        fs.write(path, contents.as_bytes()).unwrap();
+        (fs, path)
+    }
+
+    fn database(database_type: DatabaseType) -> Database {
+        Database {
+            name: "blog".to_string(),
+            username: String::new(),
+            password: String::new(),
+            host: String::new(),
+            port: String::new(),
+            database_type,
+        }
+    }
+
+    /// The dependencies of the Cargo.toml at `path`
+    fn dependencies(fs: &MemoryFileSystem, path: &Path) -> toml::Table {
+        let manifest: toml::Table = toml::from_str(&fs.contents(path).unwrap()).unwrap();
+        manifest["dependencies"].as_table().unwrap().clone()
+    }
+
+    /// The features enabled on sqlx in `dependencies`
+    fn sqlx_features_of(dependencies: &toml::Table) -> Vec<&str> {
+        dependencies["sqlx"]["features"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(toml::Value::as_str)
+            .collect()
+    }
+
+    #[test]
+    fn enables_only_the_driver_of_the_database() {
+        for (database_type, driver) in [
+            (DatabaseType::Sqlite, "sqlite"),
+            (DatabaseType::Postgres, "postgres"),
+            (DatabaseType::Mysql, "mysql"),
+        ] {
+            let (fs, path) = manifest(UPSTREAM);
+            use_sqlx_driver(&fs, path, &database_type).unwrap();
+
+            let dependencies = dependencies(&fs, path);
+            assert_eq!(
+                sqlx_features_of(&dependencies),
+                ["runtime-tokio", driver, "chrono"]
+            );
+            assert_eq!(dependencies["sqlx"]["version"].as_str(), Some("0.7"));
+        }
+    }
+
+    #[test]
+    fn edits_a_sqlx_table() {
+        let (fs, path) = manifest(
+            "[dependencies]\nserde = \"1\"\n\n[dependencies.sqlx]\nversion = \"0.7\"\nfeatures = [\"postgres\", \"macros\"]\n",
+        );
+        use_sqlx_driver(&fs, path, &DatabaseType::Sqlite).unwrap();
+
+        assert_eq!(
+            sqlx_features_of(&dependencies(&fs, path)),
+            ["runtime-tokio", "sqlite", "macros"]
+        );
+    }
+
+    #[test]
+    fn leaves_mongo_manifests_alone() {
+        let (fs, path) = manifest(UPSTREAM);
+        use_sqlx_driver(&fs, path, &DatabaseType::Mongo).unwrap();
+        assert_eq!(fs.contents(path).as_deref(), Some(UPSTREAM));
+
+        let (fs, path) = manifest("[dependencies]\nmongodb = \"2\"\n");
+        use_sqlx_driver(&fs, path, &DatabaseType::Sqlite).unwrap();
+        assert_eq!(
+            fs.contents(path).as_deref(),
+            Some("[dependencies]\nmongodb = \"2\"\n")
+        );
+    }
+
+    #[test]
+    fn trims_the_frontend_and_other_database_crates() {
+        let (fs, path) = manifest(UPSTREAM);
+        let options = NewProjectOptions {
+            no_frontend: true,
+            ..NewProjectOptions::default()
+        };
+        trim_dependencies(&fs, path, &database(DatabaseType::Sqlite), &options).unwrap();
+
+        let dependencies = dependencies(&fs, path);
+        let names: Vec<&str> = dependencies.keys().map(String::as_str).collect();
+        assert_eq!(names, ["actix-web", "serde", "sqlx"]);
+    }
+
+    #[test]
+    fn trims_sqlx_from_mongo_projects() {
+        let (fs, path) = manifest(UPSTREAM);
+        trim_dependencies(
+            &fs,
+            path,
+            &database(DatabaseType::Mongo),
+            &NewProjectOptions::default(),
+        )
+        .unwrap();
+
+        let dependencies = dependencies(&fs, path);
+        assert!(!dependencies.contains_key("sqlx"));
+        assert!(dependencies.contains_key("mongodb"));
+        assert!(dependencies.contains_key("tera"));
+    }
+
+    #[test]
+    fn minimal_sqlite_manifest_has_one_driver() {
+        let (fs, path) = manifest(UPSTREAM);
+        let sqlite = database(DatabaseType::Sqlite);
+        trim_dependencies(&fs, path, &sqlite, &NewProjectOptions::default()).unwrap();
+        use_sqlx_driver(&fs, path, &sqlite.database_type).unwrap();
+
+        let dependencies = dependencies(&fs, path);
+        let features = sqlx_features_of(&dependencies);
+        assert!(features.contains(&"sqlite"));
+        for driver in ["postgres", "mysql", "any"] {
+            assert!(!features.contains(&driver), "{driver} is enabled");
+        }
+        assert!(!dependencies.contains_key("mongodb"));
+    }
+}
@@ -0,0 +1,451 @@
+//! Writers for the CSRF protection of the session login
+//! Actix projects with session auth get a middleware in src/csrf.rs that
+//! keeps a token in the session of the upstream login and rejects requests
+//! changing state without it. The POST forms of the templates get a hidden
+//! `csrf_token` field the middleware fills with the token of the session
+//! while the page is served, so the upstream handlers need no changes. A
+//! script in the layout fills forms without the field from the cookie
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rustyroad::Project;
+
+use crate::dependency::Dependency;
+use crate::filesystem::FileSystem;
+use crate::layout::project_file;
+use crate::options::NewProjectOptions;
+use crate::writers::cargo::{add_dependency, merge_dependency};
+use crate::writers::declare_module;
+use crate::writers::session::{add_session_middleware, uses_sessions};
+
+/// The module of the generated application holding the middleware
+pub const CSRF_MODULE: &str = "csrf";
+
+/// The crates the middleware is built on, besides actix-session
+const CSRF_DEPENDENCIES: [&str; 3] = [
+    "actix-http = \"3\"",
+    "rand = \"0.8\"",
+    "serde_urlencoded = \"0.7\"",
+];
+
+/// The middleware checking the token of every request that changes state
+const CSRF_SOURCE: &str = r##"//! CSRF protection for the forms of the session login
+//! Every session gets a random token. HTML pages are served with it in
+//! their empty `csrf_token` fields, and it is mirrored into the
+//! `csrf_token` cookie for the script in the layout to copy into forms
+//! without the field. POST, PUT, PATCH and DELETE requests have to send it
+//! back in the `csrf_token` field of a urlencoded form or the
+//! `X-CSRF-Token` header, other requests are answered with 403 Forbidden.
+//! Multipart forms have to use the header
+
+use std::collections::HashMap;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_session::SessionExt;
+use actix_web::body::{self, EitherBody, MessageBody};
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header;
+use actix_web::{error, web, Error, HttpMessage, HttpResponse};
+use rand::distributions::{Alphanumeric, DistString};
+
+/// The session key, cookie and form field holding the token
+pub const FIELD: &str = "csrf_token";
+
+/// The header JavaScript clients send the token in
+const HEADER: &str = "x-csrf-token";
+
+/// The hidden field of a template, filled with the token when served
+const EMPTY_FIELD: &str = r#"name="csrf_token" value="""#;
+
+/// Checks the CSRF token of every request that changes state
+/// Has to be wrapped before the session middleware, so it runs inside it
+pub struct Csrf;
+
+impl<S, B> Transform<S, ServiceRequest> for Csrf
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CsrfMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CsrfMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct CsrfMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for CsrfMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            let session = req.get_session();
+            let token = match session.get::<String>(FIELD)? {
+                Some(token) => token,
+                None => {
+                    let token = Alphanumeric.sample_string(&mut rand::thread_rng(), 32);
+                    session.insert(FIELD, &token)?;
+                    token
+                }
+            };
+
+            if !req.method().is_safe() {
+                let submitted = submitted_token(&mut req).await?;
+                if !submitted.is_some_and(|submitted| same(&submitted, &token)) {
+                    let response = HttpResponse::Forbidden().body("Invalid or missing CSRF token");
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+            }
+
+            let has_cookie = req.cookie(FIELD).is_some_and(|cookie| cookie.value() == token);
+            let response = service.call(req).await?;
+            let mut response = fill_token_fields(response, &token).await?;
+            if !has_cookie {
+                let cookie = Cookie::build(FIELD, token)
+                    .path("/")
+                    .same_site(SameSite::Strict)
+                    .finish();
+                response.response_mut().add_cookie(&cookie)?;
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// `response` with the token in the empty `csrf_token` fields of an HTML
+/// page, other responses are passed on as they are
+async fn fill_token_fields<B>(
+    response: ServiceResponse<B>,
+    token: &str,
+) -> Result<ServiceResponse<EitherBody<B>>, Error>
+where
+    B: MessageBody + 'static,
+{
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if !is_html {
+        return Ok(response.map_into_left_body());
+    }
+
+    let (request, response) = response.into_parts();
+    let (response, page) = response.into_parts();
+    let page = body::to_bytes(page).await.map_err(|why| {
+        let why: Box<dyn std::error::Error> = why.into();
+        error::ErrorInternalServerError(why)
+    })?;
+    let page = String::from_utf8_lossy(&page).replace(
+        EMPTY_FIELD,
+        &format!(r#"name="{FIELD}" value="{token}""#),
+    );
+
+    let response = response.set_body(page).map_into_boxed_body();
+    Ok(ServiceResponse::new(request, response).map_into_right_body())
+}
+
+/// The token sent with the request, from the header or the form field
+/// The form body is put back for the handler after reading it
+async fn submitted_token(req: &mut ServiceRequest) -> Result<Option<String>, Error> {
+    if let Some(value) = req.headers().get(HEADER) {
+        return Ok(value.to_str().ok().map(str::to_string));
+    }
+
+    let is_form = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+    if !is_form {
+        return Ok(None);
+    }
+
+    let body = req.extract::<web::Bytes>().await?;
+    let fields: HashMap<String, String> = serde_urlencoded::from_bytes(&body).unwrap_or_default();
+    let (_, mut payload) = actix_http::h1::Payload::create(true);
+    payload.unread_data(body);
+    req.set_payload(payload.into());
+
+    Ok(fields.get(FIELD).cloned())
+}
+
+/// Compares the tokens in constant time
+fn same(submitted: &str, token: &str) -> bool {
+    submitted.len() == token.len()
+        && submitted
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+"##;
+
+/// The hidden field of a POST form, filled by the middleware when served
+const TOKEN_FIELD: &str = r#"<input type="hidden" name="csrf_token" value="">"#;
+
+/// Copies the token from the cookie into the forms posted from the page
+/// that weren't served with it, like forms added by scripts
+const FORM_SCRIPT: &str = r#"    <script>
+        // Sends the CSRF token with forms the server didn't fill, see src/csrf.rs
+        document.addEventListener("submit", (event) => {
+            const form = event.target;
+            const cookie = document.cookie
+                .split("; ")
+                .find((cookie) => cookie.startsWith("csrf_token="));
+            if (!cookie || form.method.toLowerCase() !== "post") {
+                return;
+            }
+            let field = form.querySelector("input[name=csrf_token]");
+            if (field && field.value) {
+                return;
+            }
+            if (!field) {
+                field = document.createElement("input");
+                field.type = "hidden";
+                field.name = "csrf_token";
+                form.appendChild(field);
+            }
+            field.value = cookie.split("=")[1];
+        });
+    </script>
+"#;
+
+/// Whether a project generated with `options` is protected against CSRF
+/// Only the Actix session login has a session to keep the token in
+pub fn uses_csrf(options: &NewProjectOptions) -> bool {
+    !options.no_csrf && uses_sessions(options)
+}
+
+/// The module holding the middleware
+pub fn csrf_module_path(project: &Project) -> PathBuf {
+    project_file(project, &format!("src/{CSRF_MODULE}.rs"))
+}
+
+/// Writes src/csrf.rs with the middleware
+pub fn write_to_csrf_module(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    fs.write(&csrf_module_path(project), CSRF_SOURCE.as_bytes())
+}
+
+/// Declares the dependencies of the middleware in the Cargo.toml at `cargo_toml`
+pub fn add_csrf_dependencies(fs: &dyn FileSystem, cargo_toml: &Path) -> io::Result<()> {
+    merge_dependency(
+        fs,
+        cargo_toml,
+        &Dependency {
+            name: "actix-session".to_string(),
+            version: "0.7".to_string(),
+            features: vec!["cookie-session".to_string()],
+        },
+    )?;
+    for dependency in CSRF_DEPENDENCIES {
+        add_dependency(fs, cargo_toml, dependency)?;
+    }
+    Ok(())
+}
+
+/// Wraps the app of the upstream main.rs at `main_rs` in the middleware
+/// A main.rs without a session middleware gets a cookie session, whose key
+/// is generated at startup
+pub fn add_csrf(fs: &dyn FileSystem, main_rs: &Path) -> io::Result<()> {
+    let contents = fs.read_to_string(main_rs)?;
+    if contents.contains("csrf::Csrf") {
+        return Ok(());
+    }
+    let mut contents = add_session_middleware(&contents, main_rs)?;
+
+    // The first wrap runs innermost, inside the session middleware
+    let app = contents.find("App::new()").ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} doesn't create an `App`", main_rs.display()),
+        )
+    })? + "App::new()".len();
+    contents.insert_str(app, "\n            .wrap(csrf::Csrf)");
+    fs.write(main_rs, contents.as_bytes())?;
+
+    declare_module(fs, main_rs, CSRF_MODULE).map_err(io::Error::other)
+}
+
+/// Adds an empty `csrf_token` field to the POST forms of the page at
+/// `path`, which the middleware fills while serving it, and the fallback
+/// script before `</body>`
+/// Pages extending the layout have no `</body>` and get the script from it
+pub fn add_form_script(fs: &dyn FileSystem, path: &Path) -> io::Result<()> {
+    let contents = fs.read_to_string(path)?;
+    let mut protected = with_token_fields(&contents);
+    if !protected.contains("see src/csrf.rs") {
+        if let Some(position) = protected.rfind("</body>") {
+            protected.insert_str(position, FORM_SCRIPT);
+        }
+    }
+
+    if protected != contents {
+        fs.write(path, protected.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// `page` with an empty `csrf_token` field at the start of every POST form
+/// that has none
+fn with_token_fields(page: &str) -> String {
+    // Lowercasing ASCII keeps the byte offsets, so tags are found in `lower`
+    // and copied from `page`
+    let lower = page.to_ascii_lowercase();
+    let mut protected = String::with_capacity(page.len());
+    let mut position = 0;
+    while let Some(start) = lower[position..]
+        .find("<form")
+        .map(|offset| position + offset)
+    {
+        let Some(length) = lower[start..].find('>') else {
+            break;
+        };
+        let tag_end = start + length + 1;
+        let form_end = lower[tag_end..]
+            .find("</form>")
+            .map_or(page.len(), |offset| tag_end + offset);
+
+        protected.push_str(&page[position..tag_end]);
+        if form_method(&lower[start..tag_end]) == "post"
+            && !page[tag_end..form_end].contains("name=\"csrf_token\"")
+        {
+            protected.push_str("\n    ");
+            protected.push_str(TOKEN_FIELD);
+        }
+        position = tag_end;
+    }
+    protected.push_str(&page[position..]);
+    protected
+}
+
+/// The method of the lowercased form `tag`, quoted or not, `get` when it
+/// names none
+fn form_method(tag: &str) -> &str {
+    let mut rest = tag;
+    while let Some(index) = rest.find("method") {
+        let is_attribute = rest[..index].ends_with(char::is_whitespace);
+        let after = &rest[index + "method".len()..];
+        if let (true, Some(value)) = (is_attribute, after.trim_start().strip_prefix('=')) {
+            let value = value.trim_start().trim_start_matches(['"', '\'']);
+            let end = value
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(value.len());
+            return &value[..end];
+        }
+        rest = after;
+    }
+    "get"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+
+    const LOGIN: &str = r#"<html>
+<body>
+<form action="/login" method="post">
+    <input type="text" name="username">
+</form>
+<form action="/search" method="get">
+    <input type="text" name="q">
+</form>
+</body>
+</html>
+"#;
+
+    #[test]
+    fn adds_the_field_to_post_forms() {
+        assert_eq!(
+            with_token_fields(LOGIN),
+            LOGIN.replace(
+                "<form action=\"/login\" method=\"post\">",
+                "<form action=\"/login\" method=\"post\">\n    <input type=\"hidden\" name=\"csrf_token\" value=\"\">"
+            )
+        );
+    }
+
+    #[test]
+    fn reads_the_method_in_any_case_and_quoting() {
+        for form in [
+            "<form action=\"/login\" method=post>",
+            "<form action=\"/login\" method=\"POST\">",
+            "<FORM ACTION='/login' METHOD=Post>",
+            "<form method = 'post'>",
+        ] {
+            let page = format!("{form}</form>");
+            assert!(
+                with_token_fields(&page).contains(TOKEN_FIELD),
+                "{form} was left unprotected"
+            );
+        }
+        for form in ["<form method=get>", "<form action=\"/method\">"] {
+            let page = format!("{form}</form>");
+            assert_eq!(with_token_fields(&page), page);
+        }
+    }
+
+    #[test]
+    fn keeps_forms_that_send_the_token() {
+        let page = r#"<form method="POST"><input type="hidden" name="csrf_token" value="{{ token }}"></form>"#;
+        assert_eq!(with_token_fields(page), page);
+    }
+
+    #[test]
+    fn the_middleware_fills_the_field_of_the_templates() {
+        let empty_field = r#"name="csrf_token" value="""#;
+        assert!(TOKEN_FIELD.contains(empty_field));
+        assert!(CSRF_SOURCE.contains(&format!("const EMPTY_FIELD: &str = r#\"{empty_field}\"#;")));
+    }
+
+    #[test]
+    fn protects_a_standalone_page_once() {
+        let fs = MemoryFileSystem::new();
+        let path = Path::new("login.html.tera");
+        fs.write(path, LOGIN.as_bytes()).unwrap();
+
+        add_form_script(&fs, path).unwrap();
+        let protected = fs.contents(path).unwrap();
+        add_form_script(&fs, path).unwrap();
+
+        assert_eq!(fs.contents(path).unwrap(), protected);
+        assert_eq!(protected.matches(TOKEN_FIELD).count(), 1);
+        assert!(protected.contains(&format!("{FORM_SCRIPT}</body>")));
+    }
+
+    #[test]
+    fn leaves_pages_of_the_layout_without_the_script() {
+        let fs = MemoryFileSystem::new();
+        let path = Path::new("dashboard.html.tera");
+        let page = "{% extends \"base.html.tera\" %}\n<form method=\"post\"></form>\n";
+        fs.write(path, page.as_bytes()).unwrap();
+
+        add_form_script(&fs, path).unwrap();
+        let protected = fs.contents(path).unwrap();
+        assert!(protected.contains(TOKEN_FIELD));
+        assert!(!protected.contains("<script>"));
+    }
+}
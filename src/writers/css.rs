@@ -0,0 +1,107 @@
+//! Writers for the projects styled with Bootstrap or a plain stylesheet
+//! The upstream base.html links the Tailwind build, so the layout of the
+//! other CSS frameworks is generated here
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rustyroad::Project;
+
+use crate::css::CssFramework;
+use crate::filesystem::FileSystem;
+use crate::layout::package_name;
+
+/// The Bootstrap release linked from the CDN
+const BOOTSTRAP_VERSION: &str = "5.3.3";
+
+/// The plain stylesheet, relative to the static directory served at `/static`
+const STYLESHEET: &str = "css/style.css";
+
+/// Where the plain stylesheet is written
+pub fn stylesheet_path(project: &Project) -> PathBuf {
+    Path::new(&project.static_dir).join(STYLESHEET)
+}
+
+/// Writes base.html.tera linking the stylesheet of `css`
+/// Only meant for Bootstrap and plain CSS, Tailwind uses the upstream layout
+pub fn write_to_base_html(
+    fs: &dyn FileSystem,
+    project: &Project,
+    css: CssFramework,
+) -> io::Result<()> {
+    let (head, scripts, main_class) = match css {
+        CssFramework::Bootstrap => (
+            format!(
+                "<link rel=\"stylesheet\" href=\"https://cdn.jsdelivr.net/npm/bootstrap@{BOOTSTRAP_VERSION}/dist/css/bootstrap.min.css\">"
+            ),
+            format!(
+                "\n    <script src=\"https://cdn.jsdelivr.net/npm/bootstrap@{BOOTSTRAP_VERSION}/dist/js/bootstrap.bundle.min.js\"></script>"
+            ),
+            "container py-4",
+        ),
+        CssFramework::Tailwind | CssFramework::None => (
+            format!("<link rel=\"stylesheet\" href=\"/static/{STYLESHEET}\">"),
+            String::new(),
+            "container",
+        ),
+    };
+
+    let contents = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>{{% block title %}}{name}{{% endblock title %}}</title>
+    {head}
+</head>
+<body>
+    <main class="{main_class}">
+        {{% block content %}}{{% endblock content %}}
+    </main>{scripts}
+</body>
+</html>
+"#,
+        name = package_name(project)
+    );
+
+    fs.write(Path::new(&project.base_html), contents.as_bytes())
+}
+
+/// Writes the plain stylesheet used when no CSS framework is selected
+pub fn write_to_stylesheet(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    let contents = r#"*,
+*::before,
+*::after {
+    box-sizing: border-box;
+}
+
+body {
+    margin: 0;
+    font-family: system-ui, -apple-system, "Segoe UI", Roboto, sans-serif;
+    line-height: 1.5;
+    color: #1f2937;
+}
+
+.container {
+    max-width: 960px;
+    margin: 0 auto;
+    padding: 2rem 1rem;
+}
+
+.title {
+    font-size: 2rem;
+    font-weight: 700;
+}
+
+.muted {
+    color: #6b7280;
+}
+"#;
+
+    let path = stylesheet_path(project);
+    if let Some(parent) = path.parent() {
+        fs.create_dir(parent)?;
+    }
+    fs.write(&path, contents.as_bytes())
+}
@@ -0,0 +1,173 @@
+//! Writers for the Dockerfile and docker-compose.yml of a generated project
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::database::{Database, DatabaseType};
+use rustyroad::Project;
+use tera::Context;
+
+use crate::database::{database_url, default_port, is_sqlite_memory, ConnectionTarget};
+use crate::filesystem::FileSystem;
+use crate::layout::{project_file, relative_to_project};
+use crate::options::NewProjectOptions;
+use crate::writers::render::render;
+
+/// Name of the database service in docker-compose.yml
+const DATABASE_SERVICE: &str = "db";
+
+/// Writes a multi-stage Dockerfile that builds the app in release mode and
+/// copies the binary, templates and static assets into a slim runtime image
+pub fn write_to_dockerfile(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> io::Result<()> {
+    let mut variables = Context::new();
+    variables.insert(
+        "templates",
+        relative_to_project(project, &project.templates),
+    );
+    variables.insert(
+        "static_dir",
+        relative_to_project(project, &project.static_dir),
+    );
+
+    let dockerfile = render("Dockerfile", project, database_data, options, variables)?;
+    fs.write(&project_file(project, "Dockerfile"), dockerfile.as_bytes())?;
+    let dockerignore = render(
+        "dockerignore",
+        project,
+        database_data,
+        options,
+        Context::new(),
+    )?;
+    fs.write(
+        &project_file(project, ".dockerignore"),
+        dockerignore.as_bytes(),
+    )
+}
+
+/// Writes a docker-compose.yml that runs the app next to a database service
+/// matching the project's database type
+/// The service uses the same credentials that were written to rustyroad.toml,
+/// and the app reaches it through the `db` hostname
+/// The app container publishes `port`, the port the server listens on
+pub fn write_to_docker_compose(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+    port: u16,
+) -> io::Result<()> {
+    // Inside the compose network the database is reached by service name on
+    // its standard port
+    let container_database = Database {
+        host: DATABASE_SERVICE.to_string(),
+        port: default_port(&database_data.database_type).to_string(),
+        ..database_data.clone()
+    };
+
+    let mut contents =
+        format!("services:\n  app:\n    build: .\n    ports:\n      - \"{port}:{port}\"\n");
+
+    match database_data.database_type {
+        DatabaseType::Sqlite if is_sqlite_memory(database_data) => {
+            // Nothing to persist, every container start gets a fresh database
+            contents.push_str(&format!(
+                "    environment:\n      DATABASE_URL: \"{}\"\n",
+                database_url(database_data, ConnectionTarget::Project)
+            ));
+        }
+        DatabaseType::Sqlite => {
+            // The database file lives in a volume so it survives rebuilds
+            contents.push_str(&format!(
+                "    environment:\n      DATABASE_URL: \"{}\"\n    volumes:\n      - app-data:/app/data\n\nvolumes:\n  app-data:\n",
+                database_url(
+                    &Database {
+                        name: format!("/app/data/{}", database_file_name(database_data)),
+                        ..database_data.clone()
+                    },
+                    ConnectionTarget::Project
+                )
+            ));
+        }
+        _ => {
+            contents.push_str(&format!(
+                "    environment:\n      DATABASE_URL: \"{}\"\n    depends_on:\n      {DATABASE_SERVICE}:\n        condition: service_healthy\n\n",
+                database_url(&container_database, ConnectionTarget::Project)
+            ));
+            contents.push_str(&database_service(database_data));
+            contents.push_str("\nvolumes:\n  db-data:\n");
+        }
+    }
+
+    fs.write(
+        &project_file(project, "docker-compose.yml"),
+        contents.as_bytes(),
+    )
+}
+
+/// The compose service definition of the database server
+fn database_service(database_data: &Database) -> String {
+    let (image, environment, data_dir, port, healthcheck) = match database_data.database_type {
+        DatabaseType::Postgres => (
+            "postgres:16",
+            format!(
+                "      POSTGRES_USER: \"{}\"\n      POSTGRES_PASSWORD: \"{}\"\n      POSTGRES_DB: \"{}\"\n",
+                database_data.username, database_data.password, database_data.name
+            ),
+            "/var/lib/postgresql/data",
+            "5432",
+            format!(
+                "[\"CMD-SHELL\", \"pg_isready -U {} -d {}\"]",
+                database_data.username, database_data.name
+            ),
+        ),
+        DatabaseType::Mysql => {
+            // The official image creates root itself and refuses MYSQL_USER=root
+            let user = if database_data.username == "root" {
+                String::new()
+            } else {
+                format!(
+                    "      MYSQL_USER: \"{}\"\n      MYSQL_PASSWORD: \"{}\"\n",
+                    database_data.username, database_data.password
+                )
+            };
+            (
+                "mysql:8",
+                format!(
+                    "      MYSQL_ROOT_PASSWORD: \"{}\"\n      MYSQL_DATABASE: \"{}\"\n{user}",
+                    database_data.password, database_data.name
+                ),
+                "/var/lib/mysql",
+                "3306",
+                "[\"CMD\", \"mysqladmin\", \"ping\", \"-h\", \"localhost\"]".to_string(),
+            )
+        }
+        DatabaseType::Mongo => (
+            "mongo:7",
+            format!(
+                "      MONGO_INITDB_ROOT_USERNAME: \"{}\"\n      MONGO_INITDB_ROOT_PASSWORD: \"{}\"\n      MONGO_INITDB_DATABASE: \"{}\"\n",
+                database_data.username, database_data.password, database_data.name
+            ),
+            "/data/db",
+            "27017",
+            "[\"CMD\", \"mongosh\", \"--quiet\", \"--eval\", \"db.adminCommand('ping')\"]"
+                .to_string(),
+        ),
+        DatabaseType::Sqlite => unreachable!("SQLite has no database service"),
+    };
+
+    format!(
+        "  {DATABASE_SERVICE}:\n    image: {image}\n    environment:\n{environment}    ports:\n      - \"{port}:{port}\"\n    volumes:\n      - db-data:{data_dir}\n    healthcheck:\n      test: {healthcheck}\n      interval: 5s\n      timeout: 5s\n      retries: 10\n"
+    )
+}
+
+/// The file name of the SQLite database, without any directories
+fn database_file_name(database_data: &Database) -> String {
+    Path::new(&database_data.name)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| format!("{}.db", database_data.name))
+}
@@ -0,0 +1,124 @@
+//! Writers for the .env files of the generated application
+//! .env holds the real connection URL and secrets and stays out of git,
+//! .env.example is committed and documents the variables with placeholders
+
+use std::io;
+
+use rustyroad::database::{Database, DatabaseType};
+use rustyroad::Project;
+use tera::Context;
+
+use crate::auth::AuthScheme;
+use crate::database::{database_url_with_tls, sqlite_location, ConnectionTarget};
+use crate::filesystem::FileSystem;
+use crate::layout::{project_file, relative_to_project};
+use crate::options::NewProjectOptions;
+use crate::writers::render::render;
+use crate::writers::session::uses_redis_sessions;
+use crate::writers::settings::uses_config_module;
+
+/// The crate the generated main.rs loads .env with
+pub const DOTENVY_DEPENDENCY: &str = "dotenvy = \"0.15\"";
+
+/// Name of the file holding the runtime settings
+pub const ENV_FILE: &str = ".env";
+
+/// Name of the committed copy of .env with placeholder values
+pub const ENV_EXAMPLE_FILE: &str = ".env.example";
+
+/// Random bytes in a generated signing key
+const SECRET_BYTES: usize = 64;
+
+/// Writes .env with the URL of the project database and, for JWT
+/// authentication, a freshly generated signing key
+pub fn write_to_env(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> io::Result<()> {
+    let jwt = options.auth == AuthScheme::Jwt;
+    let mut variables = Context::new();
+    variables.insert(
+        "database_url",
+        &runtime_url(project, database_data, options),
+    );
+    variables.insert("jwt", &jwt);
+    variables.insert("settings", &uses_config_module(options));
+    if jwt {
+        variables.insert("jwt_secret", &generate_secret()?);
+    }
+    let redis = uses_redis_sessions(options);
+    variables.insert("redis", &redis);
+    if redis {
+        // The cookie key needs at least 64 bytes
+        variables.insert("session_secret", &generate_secret()?);
+    }
+    let contents = render("env", project, database_data, options, variables)?;
+
+    fs.write(&project_file(project, ENV_FILE), contents.as_bytes())
+}
+
+/// Writes .env.example, listing the variables of .env with placeholder
+/// credentials instead of the real ones
+pub fn write_to_env_example(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> io::Result<()> {
+    let mut placeholder = database_data.clone();
+    if !matches!(database_data.database_type, DatabaseType::Sqlite) {
+        placeholder.username = "user".to_string();
+        placeholder.password = "password".to_string();
+        placeholder.host = "localhost".to_string();
+    }
+
+    let mut variables = Context::new();
+    variables.insert("database_url", &runtime_url(project, &placeholder, options));
+    variables.insert("jwt", &(options.auth == AuthScheme::Jwt));
+    variables.insert("settings", &uses_config_module(options));
+    variables.insert("redis", &uses_redis_sessions(options));
+    let contents = render("env.example", project, database_data, options, variables)?;
+
+    fs.write(
+        &project_file(project, ENV_EXAMPLE_FILE),
+        contents.as_bytes(),
+    )
+}
+
+/// The URL the application connects with when run from the project directory
+/// SQLite paths inside the project are made relative to it
+fn runtime_url(project: &Project, database_data: &Database, options: &NewProjectOptions) -> String {
+    let mut runtime = database_data.clone();
+    if matches!(database_data.database_type, DatabaseType::Sqlite) {
+        let location = sqlite_location(project, database_data, options.sqlite_path.as_deref());
+        runtime.name = relative_to_project(project, &location).to_string();
+    }
+
+    database_url_with_tls(&runtime, ConnectionTarget::Project, &options.tls)
+}
+
+/// A hex encoded signing key of `SECRET_BYTES` bytes from the OS random
+/// number generator, nothing about the project or the time it was generated
+/// at helps guessing it
+fn generate_secret() -> io::Result<String> {
+    let mut bytes = [0u8; SECRET_BYTES];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_random_and_hex_encoded() {
+        let first = generate_secret().unwrap();
+        let second = generate_secret().unwrap();
+
+        assert_eq!(first.len(), 2 * SECRET_BYTES);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,147 @@
+//! Writer for the .gitignore of a generated project
+//! Besides the build output it ignores what only makes sense on the
+//! developer's machine: the local settings in .env, a SQLite database and
+//! the installed npm packages of a Tailwind frontend
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::database::{Database, DatabaseType};
+use rustyroad::Project;
+use tera::Context;
+
+use crate::css::CssFramework;
+use crate::database::{is_sqlite_memory, sqlite_location};
+use crate::filesystem::FileSystem;
+use crate::layout::relative_to_project;
+use crate::options::NewProjectOptions;
+use crate::writers::env::ENV_FILE;
+use crate::writers::render::render;
+
+/// The lines of the .gitignore of a project generated with `options`
+pub fn gitignore_entries(
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> Vec<String> {
+    let mut entries = vec!["/target".to_string(), ENV_FILE.to_string()];
+
+    if matches!(database_data.database_type, DatabaseType::Sqlite)
+        && !is_sqlite_memory(database_data)
+    {
+        // A database outside the project can't be committed by accident
+        let location = sqlite_location(project, database_data, options.sqlite_path.as_deref());
+        if !Path::new(&location).is_absolute() {
+            entries.push(format!("/{}", relative_to_project(project, &location)));
+        }
+        entries.extend(["*.db", "*.db-journal", "*.sqlite", "*.sqlite3"].map(str::to_string));
+    }
+
+    // Only Tailwind is installed from npm and built
+    if !options.no_frontend && options.css == CssFramework::Tailwind {
+        entries.extend(["node_modules/", "dist/"].map(str::to_string));
+    }
+
+    entries
+}
+
+/// Writes the .gitignore of a project generated with `options`
+pub fn write_to_gitignore(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> io::Result<()> {
+    let mut variables = Context::new();
+    variables.insert(
+        "entries",
+        &gitignore_entries(project, database_data, options),
+    );
+    let contents = render("gitignore", project, database_data, options, variables)?;
+
+    fs.write(Path::new(&project.gitignore), contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::layout::project_at;
+
+    fn database(database_type: DatabaseType, name: &str) -> Database {
+        Database {
+            name: name.to_string(),
+            username: String::new(),
+            password: String::new(),
+            host: String::new(),
+            port: String::new(),
+            database_type,
+        }
+    }
+
+    #[test]
+    fn ignores_the_sqlite_database_and_env() {
+        let project = project_at(Path::new("blog"));
+        let options = NewProjectOptions {
+            sqlite_path: Some(PathBuf::from("data/blog.db")),
+            ..NewProjectOptions::default()
+        };
+        let entries = gitignore_entries(
+            &project,
+            &database(DatabaseType::Sqlite, "blog.db"),
+            &options,
+        );
+
+        assert!(entries.contains(&"/data/blog.db".to_string()));
+        assert!(entries.contains(&".env".to_string()));
+        assert!(entries.contains(&"*.db-journal".to_string()));
+    }
+
+    #[test]
+    fn leaves_out_a_database_outside_the_project() {
+        let project = project_at(Path::new("blog"));
+        let entries = gitignore_entries(
+            &project,
+            &database(DatabaseType::Sqlite, "/var/lib/blog.db"),
+            &NewProjectOptions::default(),
+        );
+
+        assert!(!entries.iter().any(|entry| entry.contains("/var/lib")));
+        assert!(entries.contains(&ENV_FILE.to_string()));
+    }
+
+    #[test]
+    fn ignores_no_database_of_a_server() {
+        let project = project_at(Path::new("blog"));
+        for database_data in [
+            database(DatabaseType::Postgres, "blog"),
+            database(DatabaseType::Sqlite, ":memory:"),
+        ] {
+            let options = NewProjectOptions {
+                css: CssFramework::Bootstrap,
+                ..NewProjectOptions::default()
+            };
+            assert_eq!(
+                gitignore_entries(&project, &database_data, &options),
+                ["/target", ".env"]
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_the_npm_packages_of_tailwind() {
+        let project = project_at(Path::new("blog"));
+        let postgres = database(DatabaseType::Postgres, "blog");
+
+        let tailwind = gitignore_entries(&project, &postgres, &NewProjectOptions::default());
+        assert!(tailwind.contains(&"node_modules/".to_string()));
+
+        let options = NewProjectOptions {
+            no_frontend: true,
+            ..NewProjectOptions::default()
+        };
+        let api = gitignore_entries(&project, &postgres, &options);
+        assert!(!api.contains(&"node_modules/".to_string()));
+    }
+}
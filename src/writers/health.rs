@@ -0,0 +1,116 @@
+//! Writer for the health routes of the generated application
+//! `/health` answers as soon as the server runs, for liveness probes.
+//! `/ready` also checks the database with `SELECT 1` through the pool of
+//! src/pool.rs and answers 503 while it can't be queried, for readiness
+//! probes. MongoDB projects have no pool and only get `/health`
+
+use std::io;
+use std::path::PathBuf;
+
+use rustyroad::database::Database;
+use rustyroad::Project;
+
+use crate::filesystem::FileSystem;
+use crate::framework::Framework;
+use crate::layout::routes_dir;
+use crate::writers::pool::uses_pool;
+
+/// The route module holding the health routes
+pub const HEALTH_MODULE: &str = "health";
+
+/// The path of the health route module
+pub fn health_route_path(project: &Project) -> PathBuf {
+    routes_dir(project).join(format!("{HEALTH_MODULE}.rs"))
+}
+
+/// Writes the health route module for `framework`
+pub fn write_to_health_routes(
+    fs: &dyn FileSystem,
+    project: &Project,
+    framework: Framework,
+    database_data: &Database,
+) -> io::Result<()> {
+    let ready = uses_pool(database_data);
+    let contents = match framework {
+        Framework::Actix => actix_source(ready),
+        Framework::Axum => axum_source(ready),
+    };
+
+    fs.create_dir(&routes_dir(project))?;
+    fs.write(&health_route_path(project), contents.as_bytes())
+}
+
+fn actix_source(ready: bool) -> String {
+    let (pool_import, services, ready_handler) = if ready {
+        (
+            "\nuse crate::pool;\n",
+            "cfg.service(health).service(ready);",
+            r#"
+/// Answers once the database takes queries, for readiness probes
+#[get("/ready")]
+async fn ready() -> impl Responder {
+    match sqlx::query("SELECT 1").execute(pool::shared()).await {
+        Ok(_) => HttpResponse::Ok().body("ready"),
+        Err(why) => HttpResponse::ServiceUnavailable().body(why.to_string()),
+    }
+}
+"#,
+        )
+    } else {
+        ("", "cfg.service(health);", "")
+    };
+
+    format!(
+        r#"use actix_web::{{get, web, HttpResponse, Responder}};
+{pool_import}
+/// Registers the routes of this module
+pub fn configure(cfg: &mut web::ServiceConfig) {{
+    {services}
+}}
+
+/// Answers as long as the server runs, for liveness probes
+#[get("/health")]
+async fn health() -> impl Responder {{
+    HttpResponse::Ok().body("ok")
+}}
+{ready_handler}"#
+    )
+}
+
+fn axum_source(ready: bool) -> String {
+    let (imports, routes, ready_handler) = if ready {
+        (
+            "use axum::http::StatusCode;\nuse axum::routing::get;\nuse axum::{Extension, Router};\n\nuse crate::pool::Pool;\n",
+            "Router::new()\n        .route(\"/health\", get(health))\n        .route(\"/ready\", get(ready))",
+            r#"
+/// Answers once the database takes queries, for readiness probes
+async fn ready(Extension(pool): Extension<Pool>) -> (StatusCode, String) {
+    match sqlx::query("SELECT 1").execute(&pool).await {
+        Ok(_) => (StatusCode::OK, "ready".to_string()),
+        Err(why) => (StatusCode::SERVICE_UNAVAILABLE, why.to_string()),
+    }
+}
+"#,
+        )
+    } else {
+        (
+            "use axum::routing::get;\nuse axum::Router;\n",
+            "Router::new().route(\"/health\", get(health))",
+            "",
+        )
+    };
+
+    format!(
+        r#"{imports}
+/// The routes of this module
+pub fn router() -> Router {{
+    {routes}
+}}
+
+/// Answers as long as the server runs, for liveness probes
+async fn health() -> &'static str {{
+    "ok"
+}}
+{ready_handler}"#
+    )
+}
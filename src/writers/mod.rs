@@ -0,0 +1,172 @@
+pub mod actix;
+pub mod auth;
+pub mod axum;
+pub mod cargo;
+pub mod ci;
+pub mod csrf;
+pub mod css;
+pub mod docker;
+pub mod env;
+pub mod gitignore;
+pub mod health;
+pub mod models;
+pub mod password;
+pub mod pool;
+pub mod readme;
+pub mod render;
+pub mod scripts;
+pub mod seeds;
+pub mod session;
+pub mod settings;
+pub mod tailwind;
+pub mod tasks;
+pub mod telemetry;
+pub mod tests;
+
+use std::path::Path;
+
+use crate::filesystem::FileSystem;
+use crate::GenerateError;
+
+/// Writes `contents` to `path` on `fs`, creating any missing parent
+/// directories
+/// Refuses to overwrite an existing file so generators never clobber user code
+pub fn write_new_file(
+    fs: &dyn FileSystem,
+    path: &Path,
+    contents: &str,
+) -> Result<(), GenerateError> {
+    if fs.exists(path) {
+        return Err(GenerateError::AlreadyExists {
+            path: path.to_path_buf(),
+        });
+    }
+
+    if let Some(parent) = path.parent() {
+        fs.create_dir(parent)
+            .map_err(GenerateError::file_write(parent))?;
+    }
+
+    fs.write(path, contents.as_bytes())
+        .map_err(GenerateError::file_write(path))
+}
+
+/// Registers `module` in a `mod.rs` file by appending `pub mod <module>;`
+/// The file is created if it doesn't exist and left alone if the module is
+/// already declared
+pub fn add_module(fs: &dyn FileSystem, mod_file: &Path, module: &str) -> Result<(), GenerateError> {
+    let declaration = format!("pub mod {module};");
+
+    let existing = fs.read_to_string(mod_file).unwrap_or_default();
+    if existing.lines().any(|line| line.trim() == declaration) {
+        return Ok(());
+    }
+
+    // Keep the declaration on its own line even if the file lacks a trailing newline
+    let separator = if existing.is_empty() || existing.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+
+    fs.write(
+        mod_file,
+        format!("{existing}{separator}{declaration}\n").as_bytes(),
+    )
+    .map_err(GenerateError::file_write(mod_file))
+}
+
+/// Declares `mod <module>;` in main.rs unless it is declared already
+/// The declaration goes before the first existing one to keep them together
+pub fn declare_module(
+    fs: &dyn FileSystem,
+    main_rs: &Path,
+    module: &str,
+) -> Result<(), GenerateError> {
+    let contents = fs
+        .read_to_string(main_rs)
+        .map_err(|source| GenerateError::FileRead {
+            path: main_rs.to_path_buf(),
+            source,
+        })?;
+
+    let declaration = format!("mod {module};");
+    if contents
+        .lines()
+        .any(|line| line.trim().trim_start_matches("pub ") == declaration)
+    {
+        return Ok(());
+    }
+
+    let mut lines: Vec<&str> = contents.lines().collect();
+    let position = lines
+        .iter()
+        .position(|line| line.starts_with("mod ") || line.starts_with("pub mod "))
+        .unwrap_or(0);
+    lines.insert(position, &declaration);
+
+    fs.write(main_rs, (lines.join("\n") + "\n").as_bytes())
+        .map_err(GenerateError::file_write(main_rs))
+}
+
+// `tests` is taken by the writers of the generated integration tests
+#[cfg(test)]
+mod helper_tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+
+    #[test]
+    fn generates_into_memory() {
+        let fs = MemoryFileSystem::new();
+        let main_rs = Path::new("blog/src/main.rs");
+        let mod_rs = Path::new("blog/src/routes/mod.rs");
+
+        write_new_file(&fs, main_rs, "mod routes;\n\nfn main() {}\n").unwrap();
+        write_new_file(&fs, mod_rs, "").unwrap();
+        add_module(&fs, mod_rs, "index").unwrap();
+        declare_module(&fs, main_rs, "pool").unwrap();
+
+        assert_eq!(
+            fs.files(),
+            [
+                PathBuf::from("blog/src/main.rs"),
+                PathBuf::from("blog/src/routes/mod.rs")
+            ]
+        );
+        assert_eq!(
+            fs.contents(main_rs).as_deref(),
+            Some("mod pool;\nmod routes;\n\nfn main() {}\n")
+        );
+        assert_eq!(fs.contents(mod_rs).as_deref(), Some("pub mod index;\n"));
+    }
+
+    #[test]
+    fn refuses_to_overwrite_a_file() {
+        let fs = MemoryFileSystem::new();
+        let path = Path::new("blog/src/lib.rs");
+        write_new_file(&fs, path, "// user code\n").unwrap();
+
+        assert!(matches!(
+            write_new_file(&fs, path, ""),
+            Err(GenerateError::AlreadyExists { .. })
+        ));
+        assert_eq!(fs.contents(path).as_deref(), Some("// user code\n"));
+    }
+
+    #[test]
+    fn declares_a_module_once() {
+        let fs = MemoryFileSystem::new();
+        let main_rs = Path::new("blog/src/main.rs");
+        write_new_file(&fs, main_rs, "fn main() {}\n").unwrap();
+
+        declare_module(&fs, main_rs, "pool").unwrap();
+        declare_module(&fs, main_rs, "pool").unwrap();
+
+        assert_eq!(
+            fs.contents(main_rs).as_deref(),
+            Some("mod pool;\nfn main() {}\n")
+        );
+    }
+}
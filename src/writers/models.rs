@@ -0,0 +1,361 @@
+//! Writer for the model structs of generated projects
+//! The user model of new projects and the models of `generate model` and
+//! `generate scaffold` come from the same code, for every SQL dialect. Each
+//! field is documented with its column in the dialect of the project
+//! MongoDB models are documents with an `ObjectId` id, queried through the
+//! collection API of the `mongodb` crate
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::database::DatabaseType;
+use rustyroad::Project;
+
+use crate::database::{placeholder, qualified_table, PostgresSettings, TableSettings};
+use crate::filesystem::FileSystem;
+use crate::generators::{Field, FieldType};
+use crate::writers::password::{PASSWORD_FUNCTIONS, PASSWORD_HASH_COLUMN};
+
+/// The derives of every generated model struct
+pub const MODEL_DERIVES: &str = "Debug, Clone, Serialize, Deserialize, sqlx::FromRow";
+
+/// The `id` column every generated table starts with
+pub fn primary_key_sql(database_type: &DatabaseType) -> &'static str {
+    match database_type {
+        DatabaseType::Sqlite => "id INTEGER PRIMARY KEY AUTOINCREMENT",
+        DatabaseType::Postgres => "id SERIAL PRIMARY KEY",
+        DatabaseType::Mysql => "id INT AUTO_INCREMENT PRIMARY KEY",
+        DatabaseType::Mongo => unreachable!("MongoDB models don't use SQL tables"),
+    }
+}
+
+/// The struct `name` with an `id` and `fields`, each documented with its
+/// column in `database_type`
+pub fn model_struct(name: &str, fields: &[Field], database_type: &DatabaseType) -> String {
+    let mut source = format!(
+        "#[derive({MODEL_DERIVES})]\npub struct {name} {{\n    /// `{}`\n    pub id: i32,\n",
+        primary_key_sql(database_type)
+    );
+    for field in fields {
+        source.push_str(&format!(
+            "    /// `{} {} NOT NULL`\n    pub {}: {},\n",
+            field.name,
+            field.field_type.sql_type(database_type),
+            field.name,
+            field.field_type.rust_type()
+        ));
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// The Rust source of a model module holding only the struct
+pub fn model_source(name: &str, fields: &[Field], database_type: &DatabaseType) -> String {
+    format!(
+        "use serde::{{Deserialize, Serialize}};\n\n{}",
+        model_struct(name, fields, database_type)
+    )
+}
+
+/// The columns of the users table the generated user model reads
+fn user_fields() -> Vec<Field> {
+    vec![
+        Field {
+            name: "username".to_string(),
+            field_type: FieldType::String,
+        },
+        Field {
+            name: PASSWORD_HASH_COLUMN.to_string(),
+            field_type: FieldType::String,
+        },
+    ]
+}
+
+/// The Rust source of the user model, with queries looking users up and
+/// creating them through the pool of src/pool.rs
+/// Only an Argon2 hash of the password is stored, `verify_password` checks
+/// a login against it
+/// Postgres projects outside `public` query `schema.users`, projects with a
+/// table prefix the prefixed table
+pub fn user_model_source(
+    database_type: &DatabaseType,
+    postgres: &PostgresSettings,
+    tables: &TableSettings,
+) -> String {
+    let users = qualified_table(database_type, postgres, &tables.table("users"));
+    let first = placeholder(database_type, 1);
+    let second = placeholder(database_type, 2);
+
+    format!(
+        r#"use serde::{{Deserialize, Serialize}};
+
+use crate::pool::Pool;
+
+{model}
+impl User {{
+    /// The user named `username`, `None` if there is none
+    pub async fn find_by_username(
+        pool: &Pool,
+        username: &str,
+    ) -> Result<Option<User>, sqlx::Error> {{
+        sqlx::query_as::<_, User>(
+            "SELECT id, username, password_hash FROM {users} WHERE username = {first}",
+        )
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+    }}
+
+    /// Stores a new user with the hash of `password`
+    pub async fn create(pool: &Pool, username: &str, password: &str) -> Result<(), sqlx::Error> {{
+        let password_hash =
+            hash_password(password).map_err(|why| sqlx::Error::Encode(Box::new(why)))?;
+        sqlx::query("INSERT INTO {users} (username, password_hash) VALUES ({first}, {second})")
+            .bind(username)
+            .bind(password_hash)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }}
+
+    /// Whether `password` is the password of this user
+    pub fn verify_password(&self, password: &str) -> bool {{
+        verify_password(password, &self.password_hash)
+    }}
+}}
+
+{PASSWORD_FUNCTIONS}"#,
+        model = model_struct("User", &user_fields(), database_type)
+    )
+}
+
+/// Writes the user model of a new SQL project
+pub fn write_to_user_models(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_type: &DatabaseType,
+    postgres: &PostgresSettings,
+    tables: &TableSettings,
+) -> io::Result<()> {
+    let path = Path::new(&project.user_model);
+    if let Some(parent) = path.parent() {
+        fs.create_dir(parent)?;
+    }
+    fs.write(
+        path,
+        user_model_source(database_type, postgres, tables).as_bytes(),
+    )
+}
+
+/// The Rust source of the MongoDB model `name`, a document in `collection`
+/// with an `ObjectId` id, `fields` and functions storing, finding, updating
+/// and deleting documents
+/// Each field in `indexes` gets an ascending index, created by
+/// `create_indexes` which the application calls on startup
+pub fn mongo_model_source(
+    name: &str,
+    collection: &str,
+    fields: &[Field],
+    indexes: &[String],
+) -> String {
+    let mut struct_fields = String::new();
+    for field in fields {
+        struct_fields.push_str(&format!(
+            "    pub {}: {},\n",
+            field.name,
+            field.field_type.rust_type()
+        ));
+    }
+
+    let mut source = format!(
+        r#"use futures::stream::TryStreamExt;
+use mongodb::bson::{{doc, oid::ObjectId}};
+use mongodb::{{Collection, Database{index_model}}};
+use serde::{{Deserialize, Serialize}};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct {name} {{
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+{struct_fields}}}
+
+impl {name} {{
+    /// The `{collection}` collection
+    pub fn collection(database: &Database) -> Collection<{name}> {{
+        database.collection::<{name}>("{collection}")
+    }}
+
+    /// Stores a new document, returning its generated id
+    pub async fn create(
+        database: &Database,
+        document: {name},
+    ) -> mongodb::error::Result<ObjectId> {{
+        let result = Self::collection(database).insert_one(document, None).await?;
+        Ok(result
+            .inserted_id
+            .as_object_id()
+            .expect("MongoDB always returns an ObjectId for generated ids"))
+    }}
+
+    /// The document with `id`, `None` if there is none
+    pub async fn find(
+        database: &Database,
+        id: ObjectId,
+    ) -> mongodb::error::Result<Option<{name}>> {{
+        Self::collection(database)
+            .find_one(doc! {{ "_id": id }}, None)
+            .await
+    }}
+
+    /// Every document of the collection
+    pub async fn all(database: &Database) -> mongodb::error::Result<Vec<{name}>> {{
+        Self::collection(database)
+            .find(None, None)
+            .await?
+            .try_collect()
+            .await
+    }}
+
+    /// Replaces the document with `id` by `document`
+    pub async fn update(
+        database: &Database,
+        id: ObjectId,
+        document: &{name},
+    ) -> mongodb::error::Result<()> {{
+        Self::collection(database)
+            .replace_one(doc! {{ "_id": id }}, document, None)
+            .await?;
+        Ok(())
+    }}
+
+    /// Deletes the document with `id`
+    pub async fn delete(database: &Database, id: ObjectId) -> mongodb::error::Result<()> {{
+        Self::collection(database)
+            .delete_one(doc! {{ "_id": id }}, None)
+            .await?;
+        Ok(())
+    }}
+"#,
+        index_model = if indexes.is_empty() {
+            ""
+        } else {
+            ", IndexModel"
+        },
+    );
+
+    if !indexes.is_empty() {
+        let models: String = indexes
+            .iter()
+            .map(|field| {
+                format!(
+                    "            IndexModel::builder().keys(doc! {{ \"{field}\": 1 }}).build(),\n"
+                )
+            })
+            .collect();
+        source.push_str(&format!(
+            r#"
+    /// Creates the indexes of the collection, existing ones are left alone
+    /// so this can run on every startup
+    pub async fn create_indexes(database: &Database) -> mongodb::error::Result<()> {{
+        Self::collection(database)
+            .create_indexes(
+                vec![
+{models}                ],
+                None,
+            )
+            .await?;
+        Ok(())
+    }}
+"#
+        ));
+    }
+
+    source.push_str("}\n");
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post_fields() -> Vec<Field> {
+        vec![
+            Field {
+                name: "title".to_string(),
+                field_type: FieldType::String,
+            },
+            Field {
+                name: "views".to_string(),
+                field_type: FieldType::BigInteger,
+            },
+            Field {
+                name: "published_at".to_string(),
+                field_type: FieldType::DateTime,
+            },
+        ]
+    }
+
+    #[test]
+    fn emits_one_struct_for_every_dialect() {
+        assert_eq!(
+            model_struct("Post", &post_fields(), &DatabaseType::Sqlite),
+            "#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Post {
+    /// `id INTEGER PRIMARY KEY AUTOINCREMENT`
+    pub id: i32,
+    /// `title TEXT NOT NULL`
+    pub title: String,
+    /// `views INTEGER NOT NULL`
+    pub views: i64,
+    /// `published_at DATETIME NOT NULL`
+    pub published_at: chrono::NaiveDateTime,
+}
+"
+        );
+    }
+
+    #[test]
+    fn documents_the_columns_in_the_dialect() {
+        let postgres = model_struct("Post", &post_fields(), &DatabaseType::Postgres);
+        assert!(postgres.contains("/// `id SERIAL PRIMARY KEY`"));
+        assert!(postgres.contains("/// `title VARCHAR(255) NOT NULL`"));
+        assert!(postgres.contains("/// `views BIGINT NOT NULL`"));
+
+        let mysql = model_struct("Post", &post_fields(), &DatabaseType::Mysql);
+        assert!(mysql.contains("/// `id INT AUTO_INCREMENT PRIMARY KEY`"));
+        assert!(mysql.contains("/// `views BIGINT NOT NULL`"));
+    }
+
+    #[test]
+    fn keeps_the_rust_types_across_dialects() {
+        let fields = |database_type| {
+            model_struct("Post", &post_fields(), &database_type)
+                .lines()
+                .filter(|line| !line.trim_start().starts_with("///"))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fields(DatabaseType::Sqlite), fields(DatabaseType::Postgres));
+        assert_eq!(fields(DatabaseType::Sqlite), fields(DatabaseType::Mysql));
+    }
+
+    #[test]
+    fn model_source_imports_the_serde_derives() {
+        let source = model_source("Post", &post_fields(), &DatabaseType::Postgres);
+        assert!(source.starts_with("use serde::{Deserialize, Serialize};\n\n#[derive("));
+    }
+
+    #[test]
+    fn user_model_queries_the_prefixed_table() {
+        let tables = TableSettings {
+            prefix: "app_".to_string(),
+        };
+        let source = user_model_source(
+            &DatabaseType::Postgres,
+            &PostgresSettings::default(),
+            &tables,
+        );
+        assert!(source.contains("FROM app_users WHERE username = $1"));
+        assert!(source.contains("pub password_hash: String,"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Password hashing for the users of generated projects
+//! Passwords are only ever stored as Argon2id hashes in the PHC string
+//! format, which carries the salt and parameters along with the hash. The
+//! user models and the token handlers share the functions below
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::database::DatabaseType;
+
+use crate::dependency::Dependency;
+use crate::filesystem::FileSystem;
+use crate::generators::FieldType;
+use crate::migrate::created_table;
+use crate::writers::cargo::merge_dependency;
+
+/// The column of the users table holding the hash
+pub const PASSWORD_HASH_COLUMN: &str = "password_hash";
+
+/// The column the upstream schema stores the password in
+const PASSWORD_COLUMN: &str = "password";
+
+/// Hashes and verifies passwords in the generated code
+pub const PASSWORD_FUNCTIONS: &str = r#"/// Hashes `password` with Argon2id and a random salt, as a PHC string
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    use argon2::password_hash::rand_core::OsRng;
+    use argon2::password_hash::{PasswordHasher, SaltString};
+
+    let salt = SaltString::generate(&mut OsRng);
+    argon2::Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+}
+
+/// Whether `password` matches `password_hash`, a hash of `hash_password`
+/// The comparison takes the same time wherever the passwords differ
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+
+    PasswordHash::new(password_hash).is_ok_and(|hash| {
+        argon2::Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+"#;
+
+/// Declares argon2 in the Cargo.toml at `cargo_toml`
+/// `std` brings the operating system's random number generator for salts
+pub fn add_password_dependencies(fs: &dyn FileSystem, cargo_toml: &Path) -> io::Result<()> {
+    merge_dependency(
+        fs,
+        cargo_toml,
+        &Dependency {
+            name: "argon2".to_string(),
+            version: "0.5".to_string(),
+            features: vec!["std".to_string()],
+        },
+    )
+}
+
+/// The definition of the hash column in `database_type`
+/// PHC strings of the default Argon2id parameters are 97 characters long,
+/// 255 leaves room for stronger parameters
+pub fn password_hash_column_sql(database_type: &DatabaseType) -> String {
+    format!(
+        "{PASSWORD_HASH_COLUMN} {} NOT NULL",
+        FieldType::String.sql_type(database_type)
+    )
+}
+
+/// Replaces the password column of the `CREATE TABLE users` statement
+/// among `statements` with the hash column
+/// The admin user the upstream schema inserts into the password column is
+/// left out, its bcrypt hash can't be verified and its password is `admin`
+/// Other statements are left alone, as is a users table without one
+pub fn hash_password_column(statements: Vec<String>, database_type: &DatabaseType) -> Vec<String> {
+    statements
+        .into_iter()
+        .filter(|statement| !inserts_password(statement))
+        .map(|statement| {
+            let is_users =
+                created_table(&statement).is_some_and(|table| table.eq_ignore_ascii_case("users"));
+            if !is_users {
+                return statement;
+            }
+            replace_password_column(&statement, &password_hash_column_sql(database_type))
+                .unwrap_or(statement)
+        })
+        .collect()
+}
+
+/// Whether `statement` inserts a row with a password into the users table
+fn inserts_password(statement: &str) -> bool {
+    let mut words = statement.split_whitespace();
+    let is_insert = words
+        .next()
+        .is_some_and(|word| word.eq_ignore_ascii_case("INSERT"));
+    let Some(table) = words
+        .skip_while(|word| !word.eq_ignore_ascii_case("INTO"))
+        .nth(1)
+    else {
+        return false;
+    };
+    let table = table.split('(').next().unwrap_or(table);
+    if !is_insert
+        || !table
+            .trim_matches(|c| c == '"' || c == '`')
+            .eq_ignore_ascii_case("users")
+    {
+        return false;
+    }
+
+    let columns = statement
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map_or("", |(columns, _)| columns);
+    columns
+        .split(',')
+        .any(|column| column.trim().trim_matches(|c| c == '"' || c == '`') == PASSWORD_COLUMN)
+}
+
+/// `statement` with the definition of the password column replaced by
+/// `definition`, `None` if it defines no such column
+/// A column definition starts after the opening parenthesis or a comma and
+/// runs to the next comma or the closing parenthesis
+fn replace_password_column(statement: &str, definition: &str) -> Option<String> {
+    let columns_start = statement.find('(')? + 1;
+    let columns_end = statement.rfind(')')?;
+
+    let mut start = columns_start;
+    while start < columns_end {
+        let end = statement[start..columns_end]
+            .find(',')
+            .map_or(columns_end, |comma| start + comma);
+        let column = &statement[start..end];
+        let name = column.split_whitespace().next().unwrap_or_default();
+        if name.trim_matches(|c| c == '"' || c == '`') == PASSWORD_COLUMN {
+            let indent = &column[..column.len() - column.trim_start().len()];
+            let trailing = &column[column.trim_end().len()..];
+            return Some(format!(
+                "{}{indent}{definition}{trailing}{}",
+                &statement[..start],
+                &statement[end..]
+            ));
+        }
+        start = end + 1;
+    }
+    None
+}
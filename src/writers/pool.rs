@@ -0,0 +1,103 @@
+//! Writer for the database pool module of the generated application
+
+use std::io;
+
+use rustyroad::database::{Database, DatabaseType};
+use rustyroad::Project;
+
+use crate::database::PoolSettings;
+use crate::filesystem::FileSystem;
+use crate::layout::pool_module;
+
+/// Whether the application talks to its database through a sqlx pool
+/// MongoDB projects use the driver's own client instead
+pub fn uses_pool(database_data: &Database) -> bool {
+    !matches!(database_data.database_type, DatabaseType::Mongo)
+}
+
+/// Writes src/pool.rs, which connects a sqlx pool configured with `pool` to
+/// the database at `DATABASE_URL`, taken from the settings of src/config.rs
+/// when `config_module` is set
+pub fn write_to_pool_module(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+    pool: &PoolSettings,
+    config_module: bool,
+) -> io::Result<()> {
+    let contents =
+        pool_source(&database_data.database_type, pool, config_module).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "MongoDB projects don't use a sqlx pool",
+            )
+        })?;
+
+    fs.write(&pool_module(project), contents.as_bytes())
+}
+
+/// The source of src/pool.rs, `None` for MongoDB
+/// Besides `connect` for main.rs it offers `shared`, a pool that connects on
+/// first use, for generated code that has no access to the app state
+/// With `config_module` the URL comes from the settings of src/config.rs
+pub fn pool_source(
+    database_type: &DatabaseType,
+    pool: &PoolSettings,
+    config_module: bool,
+) -> Option<String> {
+    let (driver, pool_type, options_type) = match database_type {
+        DatabaseType::Sqlite => ("sqlite", "SqlitePool", "SqlitePoolOptions"),
+        DatabaseType::Postgres => ("postgres", "PgPool", "PgPoolOptions"),
+        DatabaseType::Mysql => ("mysql", "MySqlPool", "MySqlPoolOptions"),
+        DatabaseType::Mongo => return None,
+    };
+
+    let PoolSettings {
+        max_connections,
+        acquire_timeout,
+        idle_timeout,
+    } = pool;
+    let database_url = if config_module {
+        "fn database_url() -> String {\n    super::config::Settings::get().database_url.clone()\n}"
+    } else {
+        "/// Loads .env first, main.rs may not have done so\nfn database_url() -> String {\n    dotenvy::dotenv().ok();\n    std::env::var(\"DATABASE_URL\").expect(\"DATABASE_URL must be set\")\n}"
+    };
+
+    Some(format!(
+        r#"//! The database connection pool
+//! The settings come from the [database.pool] section of rustyroad.toml
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use sqlx::{driver}::{options_type};
+
+/// The pool shared by every request
+pub type Pool = sqlx::{pool_type};
+
+/// Connects the pool to the database at `DATABASE_URL`
+pub async fn connect() -> Result<Pool, sqlx::Error> {{
+    options().connect(&database_url()).await
+}}
+
+/// A pool for code without access to the app state, connected on first use
+pub fn shared() -> &'static Pool {{
+    static POOL: OnceLock<Pool> = OnceLock::new();
+    POOL.get_or_init(|| {{
+        options()
+            .connect_lazy(&database_url())
+            .expect("DATABASE_URL is not a valid connection URL")
+    }})
+}}
+
+fn options() -> {options_type} {{
+    {options_type}::new()
+        .max_connections({max_connections})
+        .acquire_timeout(Duration::from_secs({acquire_timeout}))
+        .idle_timeout(Duration::from_secs({idle_timeout}))
+}}
+
+{database_url}
+"#
+    ))
+}
@@ -0,0 +1,33 @@
+//! Writer for the README of a generated project
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::Project;
+
+use crate::filesystem::FileSystem;
+use crate::layout::package_name;
+
+/// Writes README.md with the commands to get the project running
+pub fn write_to_readme(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    let contents = format!(
+        "# {name}
+
+This project was created with RustyRoad.
+
+## Getting Started
+
+Start the server with
+
+```bash
+cargo run
+```
+
+The database settings are read from rustyroad.toml, apply new migrations
+with `rustyroad migrate`.
+",
+        name = package_name(project)
+    );
+
+    fs.write(Path::new(&project.readme), contents.as_bytes())
+}
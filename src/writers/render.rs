@@ -0,0 +1,72 @@
+//! Renders the project files that are written from Tera templates
+//! The built-in templates in `src/writers/templates` are compiled in. Like a
+//! view, each can be overridden as `project/<template>` in the override
+//! directories, e.g. `.rustyroad/templates/project/Dockerfile.tera`
+//!
+//! Every template sees the variables of `template_context`: `project_name`,
+//! `database_type`, `database_name`, `framework`, `css`, and the `host` and
+//! `port` the server listens on. A writer adds the ones only its file needs
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::database::Database;
+use rustyroad::Project;
+use tera::{Context, Tera};
+
+use crate::options::NewProjectOptions;
+use crate::template::template_context;
+use crate::views::Views;
+use crate::GenerateError;
+
+/// The built-in template `name`
+fn builtin(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "Dockerfile" => include_str!("templates/Dockerfile.tera"),
+        "dockerignore" => include_str!("templates/dockerignore.tera"),
+        "env" => include_str!("templates/env.tera"),
+        "env.example" => include_str!("templates/env.example.tera"),
+        "gitignore" => include_str!("templates/gitignore.tera"),
+        "justfile" => include_str!("templates/justfile.tera"),
+        "Makefile" => include_str!("templates/Makefile.tera"),
+        "seeds.sql" => include_str!("templates/seeds.sql.tera"),
+        _ => return None,
+    })
+}
+
+/// Renders the template `name` for the project generated with `options`
+/// `variables` are added to the shared context and win over its variables
+pub fn render(
+    name: &str,
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+    variables: Context,
+) -> io::Result<String> {
+    let mut context = template_context(project, database_data, options);
+    context.extend(variables);
+
+    let views = Views::for_project(Path::new(&project.name));
+    let overridden = views
+        .render_override(&format!("project/{name}"), &context)
+        .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+    if let Some(contents) = overridden {
+        return Ok(contents);
+    }
+
+    let template = builtin(name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("there is no built-in template `{name}`"),
+        )
+    })?;
+    Tera::one_off(template, &context, false).map_err(|source| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            GenerateError::TemplateRender {
+                path: Path::new("templates").join(format!("{name}.tera")),
+                source,
+            },
+        )
+    })
+}
@@ -0,0 +1,19 @@
+//! Writer for the JavaScript entry point of a generated project
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::Project;
+
+use crate::filesystem::FileSystem;
+use crate::layout::package_name;
+
+/// Writes index.js, which base.html loads on every page
+pub fn write_to_index_js(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    let contents = format!(
+        "// Scripts shared by every page of {name}\nconsole.log(\"Welcome to {name}\");\n",
+        name = package_name(project)
+    );
+
+    fs.write(Path::new(&project.index_js), contents.as_bytes())
+}
@@ -0,0 +1,37 @@
+//! Writer for the seeds file run by `rustyroad seed`
+
+use std::io;
+
+use rustyroad::database::{Database, DatabaseType};
+use rustyroad::Project;
+use tera::Context;
+
+use crate::filesystem::FileSystem;
+use crate::layout::project_file;
+use crate::options::NewProjectOptions;
+use crate::seed::SEEDS_FILE;
+use crate::writers::render::render;
+
+/// Writes seeds.sql with an example insert in the project's dialect
+/// The example table carries the table prefix of the project
+pub fn write_to_seeds(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> io::Result<()> {
+    if matches!(database_data.database_type, DatabaseType::Mongo) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "seeds are not supported for MongoDB projects",
+        ));
+    }
+
+    // The template picks the dialect's way to skip rows that already exist,
+    // which keeps the seeds safe to run more than once
+    let mut variables = Context::new();
+    variables.insert("posts", &options.tables.table("posts"));
+    let contents = render(SEEDS_FILE, project, database_data, options, variables)?;
+
+    fs.write(&project_file(project, SEEDS_FILE), contents.as_bytes())
+}
@@ -0,0 +1,135 @@
+//! Writers for the session store of the Actix login
+//! Sessions are kept in a signed cookie by default. With `--session-store
+//! redis` the session middleware keeps them in the Redis server at
+//! `REDIS_URL` instead, and the session cookie is signed with
+//! `SESSION_SECRET` so every instance of the app accepts it
+
+use std::io;
+use std::path::Path;
+
+use crate::auth::AuthScheme;
+use crate::dependency::Dependency;
+use crate::filesystem::FileSystem;
+use crate::framework::Framework;
+use crate::options::NewProjectOptions;
+use crate::session::SessionStore;
+use crate::writers::cargo::merge_dependency;
+
+/// Whether a project generated with `options` has the session login, the
+/// only part of a project that keeps sessions
+pub fn uses_sessions(options: &NewProjectOptions) -> bool {
+    !options.no_frontend
+        && options.framework == Framework::Actix
+        && options.auth == AuthScheme::Session
+}
+
+/// Whether the sessions of a project generated with `options` are kept in Redis
+pub fn uses_redis_sessions(options: &NewProjectOptions) -> bool {
+    uses_sessions(options) && options.session_store == SessionStore::Redis
+}
+
+/// Declares the Redis backend of actix-session in the Cargo.toml at `cargo_toml`
+pub fn add_redis_session_dependencies(fs: &dyn FileSystem, cargo_toml: &Path) -> io::Result<()> {
+    merge_dependency(
+        fs,
+        cargo_toml,
+        &Dependency {
+            name: "actix-session".to_string(),
+            version: "0.7".to_string(),
+            features: vec!["redis-rs-session".to_string()],
+        },
+    )
+}
+
+/// `contents` of the main.rs at `main_rs` with the app wrapped in a session
+/// middleware keeping sessions in a cookie, unless it has one already
+/// The key signing the cookie is generated at startup and shared by every
+/// worker, so sessions end when the server restarts
+pub(crate) fn add_session_middleware(contents: &str, main_rs: &Path) -> io::Result<String> {
+    if contents.contains("SessionMiddleware") {
+        return Ok(contents.to_string());
+    }
+    let invalid = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} doesn't {what}", main_rs.display()),
+        )
+    };
+
+    let mut contents = contents.to_string();
+    let app = contents
+        .find("App::new()")
+        .ok_or_else(|| invalid("create an `App`"))?
+        + "App::new()".len();
+    contents.insert_str(
+        app,
+        "\n            .wrap(SessionMiddleware::new(\n                CookieSessionStore::default(),\n                session_key.clone(),\n            ))",
+    );
+
+    let server = contents
+        .find("HttpServer::new(")
+        .ok_or_else(|| invalid("start an `HttpServer`"))?;
+    contents.insert_str(server, "let session_key = Key::generate();\n    ");
+    contents = contents.replacen("HttpServer::new(||", "HttpServer::new(move ||", 1);
+    Ok(format!(
+        "use actix_session::storage::CookieSessionStore;\nuse actix_session::SessionMiddleware;\nuse actix_web::cookie::Key;\n{contents}"
+    ))
+}
+
+/// Makes the session middleware of the main.rs at `main_rs` keep sessions
+/// in Redis, adding the middleware if main.rs has none
+/// The Redis URL and the secret of a middleware added here come from the
+/// settings of src/config.rs with `config_module`, otherwise from the
+/// environment
+pub fn use_redis_sessions(
+    fs: &dyn FileSystem,
+    main_rs: &Path,
+    config_module: bool,
+) -> io::Result<()> {
+    let contents = fs.read_to_string(main_rs)?;
+    if contents.contains("RedisSessionStore") {
+        return Ok(());
+    }
+
+    let mut contents = add_session_middleware(&contents, main_rs)?;
+    if !contents.contains("CookieSessionStore::default()") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} doesn't keep sessions in a cookie store",
+                main_rs.display()
+            ),
+        ));
+    }
+    let (redis_url, secret) = if config_module {
+        ("&settings.redis_url", "settings.session_secret")
+    } else {
+        (
+            "&std::env::var(\"REDIS_URL\").expect(\"REDIS_URL must be set\")",
+            "std::env::var(\"SESSION_SECRET\").expect(\"SESSION_SECRET must be set\")",
+        )
+    };
+
+    contents = contents
+        .replace("CookieSessionStore::default()", "redis_store.clone()")
+        .replacen("use actix_session::storage::CookieSessionStore;\n", "", 1)
+        .replacen(
+            "let session_key = Key::generate();",
+            &format!("let session_key = Key::from({secret}.as_bytes());"),
+            1,
+        )
+        .replacen("HttpServer::new(||", "HttpServer::new(move ||", 1);
+    if let Some(server) = contents.find("HttpServer::new(") {
+        contents.insert_str(
+            server,
+            &format!(
+                "let redis_store = RedisSessionStore::new({redis_url})\n        .await\n        .expect(\"Failed to connect to Redis\");\n    "
+            ),
+        );
+    }
+
+    fs.write(
+        main_rs,
+        format!("use actix_session::storage::RedisSessionStore;\n{contents}").as_bytes(),
+    )
+}
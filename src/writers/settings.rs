@@ -0,0 +1,249 @@
+//! Writers for the typed settings of the generated application
+//! src/config.rs holds a `Settings` struct read from the environment and
+//! .env through serde, which main.rs binds the server with and the pool
+//! connects with, instead of reading variables where they are needed
+
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::Database;
+use rustyroad::Project;
+
+use crate::filesystem::FileSystem;
+use crate::generators::database_type_name;
+use crate::layout::project_file;
+use crate::options::NewProjectOptions;
+use crate::writers::cargo::add_dependency;
+use crate::writers::declare_module;
+use crate::writers::session::uses_redis_sessions;
+
+/// The module of the generated application holding the settings
+pub const CONFIG_MODULE: &str = "config";
+
+/// The crates the settings are read with, besides dotenvy
+const CONFIG_DEPENDENCIES: [&str; 2] = [
+    "envy = \"0.4\"",
+    "serde = { version = \"1\", features = [\"derive\"] }",
+];
+
+/// The fallback of `init_tracing` when `RUST_LOG` isn't set, which the
+/// settings take over
+const TRACING_FILTER: &str = r#"tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"))"#;
+
+/// Whether a project generated with `options` gets src/config.rs
+pub fn uses_config_module(options: &NewProjectOptions) -> bool {
+    !options.no_config_module
+}
+
+/// The module holding the settings
+pub fn config_module_path(project: &Project) -> PathBuf {
+    project_file(project, &format!("src/{CONFIG_MODULE}.rs"))
+}
+
+/// The source of src/config.rs, documenting the database RustyRoad set up
+/// The server listens on the bind address of `options` unless `HOST` or
+/// `PORT` say otherwise
+pub fn config_source(database_data: &Database, options: &NewProjectOptions) -> String {
+    let database = database_type_name(&database_data.database_type);
+    let name = &database_data.name;
+    let host = options.bind_address().ip();
+    let port = options.bind_address().port();
+    let redis = if uses_redis_sessions(options) {
+        "    /// The Redis server sessions are kept in, `REDIS_URL`\n    pub redis_url: String,\n    /// The secret session cookies are signed with, at least 64 bytes,\n    /// `SESSION_SECRET`\n    pub session_secret: String,\n"
+    } else {
+        ""
+    };
+
+    format!(
+        r#"//! The settings of the application
+//! They are read once from the environment, after loading .env. Variables
+//! are matched by name, `DATABASE_URL` fills `database_url`
+
+use std::net::{{SocketAddr, ToSocketAddrs}};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+/// Everything the application is configured with
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {{
+    /// The host the server listens on, `HOST`
+    #[serde(default = "default_host")]
+    pub host: String,
+    /// The port the server listens on, `PORT`
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// The URL of the {database} database `{name}`, `DATABASE_URL`
+    pub database_url: String,
+    /// The log filter, `RUST_LOG`
+    #[serde(default = "default_log_level", rename = "rust_log")]
+    pub log_level: String,
+{redis}}}
+
+impl Settings {{
+    /// Reads the settings from the environment and .env
+    pub fn from_env() -> Result<Settings, envy::Error> {{
+        dotenvy::dotenv().ok();
+        envy::from_env()
+    }}
+
+    /// The settings, read on first use
+    /// Panics if they are missing or invalid, the application can't run
+    /// without them
+    pub fn get() -> &'static Settings {{
+        static SETTINGS: OnceLock<Settings> = OnceLock::new();
+        SETTINGS.get_or_init(|| {{
+            Settings::from_env().unwrap_or_else(|why| panic!("Invalid settings: {{why}}"))
+        }})
+    }}
+
+    /// The address the server listens on
+    pub fn address(&self) -> SocketAddr {{
+        (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .unwrap_or_else(|| panic!("HOST `{{}}` doesn't resolve to an address", self.host))
+    }}
+}}
+
+fn default_host() -> String {{
+    "{host}".to_string()
+}}
+
+fn default_port() -> u16 {{
+    {port}
+}}
+
+fn default_log_level() -> String {{
+    "info".to_string()
+}}
+"#
+    )
+}
+
+/// Writes src/config.rs with the settings
+pub fn write_to_config_module(
+    fs: &dyn FileSystem,
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+) -> io::Result<()> {
+    fs.write(
+        &config_module_path(project),
+        config_source(database_data, options).as_bytes(),
+    )
+}
+
+/// Declares the dependencies of the settings in the Cargo.toml at `cargo_toml`
+pub fn add_config_dependencies(fs: &dyn FileSystem, cargo_toml: &Path) -> io::Result<()> {
+    for dependency in CONFIG_DEPENDENCIES {
+        add_dependency(fs, cargo_toml, dependency)?;
+    }
+    Ok(())
+}
+
+/// Makes the main.rs at `main_rs` read the settings at startup and bind
+/// the server to their address
+/// Works on the main.rs of every writer, upstream ones included. The log
+/// filter of `add_tracing` falls back to the settings as well
+pub fn use_settings(fs: &dyn FileSystem, main_rs: &Path) -> io::Result<()> {
+    let contents = fs.read_to_string(main_rs)?;
+    if contents.contains("config::Settings") {
+        return Ok(());
+    }
+    let invalid = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} doesn't {what}", main_rs.display()),
+        )
+    };
+
+    // The first statement of `main`
+    let main_start = contents
+        .find("async fn main()")
+        .and_then(|position| {
+            contents[position..]
+                .find('{')
+                .map(|brace| position + brace + 1)
+        })
+        .ok_or_else(|| invalid("have an async `main`"))?;
+    let contents = format!(
+        "{}\n    let settings = config::Settings::get();\n{}",
+        &contents[..main_start],
+        contents[main_start..].trim_start_matches('\n')
+    );
+
+    let mut contents = replace_address(
+        &contents,
+        "settings.address()",
+        "let address = settings.address()",
+        "println!(\"Listening on http://{}\", settings.address());",
+    )
+    .ok_or_else(|| invalid("bind the server"))?;
+    // Axum no longer builds the `SocketAddr` itself
+    contents = contents.replacen("use std::net::SocketAddr;\n", "", 1);
+    contents = contents.replacen(
+        TRACING_FILTER,
+        "tracing_subscriber::EnvFilter::new(&config::Settings::get().log_level)",
+        1,
+    );
+    fs.write(main_rs, contents.as_bytes())?;
+
+    declare_module(fs, main_rs, CONFIG_MODULE).map_err(io::Error::other)
+}
+
+/// Makes the main.rs at `main_rs` bind the server to `address` instead of
+/// the fixed address of its writer, for projects without src/config.rs
+pub fn bind_to(fs: &dyn FileSystem, main_rs: &Path, address: SocketAddr) -> io::Result<()> {
+    let contents = fs.read_to_string(main_rs)?;
+    let contents = replace_address(
+        &contents,
+        &format!("\"{address}\""),
+        &format!(
+            "let address: SocketAddr = \"{address}\".parse().expect(\"Invalid bind address\")"
+        ),
+        &format!("println!(\"Listening on http://{address}\");"),
+    )
+    .ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} doesn't bind the server", main_rs.display()),
+        )
+    })?;
+    fs.write(main_rs, contents.as_bytes())
+}
+
+/// `contents` of a main.rs with the fixed address replaced, `None` if it
+/// has none
+/// Actix binds with `.bind(...)`, whose arguments become `actix`. Axum
+/// builds a `SocketAddr` first, its statement becomes `axum`. The startup
+/// message printing the fixed address becomes `message`
+fn replace_address(contents: &str, actix: &str, axum: &str, message: &str) -> Option<String> {
+    let mut contents = contents.to_string();
+    if let Some(bind) = contents.find(".bind((") {
+        let arguments = bind + ".bind(".len();
+        let end = arguments + contents[arguments..].find("))")? + 1;
+        contents.replace_range(arguments..end, actix);
+    } else if let Some(address) = contents.find("let address = SocketAddr::from(") {
+        let end = address + contents[address..].find(';')?;
+        contents.replace_range(address..end, axum);
+    } else {
+        return None;
+    }
+
+    let lines: Vec<String> = contents
+        .lines()
+        .map(|line| {
+            if line.contains("println!(") && line.contains("127.0.0.1") {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                format!("{indent}{message}")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    Some(lines.join("\n") + "\n")
+}
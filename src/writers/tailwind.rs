@@ -0,0 +1,168 @@
+//! Writers for the Tailwind configuration, stylesheet and npm package
+//! The content globs are derived from the project layout so Tailwind scans
+//! the directories the generator actually writes templates and scripts to
+
+use std::io;
+use std::path::Path;
+
+use rustyroad::Project;
+
+use crate::filesystem::FileSystem;
+use crate::layout::{package_name, relative_to_project};
+
+/// Where the Tailwind build is written, relative to the static directory
+pub const TAILWIND_OUTPUT: &str = "css/tailwind.css";
+
+/// The globs Tailwind scans for class names, relative to the project root
+/// The Tailwind CLI runs from the project root next to package.json
+pub fn content_globs(project: &Project) -> Vec<String> {
+    let templates = relative_to_project(project, &project.templates);
+    let scripts = Path::new(relative_to_project(project, &project.index_js))
+        .parent()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+
+    let mut globs = vec![format!("./{templates}/**/*.{{html,tera}}")];
+    // Scripts that live among the templates are already covered
+    if !scripts.is_empty() && !Path::new(&scripts).starts_with(templates) {
+        globs.push(format!("./{scripts}/**/*.js"));
+    }
+    globs
+}
+
+/// Writes tailwind.config.js with content paths matching the project layout
+pub fn write_to_tailwind_config(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    let content = content_globs(project)
+        .iter()
+        .map(|glob| format!("    \"{glob}\",\n"))
+        .collect::<String>();
+
+    let contents = format!(
+        r#"/** @type {{import('tailwindcss').Config}} */
+module.exports = {{
+  content: [
+{content}  ],
+  theme: {{
+    extend: {{}},
+  }},
+  plugins: [],
+}};
+"#
+    );
+
+    fs.write(Path::new(&project.tailwind_config), contents.as_bytes())
+}
+
+/// Writes the stylesheet Tailwind builds from
+pub fn write_to_tailwind_css(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    fs.write(
+        Path::new(&project.tailwind_css),
+        b"@tailwind base;\n@tailwind components;\n@tailwind utilities;\n",
+    )
+}
+
+/// Writes postcss.config.js, running Tailwind and Autoprefixer
+pub fn write_to_postcss_config(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    fs.write(
+        Path::new(&project.postcss_config),
+        b"module.exports = {
+  plugins: {
+    tailwindcss: {},
+    autoprefixer: {},
+  },
+};
+",
+    )
+}
+
+/// Writes package.json with the Tailwind dependencies and the scripts
+/// building the stylesheet into the static directory
+pub fn write_to_package_json(fs: &dyn FileSystem, project: &Project) -> io::Result<()> {
+    let input = relative_to_project(project, &project.tailwind_css);
+    let output = Path::new(relative_to_project(project, &project.static_dir)).join(TAILWIND_OUTPUT);
+    let build = format!("tailwindcss -i ./{input} -o ./{}", output.display());
+
+    let contents = format!(
+        r#"{{
+  "name": "{name}",
+  "version": "0.1.0",
+  "private": true,
+  "scripts": {{
+    "tailwind:dev": "{build} --watch",
+    "tailwind:build": "{build} --minify"
+  }},
+  "devDependencies": {{
+    "@tailwindcss/forms": "^0.5.3",
+    "autoprefixer": "^10.4.14",
+    "postcss": "^8.4.23",
+    "tailwindcss": "^3.3.2"
+  }}
+}}
+"#,
+        name = package_name(project)
+    );
+
+    fs.write(Path::new(&project.package_json), contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+    use crate::layout::project_at;
+
+    /// The globs of the `content` array of a tailwind.config.js
+    fn parse_content(config: &str) -> Vec<String> {
+        let start = config.find("content: [").expect("a content array") + "content: [".len();
+        let end = start + config[start..].find(']').expect("a closed content array");
+        config[start..end]
+            .lines()
+            .map(|line| {
+                line.trim()
+                    .trim_end_matches(',')
+                    .trim_matches('"')
+                    .to_string()
+            })
+            .filter(|glob| !glob.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn every_glob_covers_a_generated_file() {
+        let project = project_at(Path::new("blog"));
+        let fs = MemoryFileSystem::new();
+        fs.create_dir(Path::new(&project.name)).unwrap();
+        write_to_tailwind_config(&fs, &project).unwrap();
+        let globs = parse_content(&fs.contents(&project.tailwind_config).unwrap());
+
+        let generated = [&project.index_html, &project.base_html, &project.index_js];
+        assert_eq!(globs, content_globs(&project));
+        for glob in &globs {
+            let directory = glob.trim_start_matches("./").split("/**").next().unwrap();
+            assert!(
+                generated
+                    .iter()
+                    .any(|file| Path::new(relative_to_project(&project, file))
+                        .starts_with(directory)),
+                "{glob} matches no generated file"
+            );
+        }
+    }
+
+    #[test]
+    fn covers_templates_and_scripts() {
+        let project = project_at(Path::new("blog"));
+        let globs = content_globs(&project);
+
+        let covers = |file: &str, extension: &str| {
+            let file = Path::new(relative_to_project(&project, file));
+            globs.iter().any(|glob| {
+                let directory = glob.trim_start_matches("./").split("/**").next().unwrap();
+                file.starts_with(directory) && glob.contains(extension)
+            })
+        };
+        assert!(covers(&project.index_html, "tera"));
+        assert!(covers(&project.base_html, "tera"));
+        assert!(covers(&project.index_js, "js"));
+    }
+}
@@ -0,0 +1,112 @@
+//! Writer for the task file of a generated project
+//! A `justfile` or `Makefile` with the commands everyone needs to work on the
+//! project. Both load .env first, so the app, the tests and the RustyRoad
+//! commands see the same `DATABASE_URL`
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rustyroad::database::{Database, DatabaseType};
+use rustyroad::Project;
+use serde::Serialize;
+use tera::Context;
+
+use crate::css::CssFramework;
+use crate::layout::{project_file, relative_to_project};
+use crate::options::NewProjectOptions;
+use crate::tasks::TaskRunner;
+use crate::writers::render::render;
+use crate::writers::tailwind::TAILWIND_OUTPUT;
+
+/// A target of the task file
+#[derive(Serialize)]
+struct Task {
+    name: &'static str,
+    description: &'static str,
+    command: String,
+}
+
+/// The task file of `runner`
+pub fn tasks_path(project: &Project, runner: TaskRunner) -> PathBuf {
+    project_file(
+        project,
+        match runner {
+            TaskRunner::Just => "justfile",
+            TaskRunner::Make => "Makefile",
+        },
+    )
+}
+
+/// The source of the task file of `runner`, with a target for every
+/// command that applies to the enabled features
+pub fn tasks_source(
+    project: &Project,
+    database_data: &Database,
+    options: &NewProjectOptions,
+    runner: TaskRunner,
+) -> io::Result<String> {
+    let template = match runner {
+        TaskRunner::Just => "justfile",
+        TaskRunner::Make => "Makefile",
+    };
+
+    let mut variables = Context::new();
+    variables.insert("tasks", &tasks(project, database_data, options));
+    render(template, project, database_data, options, variables)
+}
+
+/// The targets of the project, the database ones need a SQL database and
+/// the CSS build the Tailwind frontend
+fn tasks(project: &Project, database_data: &Database, options: &NewProjectOptions) -> Vec<Task> {
+    let mut tasks = vec![
+        Task {
+            name: "run",
+            description: "Start the app",
+            command: "cargo run".to_string(),
+        },
+        Task {
+            name: "test",
+            description: "Run the tests",
+            command: "cargo test".to_string(),
+        },
+    ];
+
+    if !matches!(database_data.database_type, DatabaseType::Mongo) {
+        tasks.extend([
+            Task {
+                name: "migrate",
+                description: "Apply the pending migrations",
+                command: "rustyroad migrate".to_string(),
+            },
+            Task {
+                name: "seed",
+                description: "Load the development data in seeds.sql",
+                command: "rustyroad seed".to_string(),
+            },
+        ]);
+    }
+
+    if !options.no_frontend && options.css == CssFramework::Tailwind {
+        let output =
+            Path::new(relative_to_project(project, &project.static_dir)).join(TAILWIND_OUTPUT);
+        tasks.push(Task {
+            name: "css",
+            description: "Build the Tailwind stylesheet",
+            command: format!(
+                "npx tailwindcss -i {} -o {} --minify",
+                relative_to_project(project, &project.tailwind_css),
+                output.display()
+            ),
+        });
+    }
+
+    if !matches!(database_data.database_type, DatabaseType::Mongo) {
+        tasks.push(Task {
+            name: "db-reset",
+            description: "Drop the database and create it again with every migration applied",
+            command: "rustyroad db reset".to_string(),
+        });
+    }
+
+    tasks
+}
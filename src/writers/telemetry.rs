@@ -0,0 +1,118 @@
+//! Writers for the logging setup of the generated application
+//! main.rs initializes `tracing-subscriber`, filtered by `RUST_LOG`, and the
+//! server logs every request through the tracing middleware of its framework
+
+use std::io;
+use std::path::Path;
+
+use crate::dependency::Dependency;
+use crate::filesystem::FileSystem;
+use crate::framework::Framework;
+use crate::writers::cargo::{add_dependency, merge_dependency};
+
+/// The subscriber with the `RUST_LOG` filter, used by both frameworks
+const SUBSCRIBER_DEPENDENCIES: [&str; 2] = [
+    "tracing = \"0.1\"",
+    "tracing-subscriber = { version = \"0.3\", features = [\"env-filter\"] }",
+];
+
+/// The request logging middleware of Actix
+const TRACING_ACTIX_WEB_DEPENDENCY: &str = "tracing-actix-web = \"0.7\"";
+
+/// Sets up the subscriber, at info level unless `RUST_LOG` says otherwise
+const INIT_TRACING: &str = r#"
+/// Logs through `tracing`, at info level unless `RUST_LOG` says otherwise
+fn init_tracing() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+}
+"#;
+
+/// Declares the logging dependencies of `framework` in the Cargo.toml at
+/// `cargo_toml`
+/// Axum logs requests with the `trace` feature of the tower-http it
+/// already depends on
+pub fn add_tracing_dependencies(
+    fs: &dyn FileSystem,
+    cargo_toml: &Path,
+    framework: Framework,
+) -> io::Result<()> {
+    for dependency in SUBSCRIBER_DEPENDENCIES {
+        add_dependency(fs, cargo_toml, dependency)?;
+    }
+
+    match framework {
+        Framework::Actix => add_dependency(fs, cargo_toml, TRACING_ACTIX_WEB_DEPENDENCY),
+        Framework::Axum => merge_dependency(
+            fs,
+            cargo_toml,
+            &Dependency {
+                name: "tower-http".to_string(),
+                version: "0.4".to_string(),
+                features: vec!["trace".to_string()],
+            },
+        ),
+    }
+}
+
+/// Initializes tracing at the start of `main` in the main.rs at `main_rs`
+/// and wraps the app in the request logging middleware of `framework`
+/// Works on the main.rs of every writer, upstream ones included
+pub fn add_tracing(fs: &dyn FileSystem, main_rs: &Path, framework: Framework) -> io::Result<()> {
+    let contents = fs.read_to_string(main_rs)?;
+    if contents.contains("fn init_tracing()") {
+        return Ok(());
+    }
+    let invalid = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} doesn't {what}", main_rs.display()),
+        )
+    };
+
+    // The first statement of `main`
+    let main_start = contents
+        .find("async fn main()")
+        .and_then(|position| {
+            contents[position..]
+                .find('{')
+                .map(|brace| position + brace + 1)
+        })
+        .ok_or_else(|| invalid("have an async `main`"))?;
+    let mut contents = format!(
+        "{}\n    init_tracing();\n{}",
+        &contents[..main_start],
+        contents[main_start..].trim_start_matches('\n')
+    );
+
+    let import = match framework {
+        Framework::Actix => {
+            let position = contents
+                .find("App::new()")
+                .ok_or_else(|| invalid("create an `App`"))?
+                + "App::new()".len();
+            contents.insert_str(position, "\n            .wrap(TracingLogger::default())");
+            "use tracing_actix_web::TracingLogger;\n"
+        }
+        Framework::Axum => {
+            let router = contents
+                .find("let app = ")
+                .ok_or_else(|| invalid("build an `app` router"))?;
+            let position = contents[router..]
+                .find(';')
+                .map(|end| router + end)
+                .ok_or_else(|| invalid("build an `app` router"))?;
+            contents.insert_str(position, "\n        .layer(TraceLayer::new_for_http())");
+            "use tower_http::trace::TraceLayer;\n"
+        }
+    };
+
+    fs.write(
+        main_rs,
+        format!("{import}{contents}{INIT_TRACING}").as_bytes(),
+    )
+}
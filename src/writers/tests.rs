@@ -0,0 +1,208 @@
+//! Writers for the integration tests of the generated application
+//! The tests start the compiled app against a test database that gets the
+//! project's migrations, so it has the same schema as the real one
+
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+use rustyroad::database::{Database, DatabaseType};
+use rustyroad::Project;
+
+use crate::layout::{package_name, project_file};
+use crate::options::NewProjectOptions;
+use crate::writers::pool::uses_pool;
+use crate::writers::settings::uses_config_module;
+
+/// The runtime the tests run on, Actix projects don't depend on Tokio
+pub const TOKIO_DEV_DEPENDENCY: &str =
+    "tokio = { version = \"1\", features = [\"macros\", \"rt-multi-thread\"] }";
+
+/// Whether a project gets integration tests, they need a sqlx pool to
+/// migrate the test database with
+pub fn writes_tests(database_data: &Database, options: &NewProjectOptions) -> bool {
+    !options.no_tests && uses_pool(database_data)
+}
+
+/// The integration test hitting the index route
+pub fn app_test_path(project: &Project) -> PathBuf {
+    project_file(project, "tests/app.rs")
+}
+
+/// The helpers shared by the integration tests
+pub fn common_module_path(project: &Project) -> PathBuf {
+    project_file(project, "tests/common/mod.rs")
+}
+
+/// The source of tests/app.rs
+pub fn app_test_source() -> &'static str {
+    r#"//! Starts the app against a test database and requests its pages
+
+mod common;
+
+#[tokio::test]
+async fn index_responds() {
+    let app = common::spawn_app().await;
+
+    let response = app.get("/");
+    assert!(
+        response.starts_with("HTTP/1.1 200"),
+        "unexpected response: {response}"
+    );
+}
+"#
+}
+
+/// The source of tests/common/mod.rs, `None` for MongoDB
+/// The test database is connected through src/pool.rs like the app's own,
+/// along with src/config.rs it reads the database URL from
+pub fn common_module_source(
+    project: &Project,
+    database_type: &DatabaseType,
+    options: &NewProjectOptions,
+) -> Option<String> {
+    let test_database_url = match database_type {
+        DatabaseType::Sqlite => format!(
+            r#"/// A fresh SQLite file in the temp directory
+fn test_database_url() -> String {{
+    let path = std::env::temp_dir().join(format!("{binary}-test-{{}}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    format!("sqlite://{{}}?mode=rwc", path.display())
+}}"#,
+            binary = package_name(project)
+        ),
+        DatabaseType::Postgres | DatabaseType::Mysql => {
+            r#"/// An empty database set aside for the tests, never the development one
+fn test_database_url() -> String {
+    std::env::var("TEST_DATABASE_URL")
+        .expect("Set TEST_DATABASE_URL to an empty database to run the tests")
+}"#
+            .to_string()
+        }
+        DatabaseType::Mongo => return None,
+    };
+    let config_module = if uses_config_module(options) {
+        "#[allow(dead_code)]\n#[path = \"../../src/config.rs\"]\nmod config;\n\n"
+    } else {
+        ""
+    };
+    // A server listening on every interface is reached through loopback
+    let mut address = options.bind_address();
+    if address.ip().is_unspecified() {
+        address.set_ip(Ipv4Addr::LOCALHOST.into());
+    }
+
+    Some(format!(
+        r#"//! Helpers shared by the integration tests
+//! The app runs as a child process against a test database that gets every
+//! migration, so it has the same schema as the development database
+
+use std::io::{{Read, Write}};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::{{Child, Command, Stdio}};
+use std::thread;
+use std::time::{{Duration, Instant}};
+
+use sqlx::Executor;
+
+{config_module}#[allow(dead_code)]
+#[path = "../../src/pool.rs"]
+mod pool;
+
+/// Where the app listens, see src/main.rs
+const ADDRESS: &str = "{address}";
+
+/// How long the app may take to start listening
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The running app, stopped when dropped
+pub struct TestApp {{
+    process: Child,
+}}
+
+impl TestApp {{
+    /// Sends a GET request for `path` and returns the raw response
+    pub fn get(&self, path: &str) -> String {{
+        let mut stream = TcpStream::connect(ADDRESS).expect("Failed to connect to the app");
+        write!(
+            stream,
+            "GET {{path}} HTTP/1.1\r\nHost: {{ADDRESS}}\r\nConnection: close\r\n\r\n"
+        )
+        .expect("Failed to send the request");
+
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("Failed to read the response");
+        response
+    }}
+
+    fn wait_until_listening(&mut self) {{
+        let started = Instant::now();
+        while TcpStream::connect(ADDRESS).is_err() {{
+            if let Ok(Some(status)) = self.process.try_wait() {{
+                panic!("The app exited with {{status}} before listening");
+            }}
+            if started.elapsed() > STARTUP_TIMEOUT {{
+                panic!("The app didn't listen on {{ADDRESS}} within {{STARTUP_TIMEOUT:?}}");
+            }}
+            thread::sleep(Duration::from_millis(100));
+        }}
+    }}
+}}
+
+impl Drop for TestApp {{
+    fn drop(&mut self) {{
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }}
+}}
+
+/// Migrates a test database and starts the app against it
+pub async fn spawn_app() -> TestApp {{
+    let database_url = test_database_url();
+    std::env::set_var("DATABASE_URL", &database_url);
+
+    let pool = pool::connect()
+        .await
+        .expect("Failed to connect to the test database");
+    run_migrations(&pool).await;
+    pool.close().await;
+
+    let process = Command::new(env!("CARGO_BIN_EXE_{binary}"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("DATABASE_URL", &database_url)
+        .stdout(Stdio::null())
+        .spawn()
+        .expect("Failed to start the app");
+
+    let mut app = TestApp {{ process }};
+    app.wait_until_listening();
+    app
+}}
+
+{test_database_url}
+
+/// Applies the up.sql of every migration in order, like `rustyroad migrate`
+async fn run_migrations(pool: &pool::Pool) {{
+    let migrations = Path::new(env!("CARGO_MANIFEST_DIR")).join("{migrations_dir}");
+    let mut versions: Vec<_> = std::fs::read_dir(&migrations)
+        .expect("Failed to read the migrations")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.join("up.sql").is_file())
+        .collect();
+    versions.sort();
+
+    for version in versions {{
+        let sql = std::fs::read_to_string(version.join("up.sql"))
+            .expect("Failed to read a migration");
+        pool.execute(sql.as_str())
+            .await
+            .unwrap_or_else(|why| panic!("Failed to apply {{}}: {{why}}", version.display()));
+    }}
+}}
+"#,
+        binary = package_name(project),
+        migrations_dir = options.migrations_dir(),
+    ))
+}